@@ -0,0 +1,191 @@
+//! The structural changes of the machines: the states, events, regions and transitions that were
+//! added or removed. The generated transition names aren't compared, as they depend on the order
+//! of the declarations.
+
+use std::fmt::Write;
+
+use finny::{FsmMeta, FsmTransitionEventMeta, FsmTransitionKindMeta, FsmTransitionMeta};
+
+use crate::{meta::build_metas, scan::FsmSource};
+
+/// The machine's structure, one item per line.
+fn structure(meta: &FsmMeta) -> Vec<String> {
+    let mut lines = vec![];
+
+    for state in meta.states {
+        match state.sub_machine {
+            Some(_) => lines.push(format!("sub-machine {}", state.name)),
+            None => lines.push(format!("state {}", state.name))
+        }
+    }
+    for event in meta.events {
+        lines.push(format!("event {}", event));
+    }
+    for region in meta.regions {
+        lines.push(format!("region {}: initial {}", region.name, region.initial_states.join(", ")));
+    }
+    for t in meta.transitions {
+        lines.push(format!("transition {}: {}", meta.regions[t.region].name, transition(t)));
+    }
+
+    lines
+}
+
+fn transition(t: &FsmTransitionMeta) -> String {
+    let mut s = String::new();
+    let from = t.from.unwrap_or("[*]");
+    let to = t.to.unwrap_or("[*]");
+
+    match t.kind {
+        FsmTransitionKindMeta::Normal => write!(s, "{} -> {}", from, to),
+        FsmTransitionKindMeta::SelfTransition => write!(s, "{} -> {} (self)", from, to),
+        FsmTransitionKindMeta::Internal => write!(s, "{} (internal)", from)
+    }.unwrap();
+
+    match t.event {
+        FsmTransitionEventMeta::Start => (),
+        FsmTransitionEventMeta::Stop => s.push_str(" on stop"),
+        FsmTransitionEventMeta::Event(ev) => write!(s, " on {}", ev).unwrap()
+    }
+
+    if t.has_guard {
+        s.push_str(" [guard]");
+    }
+    if t.has_action {
+        s.push_str(" / action");
+    }
+
+    s
+}
+
+fn same_machine(a: &FsmSource, b: &FsmSource) -> bool {
+    a.file == b.file && a.name == b.name
+}
+
+/// The changes from the old to the new sources, by file and machine name.
+pub fn diff_sources(old: &[FsmSource], new: &[FsmSource]) -> Result<String, String> {
+    let old_metas = build_metas(old)?;
+    let new_metas = build_metas(new)?;
+    let mut out = String::new();
+
+    for source in new.iter().filter(|s| s.fsm.is_err()) {
+        if let Err(ref e) = source.fsm {
+            writeln!(out, "! {} ({}): invalid: {}", source.name, source.file.display(), e).unwrap();
+        }
+    }
+
+    for (old_source, _) in &old_metas {
+        if !new.iter().any(|s| same_machine(s, old_source)) {
+            writeln!(out, "- {} ({})", old_source.name, old_source.file.display()).unwrap();
+        }
+    }
+
+    for (new_source, new_meta) in &new_metas {
+        let new_lines = structure(new_meta);
+        match old_metas.iter().find(|(s, _)| same_machine(s, new_source)) {
+            None => {
+                writeln!(out, "+ {} ({})", new_source.name, new_source.file.display()).unwrap();
+                for line in &new_lines {
+                    writeln!(out, "    + {}", line).unwrap();
+                }
+            },
+            Some((_, old_meta)) => {
+                let old_lines = structure(old_meta);
+                let removed: Vec<_> = old_lines.iter().filter(|l| !new_lines.contains(l)).collect();
+                let added: Vec<_> = new_lines.iter().filter(|l| !old_lines.contains(l)).collect();
+
+                if !removed.is_empty() || !added.is_empty() {
+                    writeln!(out, "~ {} ({})", new_source.name, new_source.file.display()).unwrap();
+                    for line in removed {
+                        writeln!(out, "    - {}", line).unwrap();
+                    }
+                    for line in added {
+                        writeln!(out, "    + {}", line).unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    if out.is_empty() {
+        out.push_str("No structural changes.\n");
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::scan::find_machines;
+
+    fn machines(src: &str) -> Vec<FsmSource> {
+        let mut sources = vec![];
+        find_machines(Path::new("src/lib.rs"), src, &mut sources);
+        sources
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = machines(r#"
+            #[finny_fsm]
+            fn build_fsm(mut fsm: FsmBuilder<Door, Context>) -> BuiltFsm {
+                fsm.initial_state::<Closed>();
+                fsm.state::<Closed>().on_event::<Open>().transition_to::<Opened>();
+                fsm.state::<Opened>().on_event::<Close>().transition_to::<Closed>();
+                fsm.build()
+            }
+
+            #[finny_fsm]
+            fn build_removed(mut fsm: FsmBuilder<Removed, Context>) -> BuiltFsm {
+                fsm.initial_state::<Closed>();
+                fsm.state::<Closed>().on_event::<Open>().self_transition();
+                fsm.build()
+            }
+        "#);
+
+        let new = machines(r#"
+            mod door {
+                #[finny::finny_fsm]
+                fn build_fsm(mut fsm: FsmBuilder<Door, Context>) -> BuiltFsm {
+                    fsm.initial_state::<Closed>();
+                    fsm.state::<Closed>().on_event::<Open>().transition_to::<Opened>().guard(|_ev, ctx, _states| ctx.unlocked);
+                    fsm.state::<Opened>().on_event::<Close>().transition_to::<Closed>();
+                    fsm.state::<Opened>().on_event::<Knock>().internal_transition();
+                    fsm.build()
+                }
+            }
+        "#);
+
+        assert_eq!(vec!["Door"], new.iter().map(|s| s.name.as_str()).collect::<Vec<_>>());
+        assert_eq!(4, new[0].line);
+
+        let expected = "\
+- Removed (src/lib.rs)
+~ Door (src/lib.rs)
+    - transition Region0: Closed -> Opened on Open
+    + event Knock
+    + transition Region0: Closed -> Opened on Open [guard]
+    + transition Region0: Opened (internal) on Knock
+";
+        assert_eq!(expected, diff_sources(&old, &new).unwrap());
+        assert_eq!("No structural changes.\n", diff_sources(&new, &new).unwrap());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let new = machines(r#"
+            #[finny_fsm]
+            fn build_fsm(mut fsm: FsmBuilder<Door, Context>) -> BuiltFsm {
+                fsm.state::<Closed>();
+                fsm.build()
+            }
+        "#);
+
+        assert_eq!("build_fsm", new[0].name);
+        assert!(new[0].fsm.is_err());
+        assert!(diff_sources(&[], &new).unwrap().starts_with("! build_fsm (src/lib.rs): invalid: "));
+    }
+}
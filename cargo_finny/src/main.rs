@@ -0,0 +1,139 @@
+//! `cargo finny` inspects the state machines declared with `#[finny_fsm]` in a crate's sources,
+//! without compiling the crate. The builder functions go through the parser and validation of
+//! `finny_model`, as in the procedural macro.
+//!
+//! ```text
+//! cargo finny list
+//! cargo finny validate
+//! cargo finny graph --format mermaid [MACHINE]
+//! cargo finny diff main
+//! ```
+
+extern crate finny;
+extern crate finny_model;
+extern crate proc_macro2;
+extern crate quote;
+extern crate syn;
+
+use std::path::PathBuf;
+use std::process::exit;
+
+mod diff;
+mod meta;
+mod scan;
+
+const USAGE: &str = "Inspects the finny state machines of a crate.
+
+USAGE:
+    cargo finny [--path <DIR>] <COMMAND>
+
+COMMANDS:
+    list                                  List the machines and their size
+    validate                              Parse and validate the machines, fails on errors
+    graph [--format dot|mermaid] [NAME]   Render the transition graph of the machines
+    diff <REV>                            The structural changes of the machines since a git revision
+
+OPTIONS:
+    --path <DIR>    The crate's directory, defaults to the current one";
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    // invoked by cargo as `cargo-finny finny ...`
+    if args.first().map(|a| a.as_str()) == Some("finny") {
+        args.remove(0);
+    }
+
+    match run(args) {
+        Ok(true) => (),
+        Ok(false) => exit(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            exit(2);
+        }
+    }
+}
+
+/// Runs the command, returns `false` if the machines are invalid.
+fn run(args: Vec<String>) -> Result<bool, String> {
+    let mut root = PathBuf::from(".");
+    let mut format = "dot".to_string();
+    let mut positional = vec![];
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--path" => { root = PathBuf::from(args.next().ok_or("Missing the value of '--path'.")?); },
+            "--format" => { format = args.next().ok_or("Missing the value of '--format'.")?; },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(true);
+            },
+            _ => positional.push(arg)
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let command = positional.next().ok_or_else(|| USAGE.to_string())?;
+
+    match command.as_str() {
+        "list" => {
+            let sources = scan::scan_dir(&root).map_err(|e| e.to_string())?;
+            for source in &sources {
+                match source.fsm {
+                    Ok(ref fsm) => {
+                        println!("{} ({}:{}): {} states, {} events, {} regions, {} transitions",
+                            source.name, source.file.display(), source.line,
+                            fsm.fsm.states.len(), fsm.fsm.events.len(), fsm.fsm.regions.len(),
+                            fsm.fsm.regions.iter().map(|r| r.transitions.len()).sum::<usize>());
+                    },
+                    Err(_) => println!("{} ({}:{}): invalid", source.name, source.file.display(), source.line)
+                }
+            }
+            Ok(true)
+        },
+        "validate" => {
+            let sources = scan::scan_dir(&root).map_err(|e| e.to_string())?;
+            let mut valid = true;
+            for source in &sources {
+                match source.fsm {
+                    Ok(_) => println!("{}: ok", source.name),
+                    Err(ref e) => {
+                        let start = e.span().start();
+                        println!("{}: error at {}:{}:{}: {}", source.name, source.file.display(), start.line, start.column + 1, e);
+                        valid = false;
+                    }
+                }
+            }
+            Ok(valid)
+        },
+        "graph" => {
+            let name = positional.next();
+            let sources = scan::scan_dir(&root).map_err(|e| e.to_string())?;
+            let metas = meta::build_metas(&sources)?;
+
+            let mut found = false;
+            for (_, m) in metas.iter().filter(|(_, m)| name.as_deref().map_or(true, |n| n == m.name)) {
+                found = true;
+                let s = match format.as_str() {
+                    "dot" => finny::render::to_dot(m),
+                    "mermaid" => finny::render::to_mermaid(m),
+                    _ => { return Err(format!("Unsupported format '{}', expected 'dot' or 'mermaid'.", format)); }
+                };
+                print!("{}", s);
+            }
+
+            match name {
+                Some(name) if !found => Err(format!("The machine '{}' wasn't found.", name)),
+                _ => Ok(true)
+            }
+        },
+        "diff" => {
+            let rev = positional.next().ok_or("Missing the git revision to compare with.")?;
+            let old = scan::scan_git(&root, &rev)?;
+            let new = scan::scan_dir(&root).map_err(|e| e.to_string())?;
+            print!("{}", diff::diff_sources(&old, &new)?);
+            Ok(true)
+        },
+        _ => Err(format!("Unknown command '{}'.\n\n{}", command, USAGE))
+    }
+}
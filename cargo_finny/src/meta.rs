@@ -0,0 +1,203 @@
+//! The static descriptions of the parsed machines, as generated by the procedural macro. They are
+//! leaked, which is fine for a short-lived command.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use finny::{FsmMeta, FsmRegionMeta, FsmStateMeta, FsmTransitionEventMeta, FsmTransitionKindMeta, FsmTransitionMeta};
+
+use finny_model::{parse::{FsmStateKind, FsmTransitionEndpoints, FsmTransitionEvent, FsmTransitionKind, FsmTransitionState}, utils::type_name};
+
+use crate::scan::FsmSource;
+
+fn leak_str(s: String) -> &'static str {
+    Box::leak(s.into_boxed_str())
+}
+
+fn leak_slice<T>(v: Vec<T>) -> &'static [T] {
+    Box::leak(v.into_boxed_slice())
+}
+
+/// The descriptions of the valid machines, in the order of their declaration. Sub-machines are
+/// linked when they are declared within the same crate, preferring the ones in the same file. Fails
+/// when a machine contains itself through its sub-machines.
+pub fn build_metas(sources: &[FsmSource]) -> Result<Vec<(&FsmSource, &'static FsmMeta)>, String> {
+    let mut metas = HashMap::new();
+    sources.iter()
+        .filter(|s| s.fsm.is_ok())
+        .map(|s| Ok((s, build_meta(s, sources, &mut metas, &mut vec![])?)))
+        .collect()
+}
+
+type MetaKey = (PathBuf, String);
+
+fn build_meta(source: &FsmSource, sources: &[FsmSource], metas: &mut HashMap<MetaKey, &'static FsmMeta>, building: &mut Vec<MetaKey>) -> Result<&'static FsmMeta, String> {
+    let key = (source.file.clone(), source.name.clone());
+    if let Some(meta) = metas.get(&key) {
+        return Ok(meta);
+    }
+    if let Some(pos) = building.iter().position(|k| *k == key) {
+        let cycle: Vec<_> = building[pos..].iter().chain(Some(&key))
+            .map(|(file, name)| format!("{} ({})", name, file.display()))
+            .collect();
+        return Err(format!("The machine '{}' contains itself through its sub-machines: {}", source.name, cycle.join(" -> ")));
+    }
+    let fsm = match source.fsm {
+        Ok(ref fsm) => fsm,
+        Err(_) => unreachable!("Only the valid machines are described.")
+    };
+    building.push(key.clone());
+
+    let name_of = |ty: &syn::Type| leak_str(type_name(ty));
+    let state_name_of = |st: &FsmTransitionState| st.state_name().map(leak_str);
+
+    let mut states = vec![];
+    for (ty, state) in &fsm.fsm.states {
+        let name = name_of(ty);
+        let sub_machine = match state.kind {
+            FsmStateKind::SubMachine(_) => sources.iter()
+                .filter(|s| s.name == name && s.fsm.is_ok())
+                .min_by_key(|s| s.file != source.file)
+                .map(|sub| build_meta(sub, sources, metas, building))
+                .transpose()?,
+            FsmStateKind::Normal => None
+        };
+        states.push(FsmStateMeta { name, sub_machine });
+    }
+
+    let events = fsm.fsm.events.keys().map(name_of).collect();
+
+    let regions = fsm.fsm.regions.iter().map(|region| {
+        FsmRegionMeta {
+            name: leak_str(region.name.clone()),
+            initial_states: leak_slice(region.initial_states.iter().map(name_of).collect()),
+            states: leak_slice(region.states.iter().map(name_of).collect())
+        }
+    }).collect();
+
+    let mut transitions = vec![];
+    for region in &fsm.fsm.regions {
+        for transition in &region.transitions {
+            let FsmTransitionEndpoints { kind, event, from, to, action } = transition.endpoints();
+            let kind = match kind {
+                FsmTransitionKind::Internal => FsmTransitionKindMeta::Internal,
+                FsmTransitionKind::SelfTransition => FsmTransitionKindMeta::SelfTransition,
+                FsmTransitionKind::Normal => FsmTransitionKindMeta::Normal
+            };
+
+            transitions.push(FsmTransitionMeta {
+                name: name_of(&transition.transition_ty),
+                region: region.region_id,
+                kind,
+                event: match event {
+                    FsmTransitionEvent::Start => FsmTransitionEventMeta::Start,
+                    FsmTransitionEvent::Stop => FsmTransitionEventMeta::Stop,
+                    FsmTransitionEvent::Event(ev) => FsmTransitionEventMeta::Event(name_of(&ev.ty))
+                },
+                from: state_name_of(from),
+                to: state_name_of(to),
+                has_guard: action.guard.is_some(),
+                has_action: action.action.is_some()
+            });
+        }
+    }
+
+    let meta: &'static FsmMeta = Box::leak(Box::new(FsmMeta {
+        name: leak_str(source.name.clone()),
+        states: leak_slice(states),
+        events: leak_slice(events),
+        regions: leak_slice(regions),
+        transitions: leak_slice(transitions)
+    }));
+    building.pop();
+    metas.insert(key, meta);
+    Ok(meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::scan::find_machines;
+
+    #[test]
+    fn test_build_metas() {
+        let mut sources = vec![];
+        find_machines(Path::new("src/lib.rs"), r#"
+            #[finny_fsm]
+            fn build_fsm(mut fsm: FsmBuilder<Door, Context>) -> BuiltFsm {
+                fsm.initial_state::<Closed>();
+                fsm.state::<Closed>().on_event::<Open>().transition_to::<Opened>().guard(|_ev, ctx, _states| ctx.unlocked);
+                fsm.state::<Opened>().on_event::<Knock>().internal_transition().action(|_ev, _ctx, _state| { });
+                fsm.state::<Opened>().on_event::<Close>().transition_to::<Lock>();
+                fsm.sub_machine::<Lock>();
+                fsm.build()
+            }
+
+            #[finny_fsm]
+            fn build_invalid(mut fsm: FsmBuilder<Invalid, Context>) -> BuiltFsm {
+                fsm.build()
+            }
+        "#, &mut sources);
+        find_machines(Path::new("src/lock.rs"), r#"
+            #[finny_fsm]
+            fn build_lock(mut fsm: FsmBuilder<Lock, ()>) -> BuiltFsm {
+                fsm.initial_state::<Engaged>();
+                fsm.state::<Engaged>().on_event::<Turn>().self_transition();
+                fsm.build()
+            }
+        "#, &mut sources);
+
+        let metas = build_metas(&sources).unwrap();
+        assert_eq!(vec!["Door", "Lock"], metas.iter().map(|(_, m)| m.name).collect::<Vec<_>>());
+
+        let door = metas[0].1;
+        assert_eq!(vec!["Closed", "Opened", "Lock"], door.states.iter().map(|s| s.name).collect::<Vec<_>>());
+        assert!(std::ptr::eq(metas[1].1, door.states[2].sub_machine.unwrap()));
+        assert_eq!(&["Open", "Knock", "Close"], door.events);
+        assert_eq!(&["Closed"], door.regions[0].initial_states);
+
+        let start = door.transitions.iter().find(|t| t.event == FsmTransitionEventMeta::Start).unwrap();
+        assert_eq!((None, Some("Closed")), (start.from, start.to));
+
+        let open = door.transitions.iter().find(|t| t.event == FsmTransitionEventMeta::Event("Open")).unwrap();
+        assert_eq!(FsmTransitionKindMeta::Normal, open.kind);
+        assert_eq!((Some("Closed"), Some("Opened")), (open.from, open.to));
+        assert!(open.has_guard && !open.has_action);
+
+        let knock = door.transitions.iter().find(|t| t.event == FsmTransitionEventMeta::Event("Knock")).unwrap();
+        assert_eq!(FsmTransitionKindMeta::Internal, knock.kind);
+        assert_eq!((Some("Opened"), Some("Opened")), (knock.from, knock.to));
+        assert!(!knock.has_guard && knock.has_action);
+
+        let turn = metas[1].1.transitions.iter().find(|t| t.event == FsmTransitionEventMeta::Event("Turn")).unwrap();
+        assert_eq!(FsmTransitionKindMeta::SelfTransition, turn.kind);
+    }
+
+    #[test]
+    fn test_build_metas_cycle() {
+        let mut sources = vec![];
+        find_machines(Path::new("src/outer.rs"), r#"
+            #[finny_fsm]
+            fn build_outer(mut fsm: FsmBuilder<Outer, ()>) -> BuiltFsm {
+                fsm.initial_state::<Inner>();
+                fsm.sub_machine::<Inner>();
+                fsm.build()
+            }
+        "#, &mut sources);
+        find_machines(Path::new("src/inner.rs"), r#"
+            #[finny_fsm]
+            fn build_inner(mut fsm: FsmBuilder<Inner, ()>) -> BuiltFsm {
+                fsm.initial_state::<Outer>();
+                fsm.sub_machine::<Outer>();
+                fsm.build()
+            }
+        "#, &mut sources);
+
+        assert_eq!(
+            Err("The machine 'Outer' contains itself through its sub-machines: Outer (src/outer.rs) -> Inner (src/inner.rs) -> Outer (src/outer.rs)".to_string()),
+            build_metas(&sources).map(|_| ())
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! Finds the `#[finny_fsm]` builder functions in the crate's sources, either in the working tree
+//! or at a git revision.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::visit::Visit;
+
+use finny_model::{fsm::FsmTypes, parse::FsmFnInput, utils::tokens_to_string};
+
+/// A machine declared in the sources.
+pub struct FsmSource {
+    /// The machine's type name, or the builder function's name if it can't be parsed.
+    pub name: String,
+    /// The file, relative to the crate's directory.
+    pub file: PathBuf,
+    pub line: usize,
+    pub fsm: syn::Result<FsmFnInput>
+}
+
+/// Scans the `.rs` files within the directory, skipping `target` and hidden directories.
+pub fn scan_dir(root: &Path) -> io::Result<Vec<FsmSource>> {
+    let mut files = vec![];
+    collect_files(root, root, &mut files)?;
+    files.sort();
+
+    let mut sources = vec![];
+    for file in files {
+        let contents = fs::read_to_string(root.join(&file))?;
+        find_machines(&file, &contents, &mut sources);
+    }
+    Ok(sources)
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if path.is_dir() {
+            if name != "target" && !name.starts_with('.') {
+                collect_files(root, &path, files)?;
+            }
+        } else if name.ends_with(".rs") {
+            files.push(path.strip_prefix(root).unwrap_or(&path).to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+/// Scans the `.rs` files within the directory, as they were at the git revision.
+pub fn scan_git(root: &Path, rev: &str) -> Result<Vec<FsmSource>, String> {
+    let git = |args: &[&str]| -> Result<String, String> {
+        let output = Command::new("git").arg("-C").arg(root).args(args).output()
+            .map_err(|e| format!("Can't run git: {}", e))?;
+        if !output.status.success() {
+            return Err(format!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+
+    let files = git(&["ls-tree", "-r", "--name-only", rev, "."])?;
+    let mut sources = vec![];
+    for file in files.lines().filter(|f| f.ends_with(".rs")) {
+        let file = PathBuf::from(file);
+        if file.components().any(|c| c.as_os_str() == "target") {
+            continue;
+        }
+
+        let contents = git(&["show", &format!("{}:./{}", rev, file.display())])?;
+        find_machines(&file, &contents, &mut sources);
+    }
+    Ok(sources)
+}
+
+/// Parses the file and all the builder functions within it, including the nested modules.
+pub fn find_machines(file: &Path, contents: &str, sources: &mut Vec<FsmSource>) {
+    let syntax = match syn::parse_file(contents) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("warning: skipping {}: {}", file.display(), e);
+            return;
+        }
+    };
+
+    let mut visitor = FsmVisitor { file, sources };
+    visitor.visit_file(&syntax);
+}
+
+struct FsmVisitor<'a> {
+    file: &'a Path,
+    sources: &'a mut Vec<FsmSource>
+}
+
+impl<'a, 'ast> Visit<'ast> for FsmVisitor<'a> {
+    fn visit_item_fn(&mut self, item_fn: &'ast syn::ItemFn) {
+        let attr = item_fn.attrs.iter().find(|a| a.path.segments.last().is_some_and(|s| s.ident == "finny_fsm"));
+
+        if let Some(attr) = attr {
+            let attr_tokens = if attr.tokens.is_empty() {
+                Ok(TokenStream::new())
+            } else {
+                attr.parse_args::<TokenStream>()
+            };
+
+            let mut item = item_fn.clone();
+            item.attrs.retain(|a| a != attr);
+
+            let fsm = attr_tokens.and_then(|attr| FsmFnInput::parse(attr, item.to_token_stream()));
+            let name = match fsm {
+                Ok(ref fsm) => tokens_to_string(FsmTypes::new(&fsm.base.fsm_ty, &fsm.base.fsm_generics).get_fsm_no_generics_ty()),
+                Err(_) => item_fn.sig.ident.to_string()
+            };
+
+            self.sources.push(FsmSource {
+                name,
+                file: self.file.to_path_buf(),
+                line: item_fn.sig.ident.span().start().line,
+                fsm
+            });
+        }
+
+        syn::visit::visit_item_fn(self, item_fn);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOOR: &str = r#"
+        use finny::{decl::{BuiltFsm, FsmBuilder}, finny_fsm};
+
+        fn helper() {}
+
+        mod door {
+            #[finny::finny_fsm]
+            fn build_fsm(mut fsm: FsmBuilder<Door, Context>) -> BuiltFsm {
+                fsm.initial_state::<Closed>();
+                fsm.state::<Closed>().on_event::<Open>().transition_to::<Opened>();
+                fsm.state::<Opened>().on_event::<Close>().transition_to::<Closed>();
+                fsm.build()
+            }
+        }
+
+        #[finny_fsm]
+        fn build_broken(mut fsm: FsmBuilder<Broken, Context>) -> BuiltFsm {
+            fsm.build()
+        }
+    "#;
+
+    /// The machines within the files, as scanned from a directory.
+    fn scan_tree(files: &[(&str, &str)]) -> Vec<FsmSource> {
+        let mut sources = vec![];
+        for (file, contents) in files {
+            find_machines(Path::new(file), contents, &mut sources);
+        }
+        sources
+    }
+
+    #[test]
+    fn test_find_machines() {
+        let sources = scan_tree(&[
+            ("src/lib.rs", DOOR),
+            ("src/broken.rs", "fn build_fsm( {"),
+            ("src/plain.rs", "fn build_fsm(mut fsm: FsmBuilder<Plain, ()>) -> BuiltFsm { fsm.build() }")
+        ]);
+
+        assert_eq!(vec!["Door", "build_broken"], sources.iter().map(|s| s.name.as_str()).collect::<Vec<_>>());
+        assert_eq!(Path::new("src/lib.rs"), sources[0].file);
+        assert_eq!(8, sources[0].line);
+        assert!(sources[0].fsm.is_ok());
+        assert!(sources[1].fsm.is_err());
+    }
+
+    #[test]
+    fn test_scan_dir() {
+        let root = std::env::temp_dir().join(format!("cargo_finny_scan_{}", std::process::id()));
+        for (file, contents) in [("src/lib.rs", DOOR), ("src/broken.rs", "fn {"), ("target/gen.rs", DOOR), (".git/hook.rs", DOOR), ("README.md", DOOR)] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        let sources = scan_dir(&root).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(vec![("Door", Path::new("src/lib.rs")), ("build_broken", Path::new("src/lib.rs"))],
+            sources.iter().map(|s| (s.name.as_str(), s.file.as_path())).collect::<Vec<_>>());
+    }
+}
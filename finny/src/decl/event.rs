@@ -65,6 +65,13 @@ impl<'a, TFsm, TContext, TEvent, TStateFrom, TStateTo> FsmEventBuilderTransition
         self
     }
 
+    /// Build a new instance of the target state from the event, replacing the existing one. Executed after the
+    /// exit action of the first state and before the transition's action. A state that is only entered through
+    /// such transitions doesn't need `Default` or a constructor, it's stored lazily and can't be an initial state.
+    pub fn construct<TConstruct: Fn(&TEvent, &TContext, &TStateFrom) -> TStateTo>(&mut self, _construct: TConstruct) -> &mut Self {
+        self
+    }
+
     /// A guard for starting this transition from one state to another, including executing the action.
     pub fn guard<TGuard: Fn(&TEvent, &EventContext<'a, TFsm, FsmQueueMock<TFsm>>, &<TFsm as FsmBackend>::States) -> bool>(&mut self, _guard: TGuard) -> &mut Self {
         self
@@ -1,7 +1,7 @@
 use crate::{FsmBackendImpl, lib::*};
 
 use crate::FsmBackend;
-use super::{FsmStateBuilder, FsmSubMachineBuilder};
+use super::{FsmInitialStateBuilder, FsmRegionBuilder, FsmStateBuilder, FsmSubMachineBuilder};
 
 /// The main builder-API for defining your Finny state machine.
 #[derive(Default)]
@@ -16,9 +16,16 @@ pub struct BuiltFsm;
 impl<TFsm, TContext> FsmBuilder<TFsm, TContext>
 	where TFsm: FsmBackend<Context = TContext>
 {
-	/// Sets the initial state of the state machine. Required!
-	pub fn initial_state<TSTate>(&mut self) {
-		
+	/// Sets the initial state of the state machine. Required! Can be declared multiple times with
+	/// guards, the first initial state whose guard passes is entered.
+	///
+	/// Example : `fsm.initial_state::<Provisioned>().guard(|ctx| ctx.provisioned)`
+	pub fn initial_state<TState>(&mut self) -> FsmInitialStateBuilder<TFsm, TContext, TState> {
+		FsmInitialStateBuilder {
+			_fsm: PhantomData::default(),
+			_context: PhantomData::default(),
+			_state: PhantomData::default()
+		}
 	}
 
 	/// Defines multiple initial states for multiple regions of the FSM. The type has to be a tuple
@@ -29,11 +36,41 @@ impl<TFsm, TContext> FsmBuilder<TFsm, TContext>
 
 	}
 
+	/// Declares a named region of the FSM. The marker type `TRegion` is generated by the code generator and
+	/// can be used to refer to the region, regardless of the order in which the regions were declared.
+	/// Can't be combined with `initial_state` or `initial_states`.
+	///
+	/// Example : `fsm.region::<MotorRegion>().initial_state::<Idle>()`
+	pub fn region<TRegion>(&mut self) -> FsmRegionBuilder<TFsm, TContext, TRegion> {
+		FsmRegionBuilder {
+			_fsm: PhantomData::default(),
+			_context: PhantomData::default(),
+			_region: PhantomData::default()
+		}
+	}
+
 	/// Require the `Debug` trait on the Events.
 	pub fn events_debug(&mut self) {
 		
 	}
 
+	/// Store only the active state of each region, inside an enum. States are constructed
+	/// when they are entered and dropped when they are exited, so the storage takes only as
+	/// much memory as the largest state of each region. Inactive states can't be accessed.
+	///
+	/// Unlike the default storage, the union storage doesn't implement `AsRef` and `AsMut` for
+	/// the states, so `get_state` and the guards that borrow other states through `AsRef` don't
+	/// compile with it. Use `try_get_state` or the `FsmStateStorage` trait instead.
+	pub fn state_storage_union(&mut self) {
+
+	}
+
+	/// Pass the events, the states and the context to the data-carrying hooks of the
+	/// inspection. Requires the `Debug` trait on all of them.
+	pub fn inspect_debug(&mut self) {
+
+	}
+
 	/// Adds some information about a state.
 	pub fn state<TState>(&mut self) -> FsmStateBuilder<TFsm, TContext, TState> {
 		FsmStateBuilder {
@@ -0,0 +1,28 @@
+use crate::lib::*;
+
+use crate::{EventContext, FsmBackend};
+use super::FsmQueueMock;
+
+/// An initial state of the machine or of one of its regions. Multiple guarded initial states can
+/// be declared for a region, they are evaluated in the order of their declaration.
+pub struct FsmInitialStateBuilder<TFsm, TContext, TState> {
+	pub (crate) _fsm: PhantomData<TFsm>,
+	pub (crate) _context: PhantomData<TContext>,
+	pub (crate) _state: PhantomData<TState>
+}
+
+impl<TFsm, TContext, TState> FsmInitialStateBuilder<TFsm, TContext, TState>
+	where TFsm: FsmBackend
+{
+	/// Start in this state only if the guard allows it. Otherwise the next initial state of this region is tried.
+	/// If all of the region's initial states are guarded and none of them passes, `start()` fails with
+	/// `FsmError::NoInitialState` and the region stays stopped.
+	pub fn guard<'a, TGuard: Fn(&EventContext<'a, TFsm, FsmQueueMock<TFsm>>) -> bool>(&mut self, _guard: TGuard) -> &mut Self {
+		self
+	}
+
+	/// An action that is executed once when the machine starts in this state, just before the state's entry action.
+	pub fn action<'a, TAction: Fn(&mut EventContext<'a, TFsm, FsmQueueMock<TFsm>>, &mut TState)>(&mut self, _action: TAction) -> &mut Self {
+		self
+	}
+}
@@ -5,11 +5,15 @@ mod fsm;
 mod state;
 mod event;
 mod sub;
+mod region;
+mod initial;
 
 pub use self::fsm::*;
 pub use self::state::*;
 pub use self::event::*;
 pub use self::sub::*;
+pub use self::region::*;
+pub use self::initial::*;
 
 #[cfg(feature = "std")]
 pub type FsmQueueMock<F> = crate::FsmEventQueueVec<F>;
@@ -0,0 +1,31 @@
+use crate::lib::*;
+
+use crate::FsmBackend;
+use super::FsmInitialStateBuilder;
+
+/// Describes a named region of the machine. The marker type is generated by the code generator.
+pub struct FsmRegionBuilder<TFsm, TContext, TRegion> {
+	pub (crate) _fsm: PhantomData<TFsm>,
+	pub (crate) _context: PhantomData<TContext>,
+	pub (crate) _region: PhantomData<TRegion>
+}
+
+impl<TFsm, TContext, TRegion> FsmRegionBuilder<TFsm, TContext, TRegion>
+	where TFsm: FsmBackend<Context = TContext>
+{
+	/// Sets the initial state of this region. Required for every named region! Can be declared
+	/// multiple times with guards, the first initial state whose guard passes is entered.
+	pub fn initial_state<TState>(&self) -> FsmInitialStateBuilder<TFsm, TContext, TState> {
+		FsmInitialStateBuilder {
+			_fsm: PhantomData::default(),
+			_context: PhantomData::default(),
+			_state: PhantomData::default()
+		}
+	}
+
+	/// Places the state into this region. The code generator verifies that the states placed in the
+	/// region match the ones that are reachable from its initial state.
+	pub fn state<TState>(&self) -> &Self {
+		self
+	}
+}
@@ -22,6 +22,17 @@ impl<TFsm, TContext, TState> FsmStateBuilder<TFsm, TContext, TState>
 		self
 	}
 
+	/// Construct the state using this closure, instead of the type's `FsmStateFactory` or `Default` implementation.
+	pub fn with_constructor<TConstructor: Fn(&TContext) -> TState>(&self, _constructor: TConstructor) -> &Self {
+		self
+	}
+
+	/// Build a fresh instance of the state every time it is entered, so no data is carried over from the
+	/// previous visit. The new instance is built before the transition's action is executed.
+	pub fn reset_on_entry(&self) -> &Self {
+		self
+	}
+
 	/// What happens if we receive this event and we are in this state right now?
 	pub fn on_event<TEvent>(&self) -> FsmEventBuilderState<TFsm, TContext, TEvent, TState> {
 		FsmEventBuilderState {
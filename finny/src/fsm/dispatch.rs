@@ -1,5 +1,5 @@
 use crate::lib::*;
-use crate::{EventContext, FsmBackend, FsmBackendImpl, FsmEvent, FsmEventQueue, FsmEventQueueSub, FsmRegionId, FsmResult, Inspect};
+use crate::{EventContext, FsmError, FsmBackend, FsmBackendImpl, FsmCurrentState, FsmStateStorage, FsmEvent, FsmEventQueue, FsmEventQueueSub, FsmRegionId, FsmResult, Inspect};
 
 pub struct DispatchContext<'a, 'b, 'c, F, Q, I>
     where F: FsmBackend,
@@ -32,14 +32,14 @@ pub fn dispatch_to_submachine<'a, 'b, 'c, TFsm, TSubMachine, TEvent, Q, I>(ctx:
     -> FsmResult<()>
     where
         TFsm: FsmBackend,
-        <TFsm as FsmBackend>::States: AsMut<TSubMachine>,
+        <TFsm as FsmBackend>::States: FsmStateStorage<TFsm, TSubMachine>,
         TSubMachine: FsmBackend + DerefMut<Target = FsmBackendImpl<TSubMachine>> + FsmBackend<Events = TEvent>,
         Q: FsmEventQueue<TFsm>,
         I: Inspect,
         <TFsm as FsmBackend>::Events: From<<TSubMachine as FsmBackend>::Events>,
         TEvent: Clone
 {
-    let sub_fsm: &mut TSubMachine = ctx.backend.states.as_mut();
+    let sub_fsm: &mut TSubMachine = ctx.backend.states.try_state_mut().ok_or(FsmError::StateNotAllocated)?;
 
     let mut queue_adapter = FsmEventQueueSub {
         parent: ctx.queue,
@@ -56,4 +56,37 @@ pub fn dispatch_to_submachine<'a, 'b, 'c, TFsm, TSubMachine, TEvent, Q, I>(ctx:
     };
 
     <TSubMachine>::dispatch_event(sub_dispatch_ctx, FsmEvent::Event(ev.clone()))
+}
+
+/// Stops the sub-machine before its parent exits it, if it is running.
+pub fn stop_submachine<'a, 'b, 'c, TFsm, TSubMachine, Q, I>(ctx: &mut DispatchContext<'a, 'b, 'c, TFsm, Q, I>, inspect_event_ctx: &mut I)
+    -> FsmResult<()>
+    where
+        TFsm: FsmBackend,
+        <TFsm as FsmBackend>::States: FsmStateStorage<TFsm, TSubMachine>,
+        TSubMachine: FsmBackend + DerefMut<Target = FsmBackendImpl<TSubMachine>>,
+        Q: FsmEventQueue<TFsm>,
+        I: Inspect,
+        <TFsm as FsmBackend>::Events: From<<TSubMachine as FsmBackend>::Events>
+{
+    let sub_fsm: &mut TSubMachine = ctx.backend.states.try_state_mut().ok_or(FsmError::StateNotAllocated)?;
+    if FsmCurrentState::all_stopped(sub_fsm.get_current_states().as_ref()) {
+        return Ok(());
+    }
+
+    let mut queue_adapter = FsmEventQueueSub {
+        parent: ctx.queue,
+        _parent_fsm: core::marker::PhantomData::<TFsm>::default(),
+        _sub_fsm: core::marker::PhantomData::<TSubMachine>::default()
+    };
+
+    let mut inspect = inspect_event_ctx.for_sub_machine::<TSubMachine>();
+
+    let sub_dispatch_ctx = DispatchContext {
+        backend: sub_fsm,
+        inspect: &mut inspect,
+        queue: &mut queue_adapter
+    };
+
+    <TSubMachine>::dispatch_event(sub_dispatch_ctx, FsmEvent::Stop)
 }
\ No newline at end of file
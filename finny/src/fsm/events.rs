@@ -35,6 +35,15 @@ impl<E> AsRef<str> for FsmEvent<E> where E: AsRef<str> {
 
 pub type FsmRegionId = usize;
 
+/// A marker type for a named region, declared using `fsm.region::<R>()` and generated by the code generator.
+/// Refers to the region independently of the order in which the regions were declared.
+pub trait FsmRegion<F: FsmBackend> {
+    /// The index of this region within the machine's current states.
+    const REGION_ID: FsmRegionId;
+    /// The name of this region.
+    const NAME: &'static str;
+}
+
 /// The context that is given to all of the guards and actions.
 pub struct EventContext<'a, TFsm, Q> where TFsm: FsmBackend, Q: FsmEventQueueSender<TFsm> {
     pub context: &'a mut TFsm::Context,
@@ -42,6 +51,18 @@ pub struct EventContext<'a, TFsm, Q> where TFsm: FsmBackend, Q: FsmEventQueueSen
     pub region: FsmRegionId
 }
 
+impl<'a, TFsm, Q> EventContext<'a, TFsm, Q> where TFsm: FsmBackend, Q: FsmEventQueueSender<TFsm> {
+    /// The name of the region that is handling this event.
+    pub fn region_name(&self) -> &'static str {
+        TFsm::REGION_NAMES[self.region]
+    }
+
+    /// Is this event being handled by the named region `R`?
+    pub fn is_region<R: FsmRegion<TFsm>>(&self) -> bool {
+        self.region == R::REGION_ID
+    }
+}
+
 impl<'a, TFsm, Q> Deref for EventContext<'a, TFsm, Q> where TFsm: FsmBackend, Q: FsmEventQueueSender<TFsm>
 {
     type Target = <TFsm as FsmBackend>::Context;
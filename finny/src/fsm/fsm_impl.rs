@@ -1,5 +1,5 @@
 use crate::{DispatchContext, Inspect, lib::*};
-use crate::{FsmBackend, FsmEvent, FsmEventQueue, FsmResult, FsmStates};
+use crate::{FsmBackend, FsmCurrentState, FsmEvent, FsmEventQueue, FsmRegion, FsmResult, FsmStateStorage, FsmStates};
 
 use super::FsmStateFactory;
 
@@ -26,6 +26,15 @@ impl<F: FsmBackend> FsmBackendImpl<F> {
         Ok(backend)
     }
     
+    /// Rebuilds all of the states, including the sub-machines, from the context. The machine is left
+    /// in the stopped state.
+    pub fn reset(&mut self) -> FsmResult<()> {
+        self.states = <<F as FsmBackend>::States>::new_state(&self.context)?;
+        self.current_states = <<<F as FsmBackend>::States as FsmStates<F>>::CurrentState>::default();
+
+        Ok(())
+    }
+
     pub fn get_context(&self) -> &<F as FsmBackend>::Context {
         &self.context
     }
@@ -34,11 +43,25 @@ impl<F: FsmBackend> FsmBackendImpl<F> {
         self.current_states
     }
 
+    /// The current state of the named region `R`.
+    pub fn get_current_state<R>(&self) -> FsmCurrentState<<<F as FsmBackend>::States as FsmStates<F>>::StateKind>
+        where R: FsmRegion<F>
+    {
+        self.current_states.as_ref()[R::REGION_ID]
+    }
+
     pub fn get_state<S>(&self) -> &S
         where <F as FsmBackend>::States : AsRef<S>
     {
         self.states.as_ref()
     }
+
+    /// The state, if it is currently allocated. Works with both the default and the union state storage.
+    pub fn try_get_state<S>(&self) -> Option<&S>
+        where <F as FsmBackend>::States : FsmStateStorage<F, S>
+    {
+        self.states.try_state_ref()
+    }
 }
 
 impl<F: FsmBackend> Deref for FsmBackendImpl<F> {
@@ -64,7 +87,46 @@ impl<F, Q, I> FsmFrontend<F, Q, I>
 {
     /// Start the FSM, initiates the transition to the initial state.
     pub fn start(&mut self) -> FsmResult<()> {
-        Self::dispatch_single_event(self, FsmEvent::Start)
+        Self::dispatch_external(self, FsmEvent::Start, false)
+    }
+
+    /// Stop the FSM, exits the currently active states.
+    pub fn stop(&mut self) -> FsmResult<()> {
+        Self::dispatch_external(self, FsmEvent::Stop, false)
+    }
+
+    /// Stops the FSM if it is running and starts it again. The states keep their data.
+    pub fn restart(&mut self) -> FsmResult<()> {
+        if !FsmCurrentState::all_stopped(self.backend.current_states.as_ref()) {
+            self.stop()?;
+        }
+
+        self.start()
+    }
+
+    /// Stops the FSM if it is running and rebuilds all of its states, including the sub-machines.
+    /// Pending events are discarded, the queue and the inspection are kept. The machine has to be
+    /// started again.
+    pub fn reset(&mut self) -> FsmResult<()> {
+        self.stop_and_discard_events()?;
+        self.backend.reset()
+    }
+
+    /// Resets the FSM like `reset()`, but also replaces its context. The states are built from the new context.
+    pub fn reset_with_context(&mut self, context: <F as FsmBackend>::Context) -> FsmResult<()> {
+        self.stop_and_discard_events()?;
+        self.backend.context = context;
+        self.backend.reset()
+    }
+
+    fn stop_and_discard_events(&mut self) -> FsmResult<()> {
+        if !FsmCurrentState::all_stopped(self.backend.current_states.as_ref()) {
+            self.stop()?;
+        }
+
+        while self.queue.dequeue().is_some() { }
+
+        Ok(())
     }
 
     /// Dispatch this event and run it to completition.
@@ -73,14 +135,26 @@ impl<F, Q, I> FsmFrontend<F, Q, I>
     {
         let ev = event.into();
         let ev = FsmEvent::Event(ev);
-        Self::dispatch_single_event(self, ev)?;
+        Self::dispatch_external(self, ev, true)
+    }
 
-        while let Some(ev) = self.queue.dequeue() {
-            let ev: <F as FsmBackend>::Events = ev.into();
-            Self::dispatch(self, ev)?;
+    /// Dispatches an event that didn't come from the queue, and then the queued events if `run_to_completion` is set.
+    /// The queue is told about the boundaries, to track the causality of the events.
+    fn dispatch_external(&mut self, event: FsmEvent<<F as FsmBackend>::Events>, run_to_completion: bool) -> FsmResult<()> {
+        self.queue.begin_external_event();
+        let mut result = Self::dispatch_single_event(self, event);
+
+        if run_to_completion {
+            while result.is_ok() {
+                match self.queue.dequeue() {
+                    Some(ev) => result = Self::dispatch_single_event(self, FsmEvent::Event(ev)),
+                    None => break
+                }
+            }
         }
 
-        Ok(())
+        self.queue.end_external_event();
+        result
     }
 
     /// Dispatch only this event, do not run it to completition.
@@ -1,16 +1,51 @@
-use crate::{FsmBackend, FsmEvent};
+use crate::lib::*;
+use crate::{FsmBackend, FsmError, FsmEvent, FsmEventEnvelope, FsmEventQueue, FsmEventQueueSender, FsmRegionId, FsmResult};
+
+/// Observes the dispatching of events. The type-level hooks are always called. The data-carrying
+/// hooks (`on_*_data`) are only called for machines built with `fsm.inspect_debug()`, which requires
+/// the `Debug` trait on the events, the states and the context. All the hooks besides the scoping ones
+/// have empty default implementations.
 pub trait Inspect {
-    
-    fn new_event<F: FsmBackend>(&self, event: &FsmEvent<<F as FsmBackend>::Events>) -> Self;
+
+    /// A new event is being dispatched. The envelope links it to the event that enqueued it, if the
+    /// machine's queue tracks the causality.
+    fn new_event<F: FsmBackend>(&self, event: &FsmEvent<<F as FsmBackend>::Events>, envelope: Option<&FsmEventEnvelope>) -> Self;
     fn event_done(self);
 
+    /// The scope of the region that is handling the event. This is a required method, so the inspectors
+    /// written before the regions were named have to implement it, usually by cloning themselves.
+    fn for_region<F: FsmBackend>(&self, region: FsmRegionId) -> Self;
     fn for_transition<T>(&self) -> Self;
     fn for_sub_machine<FSub: FsmBackend>(&self) -> Self;
 
-    fn on_guard<T>(&self, guard_result: bool);
-    fn on_state_enter<S>(&self);
-    fn on_state_exit<S>(&self);
-    fn on_action<S>(&self);
+    fn on_guard<T>(&self, _guard_result: bool) { }
+    fn on_state_enter<S>(&self) { }
+    fn on_state_exit<S>(&self) { }
+    fn on_action<S>(&self) { }
+
+    /// The dispatched event, called right after `new_event`.
+    fn on_event_data(&self, _event: &dyn Debug) { }
+    /// The state and the context, after the state's entry action.
+    fn on_state_enter_data<S>(&self, _state: &dyn Debug, _context: &dyn Debug) { }
+    /// The state and the context, before the state's exit action.
+    fn on_state_exit_data<S>(&self, _state: &dyn Debug, _context: &dyn Debug) { }
+    /// The context, after the transition's action.
+    fn on_action_data<T>(&self, _context: &dyn Debug) { }
+
+    /// None of the regions had a transition for this event.
+    fn on_no_transition(&self) { }
+    /// The dispatch failed, called before `event_done`. Also called after `on_no_transition`.
+    fn on_error(&self, _error: &FsmError) { }
+    /// An action enqueued an event.
+    fn on_queue_enqueue<F: FsmBackend>(&self, _event: &<F as FsmBackend>::Events) { }
+}
+
+/// The type's name without the module paths of the type itself, as used by the inspectors. The generic
+/// arguments keep theirs, so the name doesn't need an allocation: `Foo<my::Bar>`.
+pub fn short_type_name<T: ?Sized>() -> &'static str {
+    let name = type_name::<T>();
+    let base = &name[..name.find('<').unwrap_or(name.len())];
+    &name[base.rfind("::").map(|i| i + 2).unwrap_or(0)..]
 }
 
 #[derive(Default)]
@@ -23,7 +58,11 @@ impl InspectNull {
 }
 
 impl Inspect for InspectNull {
-    fn new_event<F: FsmBackend>(&self, _event: &FsmEvent<<F as FsmBackend>::Events>) -> Self {
+    fn new_event<F: FsmBackend>(&self, _event: &FsmEvent<<F as FsmBackend>::Events>, _envelope: Option<&FsmEventEnvelope>) -> Self {
+        Self::default()
+    }
+
+    fn for_region<F: FsmBackend>(&self, _region: FsmRegionId) -> Self {
         Self::default()
     }
 
@@ -35,23 +74,69 @@ impl Inspect for InspectNull {
         Self::default()
     }
 
-    fn on_guard<T>(&self, _guard_result: bool) {
-        
+    fn event_done(self) {
+
     }
+}
+
+/// The queue that the actions see, reports the enqueued events to the inspection.
+pub struct FsmEventQueueInspect<'a, 'b, F, Q, I> {
+    queue: &'a mut Q,
+    inspect: &'b I,
+    _fsm: PhantomData<F>
+}
 
-    fn on_state_enter<S>(&self) {
-        
+impl<'a, 'b, F, Q, I> FsmEventQueueInspect<'a, 'b, F, Q, I>
+    where F: FsmBackend, Q: FsmEventQueue<F>, I: Inspect
+{
+    pub fn new(queue: &'a mut Q, inspect: &'b I) -> Self {
+        FsmEventQueueInspect {
+            queue,
+            inspect,
+            _fsm: PhantomData
+        }
     }
+}
 
-    fn on_state_exit<S>(&self) {
-        
+impl<'a, 'b, F, Q, I> FsmEventQueue<F> for FsmEventQueueInspect<'a, 'b, F, Q, I>
+    where F: FsmBackend, Q: FsmEventQueue<F>, I: Inspect
+{
+    fn dequeue(&mut self) -> Option<<F as FsmBackend>::Events> {
+        self.queue.dequeue()
     }
 
-    fn on_action<S>(&self) {
-        
+    fn len(&self) -> usize {
+        self.queue.len()
     }
 
-    fn event_done(self) {
-        
+    fn begin_external_event(&mut self) {
+        self.queue.begin_external_event()
+    }
+
+    fn end_external_event(&mut self) {
+        self.queue.end_external_event()
+    }
+}
+
+impl<'a, 'b, F, Q, I> FsmEventQueueSender<F> for FsmEventQueueInspect<'a, 'b, F, Q, I>
+    where F: FsmBackend, Q: FsmEventQueue<F>, I: Inspect
+{
+    fn enqueue<E: Into<<F as FsmBackend>::Events>>(&mut self, event: E) -> FsmResult<()> {
+        let event = event.into();
+        self.inspect.on_queue_enqueue::<F>(&event);
+        self.queue.enqueue(event)
     }
-}
\ No newline at end of file
+
+    fn current_envelope(&self) -> Option<FsmEventEnvelope> {
+        self.queue.current_envelope()
+    }
+}
+
+#[test]
+fn test_short_type_name() {
+    use super::tests_fsm::{EventA, TestFsm};
+
+    assert_eq!("TestFsm", short_type_name::<TestFsm>());
+    assert_eq!("Option<finny::fsm::tests_fsm::EventA>", short_type_name::<Option<EventA>>());
+    assert_eq!("str", short_type_name::<str>());
+}
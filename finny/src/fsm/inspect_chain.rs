@@ -0,0 +1,98 @@
+use crate::lib::*;
+use crate::{FsmBackend, FsmError, FsmEvent, FsmEventEnvelope, FsmRegionId, Inspect};
+
+/// Forwards every hook to both of the inspectors, first to `A` and then to `B`. Chain more of them
+/// with [`InspectChain::chain`]: `InspectChain::new(a, b).chain(c)`.
+pub struct InspectChain<A, B> {
+    pub first: A,
+    pub second: B
+}
+
+impl<A, B> InspectChain<A, B> where A: Inspect, B: Inspect {
+    pub fn new(first: A, second: B) -> Self {
+        InspectChain { first, second }
+    }
+
+    /// Appends another inspector after this chain.
+    pub fn chain<C: Inspect>(self, next: C) -> InspectChain<Self, C> {
+        InspectChain::new(self, next)
+    }
+}
+
+impl<A, B> Inspect for InspectChain<A, B> where A: Inspect, B: Inspect {
+    fn new_event<F: FsmBackend>(&self, event: &FsmEvent<<F as FsmBackend>::Events>, envelope: Option<&FsmEventEnvelope>) -> Self {
+        InspectChain::new(self.first.new_event::<F>(event, envelope), self.second.new_event::<F>(event, envelope))
+    }
+
+    fn event_done(self) {
+        self.first.event_done();
+        self.second.event_done();
+    }
+
+    fn for_region<F: FsmBackend>(&self, region: FsmRegionId) -> Self {
+        InspectChain::new(self.first.for_region::<F>(region), self.second.for_region::<F>(region))
+    }
+
+    fn for_transition<T>(&self) -> Self {
+        InspectChain::new(self.first.for_transition::<T>(), self.second.for_transition::<T>())
+    }
+
+    fn for_sub_machine<FSub: FsmBackend>(&self) -> Self {
+        InspectChain::new(self.first.for_sub_machine::<FSub>(), self.second.for_sub_machine::<FSub>())
+    }
+
+    fn on_guard<T>(&self, guard_result: bool) {
+        self.first.on_guard::<T>(guard_result);
+        self.second.on_guard::<T>(guard_result);
+    }
+
+    fn on_state_enter<S>(&self) {
+        self.first.on_state_enter::<S>();
+        self.second.on_state_enter::<S>();
+    }
+
+    fn on_state_exit<S>(&self) {
+        self.first.on_state_exit::<S>();
+        self.second.on_state_exit::<S>();
+    }
+
+    fn on_action<S>(&self) {
+        self.first.on_action::<S>();
+        self.second.on_action::<S>();
+    }
+
+    fn on_event_data(&self, event: &dyn Debug) {
+        self.first.on_event_data(event);
+        self.second.on_event_data(event);
+    }
+
+    fn on_state_enter_data<S>(&self, state: &dyn Debug, context: &dyn Debug) {
+        self.first.on_state_enter_data::<S>(state, context);
+        self.second.on_state_enter_data::<S>(state, context);
+    }
+
+    fn on_state_exit_data<S>(&self, state: &dyn Debug, context: &dyn Debug) {
+        self.first.on_state_exit_data::<S>(state, context);
+        self.second.on_state_exit_data::<S>(state, context);
+    }
+
+    fn on_action_data<T>(&self, context: &dyn Debug) {
+        self.first.on_action_data::<T>(context);
+        self.second.on_action_data::<T>(context);
+    }
+
+    fn on_no_transition(&self) {
+        self.first.on_no_transition();
+        self.second.on_no_transition();
+    }
+
+    fn on_error(&self, error: &FsmError) {
+        self.first.on_error(error);
+        self.second.on_error(error);
+    }
+
+    fn on_queue_enqueue<F: FsmBackend>(&self, event: &<F as FsmBackend>::Events) {
+        self.first.on_queue_enqueue::<F>(event);
+        self.second.on_queue_enqueue::<F>(event);
+    }
+}
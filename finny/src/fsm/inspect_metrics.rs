@@ -0,0 +1,303 @@
+use crate::lib::*;
+use crate::{FsmBackend, FsmEvent, FsmEventEnvelope, FsmRegionId, Inspect, inspect::short_type_name};
+
+/// The maximum number of the histogram's buckets, including the implicit `+Inf` one.
+pub const FSM_METRICS_BUCKETS: usize = 12;
+
+/// The default upper bounds of the time in state histogram's buckets, in the clock's units.
+pub const FSM_METRICS_DEFAULT_BOUNDS: &[u64] = &[1, 10, 100, 1_000, 10_000, 100_000, 1_000_000, 10_000_000];
+
+/// A monotonic clock for measuring the time spent in the states. The units are up to the user, for
+/// example microseconds.
+pub trait FsmClock {
+    fn now(&self) -> u64;
+}
+
+/// Disables the measurement of the time spent in the states.
+#[derive(Default)]
+pub struct FsmNoClock;
+
+impl FsmClock for FsmNoClock {
+    fn now(&self) -> u64 {
+        0
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FsmMetricKind {
+    /// `count` is the number of dispatches, `rejected` the number of dispatches without a matching transition.
+    Event,
+    /// `count` is the number of executions.
+    Transition,
+    /// The transition's guard. `count` is the number of passes, `rejected` the number of rejections.
+    Guard,
+    /// `count` is the number of entries, with the time spent in the state and its histogram.
+    State
+}
+
+/// The counters of a single event, transition, guard or state. The names are the types' names, without
+/// the module paths.
+#[derive(Copy, Clone, Debug)]
+pub struct FsmMetric {
+    pub kind: FsmMetricKind,
+    /// The machine that this metric belongs to.
+    pub fsm: &'static str,
+    pub name: &'static str,
+    pub count: u64,
+    pub rejected: u64,
+    /// The total time spent in the state.
+    pub time_total: u64,
+    /// The number of visits of the state, by the time spent in it. Bucket `i` counts the visits
+    /// up to the `i`-th bound that are longer than the previous bound, the bucket after the last bound
+    /// counts the rest.
+    pub time_histogram: [u64; FSM_METRICS_BUCKETS],
+    entered_at: Option<u64>
+}
+
+impl FsmMetric {
+    fn new(kind: FsmMetricKind, fsm: &'static str, name: &'static str) -> Self {
+        FsmMetric {
+            kind,
+            fsm,
+            name,
+            count: 0,
+            rejected: 0,
+            time_total: 0,
+            time_histogram: [0; FSM_METRICS_BUCKETS],
+            entered_at: None
+        }
+    }
+}
+
+/// A copy of the metrics at some point in time.
+#[derive(Copy, Clone, Debug)]
+pub struct FsmMetricsSnapshot<const N: usize> {
+    metrics: [Option<FsmMetric>; N],
+    /// The upper bounds of the histogram's buckets.
+    pub bounds: &'static [u64],
+    /// Whether a clock was provided, and the time in state was measured.
+    pub timed: bool,
+    /// The number of updates that were dropped because all `N` slots were already taken.
+    pub dropped: u64
+}
+
+impl<const N: usize> FsmMetricsSnapshot<N> {
+    pub fn iter(&self) -> impl Iterator<Item = &FsmMetric> {
+        self.metrics.iter().flatten()
+    }
+
+    /// The metric of the machine's (named without the module path) event, transition, guard or state.
+    pub fn get(&self, kind: FsmMetricKind, fsm: &str, name: &str) -> Option<&FsmMetric> {
+        self.iter().find(|m| m.kind == kind && m.fsm == fsm && m.name == name)
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn render_prometheus<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        writeln!(w, "# HELP finny_events_total Dispatched events.")?;
+        writeln!(w, "# TYPE finny_events_total counter")?;
+        for m in self.iter().filter(|m| m.kind == FsmMetricKind::Event) {
+            writeln!(w, "finny_events_total{{fsm=\"{}\",event=\"{}\"}} {}", m.fsm, m.name, m.count)?;
+        }
+
+        writeln!(w, "# HELP finny_no_transition_total Dispatched events without a matching transition.")?;
+        writeln!(w, "# TYPE finny_no_transition_total counter")?;
+        for m in self.iter().filter(|m| m.kind == FsmMetricKind::Event) {
+            writeln!(w, "finny_no_transition_total{{fsm=\"{}\",event=\"{}\"}} {}", m.fsm, m.name, m.rejected)?;
+        }
+
+        writeln!(w, "# HELP finny_transitions_total Executed transitions.")?;
+        writeln!(w, "# TYPE finny_transitions_total counter")?;
+        for m in self.iter().filter(|m| m.kind == FsmMetricKind::Transition) {
+            writeln!(w, "finny_transitions_total{{fsm=\"{}\",transition=\"{}\"}} {}", m.fsm, m.name, m.count)?;
+        }
+
+        writeln!(w, "# HELP finny_guards_total Evaluated guards, by the result.")?;
+        writeln!(w, "# TYPE finny_guards_total counter")?;
+        for m in self.iter().filter(|m| m.kind == FsmMetricKind::Guard) {
+            writeln!(w, "finny_guards_total{{fsm=\"{}\",transition=\"{}\",result=\"pass\"}} {}", m.fsm, m.name, m.count)?;
+            writeln!(w, "finny_guards_total{{fsm=\"{}\",transition=\"{}\",result=\"reject\"}} {}", m.fsm, m.name, m.rejected)?;
+        }
+
+        writeln!(w, "# HELP finny_state_entries_total Entries into the states.")?;
+        writeln!(w, "# TYPE finny_state_entries_total counter")?;
+        for m in self.iter().filter(|m| m.kind == FsmMetricKind::State) {
+            writeln!(w, "finny_state_entries_total{{fsm=\"{}\",state=\"{}\"}} {}", m.fsm, m.name, m.count)?;
+        }
+
+        if self.timed {
+            writeln!(w, "# HELP finny_state_time Time spent in the states, in the clock's units.")?;
+            writeln!(w, "# TYPE finny_state_time histogram")?;
+            for m in self.iter().filter(|m| m.kind == FsmMetricKind::State) {
+                let mut cumulative = 0;
+                for (bound, count) in self.bounds.iter().zip(m.time_histogram.iter()) {
+                    cumulative += count;
+                    writeln!(w, "finny_state_time_bucket{{fsm=\"{}\",state=\"{}\",le=\"{}\"}} {}", m.fsm, m.name, bound, cumulative)?;
+                }
+                let visits: u64 = m.time_histogram.iter().sum();
+                writeln!(w, "finny_state_time_bucket{{fsm=\"{}\",state=\"{}\",le=\"+Inf\"}} {}", m.fsm, m.name, visits)?;
+                writeln!(w, "finny_state_time_sum{{fsm=\"{}\",state=\"{}\"}} {}", m.fsm, m.name, m.time_total)?;
+                writeln!(w, "finny_state_time_count{{fsm=\"{}\",state=\"{}\"}} {}", m.fsm, m.name, visits)?;
+            }
+        }
+
+        writeln!(w, "# HELP finny_metrics_dropped_total Updates dropped because the metrics storage was full.")?;
+        writeln!(w, "# TYPE finny_metrics_dropped_total counter")?;
+        writeln!(w, "finny_metrics_dropped_total {}", self.dropped)
+    }
+}
+
+/// The storage of the metrics, for up to `N` events, transitions, guards and states. Doesn't allocate.
+/// Shared by the machines' [`InspectMetrics`] inspectors.
+pub struct FsmMetrics<C = FsmNoClock, const N: usize = 64> {
+    clock: Option<C>,
+    bounds: &'static [u64],
+    inner: RefCell<FsmMetricsSnapshot<N>>
+}
+
+impl<const N: usize> FsmMetrics<FsmNoClock, N> {
+    /// Counts the events, transitions, guards and state entries, without measuring the time.
+    pub fn new() -> Self {
+        Self::build(None, FSM_METRICS_DEFAULT_BOUNDS)
+    }
+}
+
+impl<const N: usize> Default for FsmMetrics<FsmNoClock, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: FsmClock, const N: usize> FsmMetrics<C, N> {
+    /// Also measures the time spent in the states, with the default histogram bounds.
+    pub fn with_clock(clock: C) -> Self {
+        Self::with_clock_and_bounds(clock, FSM_METRICS_DEFAULT_BOUNDS)
+    }
+
+    /// Also measures the time spent in the states. The bounds have to be ascending, only the first
+    /// `FSM_METRICS_BUCKETS - 1` are used.
+    pub fn with_clock_and_bounds(clock: C, bounds: &'static [u64]) -> Self {
+        Self::build(Some(clock), bounds)
+    }
+
+    fn build(clock: Option<C>, bounds: &'static [u64]) -> Self {
+        let bounds = &bounds[..bounds.len().min(FSM_METRICS_BUCKETS - 1)];
+        FsmMetrics {
+            inner: RefCell::new(FsmMetricsSnapshot {
+                metrics: [None; N],
+                bounds,
+                timed: clock.is_some(),
+                dropped: 0
+            }),
+            clock,
+            bounds
+        }
+    }
+
+    /// The inspector that updates these metrics.
+    pub fn inspect(&self) -> InspectMetrics<'_, C, N> {
+        InspectMetrics {
+            metrics: self,
+            fsm: "",
+            event: ""
+        }
+    }
+
+    pub fn snapshot(&self) -> FsmMetricsSnapshot<N> {
+        *self.inner.borrow()
+    }
+
+    /// Clears all of the counters, including the ones for the states that are currently active.
+    pub fn reset(&self) {
+        let mut inner = self.inner.borrow_mut();
+        inner.metrics = [None; N];
+        inner.dropped = 0;
+    }
+
+    fn update<U: FnOnce(&mut FsmMetric)>(&self, kind: FsmMetricKind, fsm: &'static str, name: &'static str, update: U) {
+        let mut inner = self.inner.borrow_mut();
+        let inner = &mut *inner;
+        let existing = inner.metrics.iter().position(|m| matches!(m, Some(m) if m.kind == kind && m.fsm == fsm && m.name == name));
+        let slot = existing.or_else(|| inner.metrics.iter().position(|m| m.is_none()));
+        match slot {
+            Some(i) => update(inner.metrics[i].get_or_insert_with(|| FsmMetric::new(kind, fsm, name))),
+            None => inner.dropped += 1
+        }
+    }
+}
+
+/// Counts the events, transitions, guard results and misses into the [`FsmMetrics`], and with a clock,
+/// the time spent in the states.
+pub struct InspectMetrics<'a, C, const N: usize> {
+    metrics: &'a FsmMetrics<C, N>,
+    fsm: &'static str,
+    event: &'static str
+}
+
+impl<'a, C: FsmClock, const N: usize> Inspect for InspectMetrics<'a, C, N> {
+    fn new_event<F: FsmBackend>(&self, event: &FsmEvent<<F as FsmBackend>::Events>, _envelope: Option<&FsmEventEnvelope>) -> Self {
+        let fsm = short_type_name::<F>();
+        let event = match event {
+            FsmEvent::Start => "Fsm::Start",
+            FsmEvent::Stop => "Fsm::Stop",
+            FsmEvent::Event(ev) => F::event_name(ev)
+        };
+        self.metrics.update(FsmMetricKind::Event, fsm, event, |m| m.count += 1);
+
+        InspectMetrics { metrics: self.metrics, fsm, event }
+    }
+
+    fn event_done(self) {
+
+    }
+
+    fn for_region<F: FsmBackend>(&self, _region: FsmRegionId) -> Self {
+        InspectMetrics { ..*self }
+    }
+
+    fn for_transition<T>(&self) -> Self {
+        self.metrics.update(FsmMetricKind::Transition, self.fsm, short_type_name::<T>(), |m| m.count += 1);
+        InspectMetrics { ..*self }
+    }
+
+    fn for_sub_machine<FSub: FsmBackend>(&self) -> Self {
+        InspectMetrics { ..*self }
+    }
+
+    fn on_guard<T>(&self, guard_result: bool) {
+        self.metrics.update(FsmMetricKind::Guard, self.fsm, short_type_name::<T>(), |m| {
+            if guard_result {
+                m.count += 1;
+            } else {
+                m.rejected += 1;
+            }
+        });
+    }
+
+    fn on_state_enter<S>(&self) {
+        let now = self.metrics.clock.as_ref().map(|c| c.now());
+        self.metrics.update(FsmMetricKind::State, self.fsm, short_type_name::<S>(), |m| {
+            m.count += 1;
+            m.entered_at = now;
+        });
+    }
+
+    fn on_state_exit<S>(&self) {
+        let now = match self.metrics.clock {
+            Some(ref clock) => clock.now(),
+            None => return
+        };
+        let bounds = self.metrics.bounds;
+        self.metrics.update(FsmMetricKind::State, self.fsm, short_type_name::<S>(), |m| {
+            if let Some(entered_at) = m.entered_at.take() {
+                let time = now.saturating_sub(entered_at);
+                m.time_total += time;
+                let bucket = bounds.iter().position(|b| time <= *b).unwrap_or(bounds.len());
+                m.time_histogram[bucket] += 1;
+            }
+        });
+    }
+
+    fn on_no_transition(&self) {
+        self.metrics.update(FsmMetricKind::Event, self.fsm, self.event, |m| m.rejected += 1);
+    }
+}
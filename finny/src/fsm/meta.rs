@@ -0,0 +1,87 @@
+//! A static description of the machine's structure, generated by the procedural macro. Useful for
+//! rendering diagrams and documentation of the machine at runtime.
+
+use crate::FsmRegionId;
+
+/// Implemented by the procedural macro for every machine.
+pub trait FsmDescription {
+    /// The static description of this machine.
+    const META: &'static FsmMeta;
+
+    /// The states, events, regions and transitions of this machine.
+    fn describe() -> &'static FsmMeta {
+        Self::META
+    }
+}
+
+/// The states, events, regions and transitions of a machine. The items are listed in the
+/// order of their declaration in the builder.
+#[derive(Debug)]
+pub struct FsmMeta {
+    /// The machine's type name.
+    pub name: &'static str,
+    pub states: &'static [FsmStateMeta],
+    pub events: &'static [&'static str],
+    pub regions: &'static [FsmRegionMeta],
+    pub transitions: &'static [FsmTransitionMeta]
+}
+
+impl FsmMeta {
+    /// Find a state by its type name.
+    pub fn state(&self, name: &str) -> Option<&'static FsmStateMeta> {
+        self.states.iter().find(|s| s.name == name)
+    }
+
+    /// All the transitions within a region.
+    pub fn region_transitions(&self, region: FsmRegionId) -> impl Iterator<Item = &'static FsmTransitionMeta> {
+        self.transitions.iter().filter(move |t| t.region == region)
+    }
+}
+
+#[derive(Debug)]
+pub struct FsmStateMeta {
+    /// The state's type name.
+    pub name: &'static str,
+    /// The description of the machine, if this state is a sub-machine.
+    pub sub_machine: Option<&'static FsmMeta>
+}
+
+#[derive(Debug)]
+pub struct FsmRegionMeta {
+    pub name: &'static str,
+    /// The initial states, in the order in which their guards are evaluated.
+    pub initial_states: &'static [&'static str],
+    pub states: &'static [&'static str]
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FsmTransitionKindMeta {
+    /// A transition between two states, or from the stopped machine into an initial state.
+    Normal,
+    /// Triggers the state's exit and entry actions.
+    SelfTransition,
+    /// Doesn't trigger the state's exit and entry actions.
+    Internal
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FsmTransitionEventMeta {
+    Start,
+    Stop,
+    /// The event's type name.
+    Event(&'static str)
+}
+
+#[derive(Debug)]
+pub struct FsmTransitionMeta {
+    /// The generated type name of this transition.
+    pub name: &'static str,
+    pub region: FsmRegionId,
+    pub kind: FsmTransitionKindMeta,
+    pub event: FsmTransitionEventMeta,
+    /// The source state, `None` for the start transitions.
+    pub from: Option<&'static str>,
+    pub to: Option<&'static str>,
+    pub has_guard: bool,
+    pub has_action: bool
+}
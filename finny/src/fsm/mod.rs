@@ -5,20 +5,28 @@ mod events;
 mod fsm_impl;
 mod fsm_factory;
 mod queue;
+mod queue_causal;
 mod states;
 mod transitions;
 mod tests_fsm;
-mod inspect;
+pub(crate) mod inspect;
+mod inspect_chain;
+mod inspect_metrics;
 mod dispatch;
+mod meta;
 
 pub use self::events::*;
 pub use self::fsm_factory::*;
 pub use self::fsm_impl::*;
 pub use self::queue::*;
+pub use self::queue_causal::*;
 pub use self::states::*;
 pub use self::transitions::*;
 pub use self::inspect::*;
+pub use self::inspect_chain::*;
+pub use self::inspect_metrics::*;
 pub use self::dispatch::*;
+pub use self::meta::*;
 
 use crate::{bundled, lib::*};
 
@@ -28,7 +36,12 @@ pub type FsmResult<T> = Result<T, FsmError>;
 #[derive(Debug, PartialEq)]
 pub enum FsmError {
     NoTransition,
-    QueueOverCapacity
+    QueueOverCapacity,
+    /// All the initial states of a region are guarded, and none of the guards allowed the start.
+    /// The region stays stopped.
+    NoInitialState,
+    /// The state storage doesn't hold the state that the dispatch requires. A bug in the storage.
+    StateNotAllocated
 }
 
 pub type FsmDispatchResult = FsmResult<()>;
@@ -43,7 +56,27 @@ pub trait FsmBackend where Self: Sized {
     /// A tagged union type with all the supported events. This type has to support cloning to facilitate
     /// the dispatch into sub-machines and into multiple regions.
     type Events: AsRef<str> + Clone;
+    /// The names of the machine's regions, indexed by their `FsmRegionId`.
+    const REGION_NAMES: &'static [&'static str];
 
     fn dispatch_event<Q, I>(ctx: DispatchContext<Self, Q, I>, event: FsmEvent<Self::Events>) -> FsmDispatchResult
         where Q: FsmEventQueue<Self>, I: Inspect;
+
+    /// The name of the event's variant, the same as its `AsRef<str>`, but with a static lifetime.
+    fn event_name(event: &Self::Events) -> &'static str;
+
+    /// The event's data for the inspection, if the machine was built with `fsm.inspect_debug()`.
+    fn inspect_event(_event: &Self::Events) -> Option<&dyn Debug> {
+        None
+    }
+
+    /// The state's data for the inspection, if the machine was built with `fsm.inspect_debug()`. Sub-machines aren't included.
+    fn inspect_state(_states: &Self::States, _state: <Self::States as FsmStates<Self>>::StateKind) -> Option<&dyn Debug> {
+        None
+    }
+
+    /// The context's data for the inspection, if the machine was built with `fsm.inspect_debug()`.
+    fn inspect_context(_context: &Self::Context) -> Option<&dyn Debug> {
+        None
+    }
 }
\ No newline at end of file
@@ -7,11 +7,35 @@ pub trait FsmEventQueue<F: FsmBackend>: FsmEventQueueSender<F> {
     fn dequeue(&mut self) -> Option<<F as FsmBackend>::Events>;
     /// Number of messages to be dequeued.
     fn len(&self) -> usize;
+
+    /// The frontend is about to dispatch an event that didn't come from this queue.
+    fn begin_external_event(&mut self) { }
+    /// The frontend has run the external event to completion.
+    fn end_external_event(&mut self) { }
 }
 
 pub trait FsmEventQueueSender<F: FsmBackend> {
     /// Try to enqueue an event.
     fn enqueue<E: Into<<F as FsmBackend>::Events>>(&mut self, event: E) -> FsmResult<()>;
+
+    /// The envelope of the event that is being dispatched, if the queue tracks them.
+    fn current_envelope(&self) -> Option<FsmEventEnvelope> {
+        None
+    }
+}
+
+/// Links the dispatched events to the events whose actions enqueued them. Tracked by
+/// [`FsmEventQueueCausal`], other queues don't provide it.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FsmEventEnvelope {
+    /// Unique for every event that passed through the queue.
+    pub id: u64,
+    /// The event that was being dispatched when this one was enqueued. `None` for external events.
+    pub parent: Option<u64>,
+    /// The external event that started the chain.
+    pub root: u64,
+    /// The clock's time of the enqueue, or of the dispatch for the external events. `None` without a clock.
+    pub enqueued_at: Option<u64>
 }
 
 #[cfg(feature = "std")]
@@ -166,6 +190,10 @@ impl<'a, Q, F, FSub> FsmEventQueueSender<FSub> for FsmEventQueueSub<'a, Q, F, FS
     {
         self.parent.enqueue(event.into())
     }
+
+    fn current_envelope(&self) -> Option<FsmEventEnvelope> {
+        self.parent.current_envelope()
+    }
 }
 
 
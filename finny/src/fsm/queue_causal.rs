@@ -0,0 +1,159 @@
+use crate::lib::*;
+use crate::{FsmBackend, FsmClock, FsmError, FsmEventEnvelope, FsmEventQueue, FsmEventQueueSender, FsmNoClock, FsmResult};
+
+/// Wraps a queue and tracks the envelopes of its events, so that the inspection can link every
+/// dispatched event back to the event whose action enqueued it. The envelopes are kept in a ring
+/// of `N` entries next to the wrapped queue, enqueueing fails with `QueueOverCapacity` when it is full.
+pub struct FsmEventQueueCausal<F, Q, C = FsmNoClock, const N: usize = 64> {
+    queue: Q,
+    clock: Option<C>,
+    envelopes: [FsmEventEnvelope; N],
+    head: usize,
+    len: usize,
+    next_id: u64,
+    current: Option<FsmEventEnvelope>,
+    _fsm: PhantomData<F>
+}
+
+impl<F, Q, const N: usize> FsmEventQueueCausal<F, Q, FsmNoClock, N>
+    where F: FsmBackend, Q: FsmEventQueue<F>
+{
+    /// Tracks the IDs of the events, without the timestamps.
+    pub fn new(queue: Q) -> Self {
+        Self::build(queue, None)
+    }
+}
+
+impl<F, Q, C, const N: usize> FsmEventQueueCausal<F, Q, C, N>
+    where F: FsmBackend, Q: FsmEventQueue<F>, C: FsmClock
+{
+    /// Also stamps the envelopes with the clock's time.
+    pub fn with_clock(queue: Q, clock: C) -> Self {
+        Self::build(queue, Some(clock))
+    }
+
+    fn build(queue: Q, clock: Option<C>) -> Self {
+        FsmEventQueueCausal {
+            queue,
+            clock,
+            envelopes: [FsmEventEnvelope::default(); N],
+            head: 0,
+            len: 0,
+            next_id: 1,
+            current: None,
+            _fsm: PhantomData
+        }
+    }
+
+    /// The wrapped queue.
+    pub fn inner(&self) -> &Q {
+        &self.queue
+    }
+
+    fn envelope(&mut self, parent: Option<FsmEventEnvelope>) -> FsmEventEnvelope {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        FsmEventEnvelope {
+            id,
+            parent: parent.map(|p| p.id),
+            root: parent.map(|p| p.root).unwrap_or(id),
+            enqueued_at: self.clock.as_ref().map(|c| c.now())
+        }
+    }
+}
+
+impl<F, Q, C, const N: usize> FsmEventQueue<F> for FsmEventQueueCausal<F, Q, C, N>
+    where F: FsmBackend, Q: FsmEventQueue<F>, C: FsmClock
+{
+    fn dequeue(&mut self) -> Option<<F as FsmBackend>::Events> {
+        let event = self.queue.dequeue()?;
+        if self.len > 0 {
+            self.current = Some(self.envelopes[self.head]);
+            self.head = (self.head + 1) % N;
+            self.len -= 1;
+        } else {
+            // The event was enqueued into the wrapped queue directly, so it starts a chain of its own.
+            let envelope = self.envelope(None);
+            self.current = Some(envelope);
+        }
+        Some(event)
+    }
+
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn begin_external_event(&mut self) {
+        let envelope = self.envelope(None);
+        self.current = Some(envelope);
+    }
+
+    fn end_external_event(&mut self) {
+        self.current = None;
+    }
+}
+
+impl<F, Q, C, const N: usize> FsmEventQueueSender<F> for FsmEventQueueCausal<F, Q, C, N>
+    where F: FsmBackend, Q: FsmEventQueue<F>, C: FsmClock
+{
+    fn enqueue<E: Into<<F as FsmBackend>::Events>>(&mut self, event: E) -> FsmResult<()> {
+        if self.len == N {
+            return Err(FsmError::QueueOverCapacity);
+        }
+        self.queue.enqueue(event)?;
+
+        let envelope = self.envelope(self.current);
+        self.envelopes[(self.head + self.len) % N] = envelope;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn current_envelope(&self) -> Option<FsmEventEnvelope> {
+        self.current
+    }
+}
+
+#[cfg(test)]
+use super::tests_fsm::TestFsm;
+
+#[test]
+fn test_causal_envelopes() {
+    use super::tests_fsm::EventA;
+    use crate::FsmEventQueueArray;
+
+    let mut queue = FsmEventQueueCausal::<TestFsm, _, _, 4>::new(FsmEventQueueArray::<TestFsm, [_; 8]>::new());
+    assert_eq!(None, queue.current_envelope());
+
+    queue.begin_external_event();
+    let root = queue.current_envelope().unwrap();
+    assert_eq!(FsmEventEnvelope { id: 1, parent: None, root: 1, enqueued_at: None }, root);
+    queue.enqueue(EventA { n: 0 }).unwrap();
+    queue.enqueue(EventA { n: 1 }).unwrap();
+
+    queue.dequeue().unwrap();
+    assert_eq!(Some(FsmEventEnvelope { id: 2, parent: Some(1), root: 1, enqueued_at: None }), queue.current_envelope());
+    queue.enqueue(EventA { n: 2 }).unwrap();
+
+    queue.dequeue().unwrap();
+    assert_eq!(Some(FsmEventEnvelope { id: 3, parent: Some(1), root: 1, enqueued_at: None }), queue.current_envelope());
+    queue.dequeue().unwrap();
+    assert_eq!(Some(FsmEventEnvelope { id: 4, parent: Some(2), root: 1, enqueued_at: None }), queue.current_envelope());
+    queue.end_external_event();
+
+    for n in 0..4 {
+        queue.enqueue(EventA { n }).unwrap();
+    }
+    assert_eq!(Err(FsmError::QueueOverCapacity), queue.enqueue(EventA { n: 4 }));
+    assert_eq!(4, queue.len());
+    queue.dequeue().unwrap();
+    assert_eq!(Some(FsmEventEnvelope { id: 5, parent: None, root: 5, enqueued_at: None }), queue.current_envelope());
+
+    // An event enqueued into the wrapped queue directly starts a chain of its own.
+    let mut inner = FsmEventQueueArray::<TestFsm, [_; 8]>::new();
+    inner.enqueue(EventA { n: 0 }).unwrap();
+    let mut queue = FsmEventQueueCausal::<TestFsm, _, _, 4>::new(inner);
+    queue.begin_external_event();
+    queue.dequeue().unwrap();
+    assert_eq!(Some(FsmEventEnvelope { id: 2, parent: None, root: 2, enqueued_at: None }), queue.current_envelope());
+}
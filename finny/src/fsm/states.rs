@@ -55,6 +55,29 @@ impl<TState, TFsm> FsmStateFactory<TFsm> for TState where TState: Default, TFsm:
     }
 }
 
+/// Access to a single state within the machine's state storage, used by the transitions. The default
+/// storage keeps all of the states allocated for the whole lifetime of the machine, while the union
+/// storage (`fsm.state_storage_union()`) only holds the active state of each region.
+pub trait FsmStateStorage<TFsm, TState> where TFsm: FsmBackend {
+    /// The state, if it is currently allocated.
+    fn try_state_ref(&self) -> Option<&TState>;
+    /// The state, if it is currently allocated.
+    fn try_state_mut(&mut self) -> Option<&mut TState>;
+    /// Executed before the state is entered. The union storage and states that reset on entry construct the state here.
+    fn construct_state(&mut self, context: &<TFsm as FsmBackend>::Context) -> FsmResult<()>;
+    /// Executed after the state was exited by stopping the machine. The union storage drops the state here.
+    fn destruct_state(&mut self);
+}
+
+/// Executes a transition's action on both of its states. The target state is replaced with the one returned
+/// by `construct`, if any. The union storage constructs the target state before the action and replaces the
+/// source state with it afterwards.
+pub trait FsmStateTransitionStorage<TFsm, T1, T2> where TFsm: FsmBackend {
+    fn transition_states<C, A>(&mut self, context: &mut <TFsm as FsmBackend>::Context, construct: C, action: A) -> FsmResult<()>
+        where C: FnOnce(&<TFsm as FsmBackend>::Context, &T1) -> Option<T2>,
+              A: FnOnce(&mut <TFsm as FsmBackend>::Context, &mut T1, &mut T2);
+}
+
 /// Retrieve a pair of states as immutable references. Used in state transitions.
 pub trait FsmStateTransitionAsRef<T1, T2> {
     fn as_state_transition_ref(&self) -> (&T1, &T2);
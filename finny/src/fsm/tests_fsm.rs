@@ -41,6 +41,13 @@ impl FsmBackend for TestFsm {
     type Context = ();
     type States = States;
     type Events = Events;
+    const REGION_NAMES: &'static [&'static str] = &["Region0"];
+
+    fn event_name(event: &Self::Events) -> &'static str {
+        match event {
+            Events::EventA(_) => "EventA"
+        }
+    }
 
     fn dispatch_event<Q, I>(_ctx: crate::DispatchContext<Self, Q, I>, _event: crate::FsmEvent<Self::Events>) -> crate::FsmDispatchResult
         where Q: crate::FsmEventQueue<Self>,
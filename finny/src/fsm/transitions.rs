@@ -1,8 +1,18 @@
 //! All of these traits will be implemented by the procedural code generator.
 
-use crate::{FsmBackendImpl, FsmDispatchResult, FsmEventQueueSub, lib::*};
+use crate::{FsmBackendImpl, FsmDispatchResult, FsmError, FsmEventQueueSub, FsmResult, lib::*};
 
-use crate::{DispatchContext, EventContext, FsmBackend, FsmCurrentState, FsmEvent, FsmEventQueue, FsmFrontend, FsmRegionId, FsmStateTransitionAsMut, FsmStates, Inspect};
+use crate::{DispatchContext, EventContext, FsmBackend, FsmCurrentState, FsmEvent, FsmEventQueue, FsmEventQueueInspect, FsmFrontend, FsmRegionId, FsmStateStorage, FsmStateTransitionStorage, FsmStates, Inspect};
+
+/// The state's and the context's data for the inspection, if the machine provides them.
+fn inspect_state_data<F, S>(backend: &FsmBackendImpl<F>) -> Option<(&dyn Debug, &dyn Debug)>
+    where F: FsmBackend, S: FsmState<F>
+{
+    match (F::inspect_state(&backend.states, S::fsm_state()), F::inspect_context(&backend.context)) {
+        (Some(state), Some(context)) => Some((state, context)),
+        _ => None
+    }
+}
 
 /// A state's entry and exit actions.
 pub trait FsmState<F: FsmBackend> {
@@ -11,30 +21,61 @@ pub trait FsmState<F: FsmBackend> {
     /// Action that is executed whenever this state is being exited.
     fn on_exit<'a, Q: FsmEventQueue<F>>(&mut self, context: &mut EventContext<'a, F, Q>);
 
-    fn execute_on_entry<'a, 'b, 'c, 'd, Q, I>(context: &'d mut DispatchContext<'a, 'b, 'c, F, Q, I>, region: FsmRegionId) 
-        where Q: FsmEventQueue<F>, I: Inspect, <F as FsmBackend>::States: AsMut<Self>
+    fn execute_on_entry<'a, 'b, 'c, 'd, Q, I>(context: &'d mut DispatchContext<'a, 'b, 'c, F, Q, I>, region: FsmRegionId, inspect: &I) -> FsmResult<()>
+        where Q: FsmEventQueue<F>, I: Inspect, <F as FsmBackend>::States: FsmStateStorage<F, Self>, Self: Sized
     {
+        inspect.on_state_enter::<Self>();
+
+        let mut queue = FsmEventQueueInspect::new(context.queue, inspect);
         let mut event_context = EventContext {
             context: &mut context.backend.context,
             region,
-            queue: context.queue
+            queue: &mut queue
         };
 
-        let state: &mut Self = context.backend.states.as_mut();
+        let state = context.backend.states.try_state_mut().ok_or(FsmError::StateNotAllocated)?;
         state.on_entry(&mut event_context);
+
+        if let Some((state, ctx)) = inspect_state_data::<F, Self>(context.backend) {
+            inspect.on_state_enter_data::<Self>(state, ctx);
+        }
+
+        Ok(())
     }
 
-    fn execute_on_exit<'a, 'b, 'c, 'd, Q, I>(context: &'d mut DispatchContext<'a, 'b, 'c, F, Q, I>, region: FsmRegionId) 
-        where Q: FsmEventQueue<F>, I: Inspect, <F as FsmBackend>::States: AsMut<Self>
+    fn execute_on_exit<'a, 'b, 'c, 'd, Q, I>(context: &'d mut DispatchContext<'a, 'b, 'c, F, Q, I>, region: FsmRegionId, inspect: &I) -> FsmResult<()>
+        where Q: FsmEventQueue<F>, I: Inspect, <F as FsmBackend>::States: FsmStateStorage<F, Self>, Self: Sized
     {
+        inspect.on_state_exit::<Self>();
+
+        if let Some((state, ctx)) = inspect_state_data::<F, Self>(context.backend) {
+            inspect.on_state_exit_data::<Self>(state, ctx);
+        }
+
+        let mut queue = FsmEventQueueInspect::new(context.queue, inspect);
         let mut event_context = EventContext {
             context: &mut context.backend.context,
-            queue: context.queue,
+            queue: &mut queue,
             region
         };
 
-        let state: &mut Self = context.backend.states.as_mut();
+        let state = context.backend.states.try_state_mut().ok_or(FsmError::StateNotAllocated)?;
         state.on_exit(&mut event_context);
+
+        Ok(())
+    }
+
+    /// Exits this state when the machine is being stopped.
+    fn execute_on_stop<'a, 'b, 'c, 'd, Q, I>(context: &'d mut DispatchContext<'a, 'b, 'c, F, Q, I>, region: FsmRegionId, inspect_event_ctx: &mut I) -> FsmResult<()>
+        where Q: FsmEventQueue<F>, I: Inspect, <F as FsmBackend>::States: FsmStateStorage<F, Self>, Self: Sized
+    {
+        Self::execute_on_exit(context, region, inspect_event_ctx)?;
+        context.backend.states.destruct_state();
+
+        let cs = context.backend.current_states.as_mut();
+        cs[region] = FsmCurrentState::Stopped;
+
+        Ok(())
     }
 
     fn fsm_state() -> <<F as FsmBackend>::States as FsmStates<F>>::StateKind;
@@ -65,24 +106,47 @@ pub trait FsmTransitionGuard<F: FsmBackend, E> {
 
 /// The transition that starts the machine, triggered using the `start()` method.
 pub trait FsmTransitionFsmStart<F: FsmBackend, TInitialState> {
+    /// This action is executed once when the machine starts, just before the initial state's entry action.
+    fn action<'a, Q: FsmEventQueue<F>>(context: &mut EventContext<'a, F, Q>, state: &mut TInitialState);
+
     fn execute_transition<'a, 'b, 'c, 'd, Q: FsmEventQueue<F>, I >(context: &'d mut DispatchContext<'a, 'b, 'c, F, Q, I>, 
         _fsm_event: &FsmEvent<<F as FsmBackend>::Events>,
         region: FsmRegionId,
-        inspect_event_ctx: &mut I)
+        inspect_event_ctx: &mut I) -> FsmDispatchResult
         where
             I: Inspect,
             TInitialState: FsmState<F>,
-            <F as FsmBackend>::States: AsMut<TInitialState>,
-            <F as FsmBackend>::States: AsRef<TInitialState>,
+            <F as FsmBackend>::States: FsmStateStorage<F, TInitialState>,
             Self: Sized,
     {
         let ctx = inspect_event_ctx.for_transition::<Self>();
-        ctx.on_state_enter::<TInitialState>();
-        
-        <TInitialState>::execute_on_entry(context, region);
+
+        context.backend.states.construct_state(&context.backend.context)?;
+
+        // initial transition action
+        {
+            ctx.on_action::<Self>();
+
+            let mut queue = FsmEventQueueInspect::new(context.queue, &ctx);
+            let mut event_context = EventContext {
+                context: &mut context.backend.context,
+                queue: &mut queue,
+                region
+            };
+            let state = context.backend.states.try_state_mut().ok_or(FsmError::StateNotAllocated)?;
+            Self::action(&mut event_context, state);
+
+            if let Some(data) = F::inspect_context(&context.backend.context) {
+                ctx.on_action_data::<Self>(data);
+            }
+        }
+
+        <TInitialState>::execute_on_entry(context, region, &ctx)?;
         
         let cs = context.backend.current_states.as_mut();
         cs[region] = FsmCurrentState::State(<TInitialState>::fsm_state());
+
+        Ok(())
     }
 }
 
@@ -91,37 +155,49 @@ pub trait FsmTransitionAction<F: FsmBackend, E, TStateFrom, TStateTo> {
     /// This action is executed after the first state's exit event, and just before the second event's entry action. It can mutate both states.
     fn action<'a, Q: FsmEventQueue<F>>(event: &E, context: &mut EventContext<'a, F, Q>, from: &mut TStateFrom, to: &mut TStateTo);
 
+    /// Builds a new instance of the target state from the event, if the transition declares a constructor.
+    fn construct(event: &E, context: &<F as FsmBackend>::Context, from: &TStateFrom) -> Option<TStateTo>;
+
     fn execute_transition<'a, 'b, 'c, 'd, Q: FsmEventQueue<F>, I>(context: &'d mut DispatchContext<'a, 'b, 'c, F, Q, I>, event: &E, region: FsmRegionId, inspect_event_ctx: &mut I)
+        -> FsmDispatchResult
         where 
             I: Inspect,
-            <F as FsmBackend>::States: FsmStateTransitionAsMut<TStateFrom, TStateTo>,
-            <F as FsmBackend>::States: AsMut<TStateFrom>,
-            <F as FsmBackend>::States: AsMut<TStateTo>,
+            <F as FsmBackend>::States: FsmStateTransitionStorage<F, TStateFrom, TStateTo>,
+            <F as FsmBackend>::States: FsmStateStorage<F, TStateFrom>,
+            <F as FsmBackend>::States: FsmStateStorage<F, TStateTo>,
             TStateFrom: FsmState<F>,
             TStateTo: FsmState<F>, Self: Sized
     {
         let inspect_ctx = inspect_event_ctx.for_transition::<Self>();
 
-        <TStateFrom>::execute_on_exit(context, region);
+        <TStateFrom>::execute_on_exit(context, region, &inspect_ctx)?;
         
         // transition action
         {
             inspect_ctx.on_action::<Self>();
 
-            let mut event_context = EventContext {
-                context: &mut context.backend.context,
-                queue: context.queue,
-                region
-            };        
-            let states: (&mut TStateFrom, &mut TStateTo) = context.backend.states.as_state_transition_mut();
-            Self::action(event, &mut event_context, states.0, states.1);
+            let mut queue = FsmEventQueueInspect::new(context.queue, &inspect_ctx);
+            context.backend.states.transition_states(&mut context.backend.context, |ctx, from| Self::construct(event, ctx, from), |ctx, from, to| {
+                let mut event_context = EventContext {
+                    context: ctx,
+                    queue: &mut queue,
+                    region
+                };
+                Self::action(event, &mut event_context, from, to);
+            })?;
+
+            if let Some(data) = F::inspect_context(&context.backend.context) {
+                inspect_ctx.on_action_data::<Self>(data);
+            }
         }
         
 
-        <TStateTo>::execute_on_entry(context, region);
+        <TStateTo>::execute_on_entry(context, region, &inspect_ctx)?;
 
         let cs = context.backend.current_states.as_mut();
         cs[region] = FsmCurrentState::State(<TStateTo>::fsm_state());
+
+        Ok(())
     }
 
     /// Executed after the transition on the parent FSM (F) and triggers the first `start()` call if necessary. Subsequent
@@ -133,10 +209,10 @@ pub trait FsmTransitionAction<F: FsmBackend, E, TStateFrom, TStateTo> {
             Q: FsmEventQueue<F>,
             I: Inspect,
             <F as FsmBackend>::Events: From<<TStateTo as FsmBackend>::Events>,
-            <F as FsmBackend>::States: AsMut<TStateTo>,
+            <F as FsmBackend>::States: FsmStateStorage<F, TStateTo>,
             TStateTo: DerefMut<Target = FsmBackendImpl<TStateTo>>
     {
-        let sub_backend: &mut TStateTo = context.backend.states.as_mut();
+        let sub_backend: &mut TStateTo = context.backend.states.try_state_mut().ok_or(FsmError::StateNotAllocated)?;
         let states = sub_backend.get_current_states();
         if FsmCurrentState::all_stopped(states.as_ref()) {
             let mut queue_adapter = FsmEventQueueSub {
@@ -167,35 +243,47 @@ pub trait FsmAction<F: FsmBackend, E, State> {
     /// Is this a self transition which should trigger the state's exit and entry actions?
     fn should_trigger_state_actions() -> bool;
 
-    fn execute_action<'a, 'b, 'c, 'd, Q: FsmEventQueue<F>, I >(context: &'d mut DispatchContext<'a, 'b, 'c, F, Q, I>, event: &E, region: FsmRegionId)
-        where <F as FsmBackend>::States: AsMut<State>, I: Inspect
+    fn execute_action<'a, 'b, 'c, 'd, Q: FsmEventQueue<F>, I >(context: &'d mut DispatchContext<'a, 'b, 'c, F, Q, I>, event: &E, region: FsmRegionId, inspect: &I) -> FsmResult<()>
+        where <F as FsmBackend>::States: FsmStateStorage<F, State>, I: Inspect, Self: Sized
     {
+        inspect.on_action::<Self>();
+
+        let mut queue = FsmEventQueueInspect::new(context.queue, inspect);
         let mut event_context = EventContext {
             context: &mut context.backend.context,
-            queue: context.queue,
+            queue: &mut queue,
             region
         };
 
-        let state: &mut State = context.backend.states.as_mut();
-
+        let state = context.backend.states.try_state_mut().ok_or(FsmError::StateNotAllocated)?;
         Self::action(event, &mut event_context, state);
+
+        if let Some(data) = F::inspect_context(&context.backend.context) {
+            inspect.on_action_data::<Self>(data);
+        }
+
+        Ok(())
     }
 
     fn execute_transition<'a, 'b, 'c, 'd, Q: FsmEventQueue<F>, I>(context: &'d mut DispatchContext<'a, 'b, 'c, F, Q, I>, event: &E, region: FsmRegionId, inspect_event_ctx: &mut I)
+        -> FsmDispatchResult
         where I: Inspect,
             State: FsmState<F>,
-            <F as FsmBackend>::States: AsMut<State>, Self: Sized
+            <F as FsmBackend>::States: FsmStateStorage<F, State>, Self: Sized
     {
         let ctx = inspect_event_ctx.for_transition::<Self>();
 
         if Self::should_trigger_state_actions() {
-            <State>::execute_on_exit(context, region);
+            <State>::execute_on_exit(context, region, &ctx)?;
+            context.backend.states.construct_state(&context.backend.context)?;
         }
 
-        Self::execute_action(context, event, region);
+        Self::execute_action(context, event, region, &ctx)?;
 
         if Self::should_trigger_state_actions() {
-            <State>::execute_on_entry(context, region);
+            <State>::execute_on_entry(context, region, &ctx)?;
         }
+
+        Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,251 @@
+//! Writes the dispatch in the Chrome trace-event format, for Perfetto or `chrome://tracing`.
+//!
+//! Each dispatched event is a slice on its machine's track, and each region has a track of its own
+//! with a slice per event. The matched transitions are nested within the region's slices, with the
+//! exit actions, the transition's actions and the entry actions nested within them. Guards are
+//! slices that cover their evaluation. A sub-machine's dispatch is nested within the parent's
+//! slice. Event queueing, missed transitions and errors are instant events. With
+//! [`crate::FsmEventQueueCausal`], the event slices have the envelope's IDs in their arguments.
+//!
+//! The hooks only mark the start of the actions, so an action's slice ends when the next hook starts,
+//! or when the enclosing slice ends.
+
+use std::{cell::{Cell, RefCell}, io::Write, rc::Rc, time::Instant};
+
+use crate::{FsmBackend, FsmError, FsmEvent, FsmEventEnvelope, FsmRegionId, Inspect, fsm::inspect::short_type_name, inspect_json::push_json_str};
+use super::lib::*;
+
+/// A slice that is still open, written once it ends.
+struct Slice {
+    name: String,
+    cat: &'static str,
+    tid: usize,
+    start: f64,
+    args: Vec<(&'static str, String)>
+}
+
+struct ChromeShared<W> {
+    writer: RefCell<W>,
+    started: Instant,
+    written: Cell<bool>,
+    /// The names of the tracks, the thread ID is the index plus one.
+    tracks: RefCell<Vec<String>>
+}
+
+impl<W: Write> ChromeShared<W> {
+    fn now(&self) -> f64 {
+        self.started.elapsed().as_nanos() as f64 / 1000.0
+    }
+
+    fn track(&self, name: String) -> usize {
+        let mut tracks = self.tracks.borrow_mut();
+        if let Some(i) = tracks.iter().position(|t| *t == name) {
+            return i + 1;
+        }
+        tracks.push(name);
+        let tid = tracks.len();
+
+        let mut event = format!("{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":{},\"args\":{{\"name\":", tid);
+        push_json_str(&mut event, &tracks[tid - 1]);
+        event.push_str("}}");
+        self.write(&event);
+        tid
+    }
+
+    fn write(&self, event: &str) {
+        let mut writer = self.writer.borrow_mut();
+        let separator = if self.written.replace(true) { ",\n" } else { "[\n" };
+        let _ = writer.write_all(separator.as_bytes());
+        let _ = writer.write_all(event.as_bytes());
+    }
+
+    fn write_slice(&self, slice: Slice, end: f64) {
+        let mut event = String::with_capacity(128);
+        event.push_str("{\"name\":");
+        push_json_str(&mut event, &slice.name);
+        event.push_str(&format!(",\"cat\":\"{}\",\"ph\":\"X\",\"pid\":1,\"tid\":{},\"ts\":{:.3},\"dur\":{:.3}", slice.cat, slice.tid, slice.start, end - slice.start));
+        if !slice.args.is_empty() {
+            event.push_str(",\"args\":{");
+            for (i, (key, value)) in slice.args.iter().enumerate() {
+                if i > 0 {
+                    event.push(',');
+                }
+                push_json_str(&mut event, key);
+                event.push(':');
+                push_json_str(&mut event, value);
+            }
+            event.push('}');
+        }
+        event.push('}');
+        self.write(&event);
+    }
+
+    fn write_instant(&self, name: &str, cat: &'static str, tid: usize) {
+        let mut event = String::with_capacity(96);
+        event.push_str("{\"name\":");
+        push_json_str(&mut event, name);
+        event.push_str(&format!(",\"cat\":\"{}\",\"ph\":\"i\",\"s\":\"t\",\"pid\":1,\"tid\":{},\"ts\":{:.3}}}", cat, tid, self.now()));
+        self.write(&event);
+    }
+}
+
+/// Writes the Chrome trace-event JSON array format into the writer. Call [`InspectChromeTrace::finish`]
+/// to close the array, although the viewers also accept the trace without it. Write errors are ignored.
+pub struct InspectChromeTrace<W: Write> {
+    shared: Rc<ChromeShared<W>>,
+    fsm: &'static str,
+    tid: usize,
+    slice: RefCell<Option<Slice>>,
+    /// The action that started with the last hook and is assumed to last until the next one.
+    action: RefCell<Option<Slice>>,
+    /// The time of the last hook, where the next guard's evaluation started.
+    last: Cell<f64>
+}
+
+impl<W: Write> InspectChromeTrace<W> {
+    pub fn new(writer: W) -> Self {
+        let shared = ChromeShared {
+            writer: RefCell::new(writer),
+            started: Instant::now(),
+            written: Cell::new(false),
+            tracks: RefCell::new(vec![])
+        };
+        let now = shared.now();
+        InspectChromeTrace {
+            shared: Rc::new(shared),
+            fsm: "",
+            tid: 0,
+            slice: RefCell::new(None),
+            action: RefCell::new(None),
+            last: Cell::new(now)
+        }
+    }
+
+    /// Closes the JSON array and flushes the writer.
+    pub fn finish(&self) {
+        let mut writer = self.shared.writer.borrow_mut();
+        let _ = writer.write_all(if self.shared.written.get() { b"\n]\n" } else { b"[]\n" });
+        let _ = writer.flush();
+    }
+
+    /// Access to the underlying writer.
+    pub fn with_writer<R, T: FnOnce(&mut W) -> R>(&self, f: T) -> R {
+        f(&mut self.shared.writer.borrow_mut())
+    }
+
+    /// Ends the running action, and returns the current time.
+    fn mark(&self) -> f64 {
+        let now = self.shared.now();
+        if let Some(action) = self.action.borrow_mut().take() {
+            self.shared.write_slice(action, now);
+        }
+        self.last.set(now);
+        now
+    }
+
+    fn start_action(&self, name: String, cat: &'static str) {
+        let start = self.mark();
+        *self.action.borrow_mut() = Some(Slice { name, cat, tid: self.tid, start, args: vec![] });
+    }
+
+    fn child(&self, fsm: &'static str, tid: usize, name: String, cat: &'static str) -> Self {
+        let start = self.mark();
+        InspectChromeTrace {
+            shared: self.shared.clone(),
+            fsm,
+            tid,
+            slice: RefCell::new(Some(Slice { name, cat, tid, start, args: vec![] })),
+            action: RefCell::new(None),
+            last: Cell::new(start)
+        }
+    }
+
+    fn add_arg(&self, key: &'static str, value: String) {
+        if let Some(ref mut slice) = *self.slice.borrow_mut() {
+            slice.args.push((key, value));
+        }
+    }
+}
+
+impl<W: Write> Drop for InspectChromeTrace<W> {
+    fn drop(&mut self) {
+        let now = self.mark();
+        if let Some(slice) = self.slice.borrow_mut().take() {
+            self.shared.write_slice(slice, now);
+        }
+    }
+}
+
+impl<W: Write> Inspect for InspectChromeTrace<W> {
+    fn new_event<F: FsmBackend>(&self, event: &FsmEvent<<F as FsmBackend>::Events>, envelope: Option<&FsmEventEnvelope>) -> Self {
+        let fsm = short_type_name::<F>();
+        let tid = if self.slice.borrow().is_some() {
+            self.tid
+        } else {
+            self.shared.track(fsm.to_string())
+        };
+        let inspect = self.child(fsm, tid, event.as_ref().to_string(), "event");
+        if let Some(envelope) = envelope {
+            inspect.add_arg("event_id", envelope.id.to_string());
+            if let Some(parent) = envelope.parent {
+                inspect.add_arg("parent_id", parent.to_string());
+            }
+            inspect.add_arg("root_id", envelope.root.to_string());
+        }
+        inspect
+    }
+
+    fn event_done(self) {
+
+    }
+
+    fn for_region<F: FsmBackend>(&self, region: FsmRegionId) -> Self {
+        let tid = self.shared.track(format!("{} / {}", self.fsm, F::REGION_NAMES[region]));
+        let name = self.slice.borrow().as_ref().map(|s| s.name.clone()).unwrap_or_default();
+        self.child(self.fsm, tid, name, "region")
+    }
+
+    fn for_transition<T>(&self) -> Self {
+        self.child(self.fsm, self.tid, short_type_name::<T>().to_string(), "transition")
+    }
+
+    fn for_sub_machine<FSub: FsmBackend>(&self) -> Self {
+        self.child(self.fsm, self.tid, short_type_name::<FSub>().to_string(), "sub_machine")
+    }
+
+    fn on_guard<T>(&self, guard_result: bool) {
+        let start = self.last.get();
+        let end = self.mark();
+        let args = vec![("result", guard_result.to_string())];
+        self.shared.write_slice(Slice { name: format!("guard {}", short_type_name::<T>()), cat: "guard", tid: self.tid, start, args }, end);
+    }
+
+    fn on_state_enter<S>(&self) {
+        self.start_action(format!("enter {}", short_type_name::<S>()), "enter");
+    }
+
+    fn on_state_exit<S>(&self) {
+        self.start_action(format!("exit {}", short_type_name::<S>()), "exit");
+    }
+
+    fn on_action<S>(&self) {
+        self.start_action(format!("action {}", short_type_name::<S>()), "action");
+    }
+
+    fn on_event_data(&self, event: &dyn Debug) {
+        self.add_arg("data", format!("{:?}", event));
+    }
+
+    fn on_no_transition(&self) {
+        self.shared.write_instant("no transition", "no_transition", self.tid);
+    }
+
+    fn on_error(&self, error: &FsmError) {
+        self.add_arg("error", format!("{:?}", error));
+        self.shared.write_instant(&format!("error {:?}", error), "error", self.tid);
+    }
+
+    fn on_queue_enqueue<F: FsmBackend>(&self, event: &<F as FsmBackend>::Events) {
+        self.shared.write_instant(&format!("enqueue {}", event.as_ref()), "enqueue", self.tid);
+    }
+}
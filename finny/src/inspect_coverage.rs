@@ -0,0 +1,346 @@
+//! Transition coverage of the machines in a test suite. Combines the static description of the
+//! machines, generated by the procedural macro, with the transitions, guard results and states that
+//! were recorded while the tests were dispatching the events.
+//!
+//! ```rust
+//! use finny::{finny_fsm, FsmEventQueueVec, FsmFactory, FsmResult, decl::{BuiltFsm, FsmBuilder}, inspect_coverage::FsmCoverage};
+//!
+//! #[derive(Default)]
+//! pub struct Closed;
+//! #[derive(Default)]
+//! pub struct Opened;
+//! #[derive(Clone)]
+//! pub struct Open;
+//! #[derive(Clone)]
+//! pub struct Close;
+//!
+//! #[finny_fsm]
+//! fn build_fsm(mut fsm: FsmBuilder<Door, ()>) -> BuiltFsm {
+//!     fsm.initial_state::<Closed>();
+//!     fsm.state::<Closed>().on_event::<Open>().transition_to::<Opened>();
+//!     fsm.state::<Opened>().on_event::<Close>().transition_to::<Closed>();
+//!     fsm.build()
+//! }
+//!
+//! fn main() -> FsmResult<()> {
+//!     let coverage = FsmCoverage::new::<Door>();
+//!     let mut fsm = Door::new_with((), FsmEventQueueVec::new(), coverage.clone())?;
+//!     fsm.start()?;
+//!     fsm.dispatch(Open)?;
+//!
+//!     let report = coverage.report();
+//!     assert!(!report.is_complete());
+//!     assert_eq!(vec!["transition DoorTransition3 (Opened --Close--> Closed) never exercised"], report.uncovered());
+//!     Ok(())
+//! }
+//! ```
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{FsmBackend, FsmDescription, FsmEvent, FsmEventEnvelope, FsmMeta, FsmRegionId, FsmTransitionEventMeta, FsmTransitionKindMeta, FsmTransitionMeta, Inspect, fsm::inspect::short_type_name};
+use crate::inspect_json::push_json_str;
+use super::lib::*;
+
+#[derive(Default)]
+struct CoverageData {
+    machines: Vec<&'static FsmMeta>,
+    /// Keyed by the machine's and the transition's name.
+    transitions: HashMap<(&'static str, &'static str), usize>,
+    guards: HashMap<(&'static str, &'static str, bool), usize>,
+    states: HashMap<(&'static str, &'static str), usize>
+}
+
+impl CoverageData {
+    fn add_machine(&mut self, meta: &'static FsmMeta) {
+        if self.machines.iter().any(|m| m.name == meta.name) {
+            return;
+        }
+        self.machines.push(meta);
+        for state in meta.states {
+            if let Some(sub) = state.sub_machine {
+                self.add_machine(sub);
+            }
+        }
+    }
+}
+
+/// Records the coverage of the machines as their inspection. Clones share the recorded data, so keep
+/// one to get the report after the machine took the inspector.
+#[derive(Clone)]
+pub struct FsmCoverage {
+    data: Rc<RefCell<CoverageData>>,
+    fsm: &'static str
+}
+
+impl FsmCoverage {
+    /// Covers this machine, including its sub-machines.
+    pub fn new<F: FsmDescription>() -> Self {
+        let coverage = FsmCoverage {
+            data: Rc::new(RefCell::new(CoverageData::default())),
+            fsm: ""
+        };
+        coverage.with::<F>()
+    }
+
+    /// Also covers this machine, for sharing the coverage between several machines.
+    pub fn with<F: FsmDescription>(self) -> Self {
+        self.data.borrow_mut().add_machine(F::describe());
+        self
+    }
+
+    /// Forgets the recorded coverage, the machines are kept.
+    pub fn reset(&self) {
+        let mut data = self.data.borrow_mut();
+        data.transitions.clear();
+        data.guards.clear();
+        data.states.clear();
+    }
+
+    /// The coverage of every machine, in the order in which they were added.
+    pub fn report(&self) -> FsmCoverageReport {
+        let data = self.data.borrow();
+        let machines = data.machines.iter().map(|meta| {
+            let transitions = meta.transitions.iter().map(|transition| FsmTransitionCoverage {
+                transition,
+                taken: data.transitions.get(&(meta.name, transition.name)).copied().unwrap_or(0),
+                guard_passed: data.guards.get(&(meta.name, transition.name, true)).copied().unwrap_or(0),
+                guard_rejected: data.guards.get(&(meta.name, transition.name, false)).copied().unwrap_or(0)
+            }).collect();
+
+            let states = meta.states.iter().map(|state| FsmStateCoverage {
+                name: state.name,
+                entered: data.states.get(&(meta.name, state.name)).copied().unwrap_or(0)
+            }).collect();
+
+            FsmMachineCoverage { name: meta.name, transitions, states }
+        }).collect();
+
+        FsmCoverageReport { machines }
+    }
+
+    /// Panics with the list of the uncovered items, if there are any.
+    pub fn assert_complete(&self) {
+        let report = self.report();
+        if !report.is_complete() {
+            panic!("The machines aren't fully covered.\n\n{}", report.to_text());
+        }
+    }
+
+    fn scoped(&self, fsm: &'static str) -> Self {
+        FsmCoverage {
+            data: self.data.clone(),
+            fsm
+        }
+    }
+}
+
+/// The name of the machine, without the generic arguments, as in its description.
+fn machine_name<F>() -> &'static str {
+    let name = short_type_name::<F>();
+    &name[..name.find('<').unwrap_or(name.len())]
+}
+
+impl Inspect for FsmCoverage {
+    fn new_event<F: FsmBackend>(&self, _event: &FsmEvent<<F as FsmBackend>::Events>, _envelope: Option<&FsmEventEnvelope>) -> Self {
+        self.scoped(machine_name::<F>())
+    }
+
+    fn event_done(self) {
+
+    }
+
+    fn for_region<F: FsmBackend>(&self, _region: FsmRegionId) -> Self {
+        self.clone()
+    }
+
+    fn for_transition<T>(&self) -> Self {
+        *self.data.borrow_mut().transitions.entry((self.fsm, short_type_name::<T>())).or_insert(0) += 1;
+        self.clone()
+    }
+
+    fn for_sub_machine<FSub: FsmBackend>(&self) -> Self {
+        self.clone()
+    }
+
+    fn on_guard<T>(&self, guard_result: bool) {
+        *self.data.borrow_mut().guards.entry((self.fsm, short_type_name::<T>(), guard_result)).or_insert(0) += 1;
+    }
+
+    fn on_state_enter<S>(&self) {
+        *self.data.borrow_mut().states.entry((self.fsm, short_type_name::<S>())).or_insert(0) += 1;
+    }
+}
+
+/// The coverage of all the machines. The text rendering lists the uncovered items of each machine.
+#[derive(Debug)]
+pub struct FsmCoverageReport {
+    pub machines: Vec<FsmMachineCoverage>
+}
+
+#[derive(Debug)]
+pub struct FsmMachineCoverage {
+    pub name: &'static str,
+    /// In the order of the machine's description.
+    pub transitions: Vec<FsmTransitionCoverage>,
+    pub states: Vec<FsmStateCoverage>
+}
+
+#[derive(Debug)]
+pub struct FsmTransitionCoverage {
+    pub transition: &'static FsmTransitionMeta,
+    /// How many times the transition was taken.
+    pub taken: usize,
+    /// How many times the guard allowed the transition.
+    pub guard_passed: usize,
+    /// How many times the guard rejected the transition.
+    pub guard_rejected: usize
+}
+
+#[derive(Debug)]
+pub struct FsmStateCoverage {
+    pub name: &'static str,
+    /// How many times the state was entered.
+    pub entered: usize
+}
+
+impl FsmTransitionCoverage {
+    /// The transition's source, event and target, like `StateB --EventEnter--> internal`.
+    pub fn describe(&self) -> String {
+        let t = self.transition;
+        let from = t.from.unwrap_or("[*]");
+        let event = match t.event {
+            FsmTransitionEventMeta::Start => "Fsm::Start",
+            FsmTransitionEventMeta::Stop => "Fsm::Stop",
+            FsmTransitionEventMeta::Event(e) => e
+        };
+        let to = match t.kind {
+            FsmTransitionKindMeta::Internal => "internal",
+            FsmTransitionKindMeta::SelfTransition => "self",
+            FsmTransitionKindMeta::Normal => t.to.unwrap_or("[*]")
+        };
+        format!("{} --{}--> {}", from, event, to)
+    }
+}
+
+impl FsmMachineCoverage {
+    /// The covered and the total count of the transitions.
+    pub fn transitions_covered(&self) -> (usize, usize) {
+        (self.transitions.iter().filter(|t| t.taken > 0).count(), self.transitions.len())
+    }
+
+    /// The covered and the total count of the guard outcomes, two for every guarded transition.
+    pub fn guards_covered(&self) -> (usize, usize) {
+        let guarded = self.transitions.iter().filter(|t| t.transition.has_guard);
+        let covered = guarded.clone().map(|t| (t.guard_passed > 0) as usize + (t.guard_rejected > 0) as usize).sum();
+        (covered, guarded.count() * 2)
+    }
+
+    /// The covered and the total count of the states.
+    pub fn states_covered(&self) -> (usize, usize) {
+        (self.states.iter().filter(|s| s.entered > 0).count(), self.states.len())
+    }
+
+    /// A line for every transition that was never taken, every guard outcome that never happened and
+    /// every state that was never entered.
+    pub fn uncovered(&self) -> Vec<String> {
+        let mut lines = vec![];
+        for t in &self.transitions {
+            if t.taken == 0 {
+                lines.push(format!("transition {} ({}) never exercised", t.transition.name, t.describe()));
+            }
+            if t.transition.has_guard {
+                for (count, outcome) in [(t.guard_passed, true), (t.guard_rejected, false)] {
+                    if count == 0 {
+                        lines.push(format!("guard of transition {} ({}) never evaluated to {}", t.transition.name, t.describe(), outcome));
+                    }
+                }
+            }
+        }
+        for s in &self.states {
+            if s.entered == 0 {
+                lines.push(format!("state {} never entered", s.name));
+            }
+        }
+        lines
+    }
+}
+
+impl FsmCoverageReport {
+    /// Whether every transition, guard outcome and state was covered.
+    pub fn is_complete(&self) -> bool {
+        self.machines.iter().all(|m| m.uncovered().is_empty())
+    }
+
+    /// The uncovered items of all the machines.
+    pub fn uncovered(&self) -> Vec<String> {
+        self.machines.iter().flat_map(|m| m.uncovered()).collect()
+    }
+
+    /// A summary line for every machine, followed by its uncovered items.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for m in &self.machines {
+            let (t, t_total) = m.transitions_covered();
+            let (g, g_total) = m.guards_covered();
+            let (s, s_total) = m.states_covered();
+            text.push_str(&format!("{}: {}/{} transitions, {}/{} guard outcomes, {}/{} states\n", m.name, t, t_total, g, g_total, s, s_total));
+            for line in m.uncovered() {
+                text.push_str("  ");
+                text.push_str(&line);
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// The full report as a JSON object, with the counts of every transition and state.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\"machines\":[");
+        for (i, m) in self.machines.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str("{\"name\":");
+            push_json_str(&mut json, m.name);
+            let (t, t_total) = m.transitions_covered();
+            let (g, g_total) = m.guards_covered();
+            let (s, s_total) = m.states_covered();
+            json.push_str(&format!(",\"transitions_covered\":{},\"transitions_total\":{},\"guards_covered\":{},\"guards_total\":{},\"states_covered\":{},\"states_total\":{}", t, t_total, g, g_total, s, s_total));
+
+            json.push_str(",\"transitions\":[");
+            for (j, t) in m.transitions.iter().enumerate() {
+                if j > 0 {
+                    json.push(',');
+                }
+                json.push_str("{\"name\":");
+                push_json_str(&mut json, t.transition.name);
+                json.push_str(",\"description\":");
+                push_json_str(&mut json, &t.describe());
+                json.push_str(&format!(",\"taken\":{}", t.taken));
+                if t.transition.has_guard {
+                    json.push_str(&format!(",\"guard_passed\":{},\"guard_rejected\":{}", t.guard_passed, t.guard_rejected));
+                }
+                json.push('}');
+            }
+
+            json.push_str("],\"states\":[");
+            for (j, s) in m.states.iter().enumerate() {
+                if j > 0 {
+                    json.push(',');
+                }
+                json.push_str("{\"name\":");
+                push_json_str(&mut json, s.name);
+                json.push_str(&format!(",\"entered\":{}}}", s.entered));
+            }
+            json.push_str("]}");
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+impl fmt::Display for FsmCoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_text())
+    }
+}
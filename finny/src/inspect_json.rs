@@ -0,0 +1,230 @@
+//! Writes the dispatch as a trace of JSON lines, one object per inspection hook, for external tools.
+//!
+//! Every line has these fields:
+//! * `ts_us`: microseconds since the inspector was created, from a monotonic clock
+//! * `id`: the correlation ID of the event, shared by all the lines of its dispatch, including the
+//!   dispatch into the sub-machines
+//! * `depth`: the nesting depth. The event is at depth 0, its regions at 1 and the transitions at 2.
+//!   A sub-machine's event is nested one level deeper than its `sub_machine` line.
+//! * `fsm`: the type name of the machine that handles the hook
+//! * `hook`: one of `event`, `region`, `transition`, `sub_machine`, `guard`, `exit`, `action`, `enter`,
+//!   `event_data`, `exit_data`, `enter_data`, `action_data`, `no_transition`, `error`, `enqueue` and `done`
+//!
+//! The hooks add these fields, where they apply: `event`, `region`, `name` (the transition's, the
+//! sub-machine's, the state's or the guarding transition's type name), `result` (the guard's result),
+//! `data` and `context` (the `Debug` output, with `fsm.inspect_debug()`) and `error`. With
+//! [`crate::FsmEventQueueCausal`], the `event` line also has the envelope's `event_id`, `root_id`, and
+//! where they apply, `parent_id` and `enqueued_at`.
+
+use std::{cell::{Cell, RefCell}, io::Write, rc::Rc, time::Instant};
+
+use crate::{FsmBackend, FsmError, FsmEvent, FsmEventEnvelope, FsmRegionId, Inspect};
+use super::lib::*;
+
+struct JsonShared<W> {
+    writer: RefCell<W>,
+    started: Instant,
+    next_id: Cell<u64>
+}
+
+/// Writes each inspection hook as a line of JSON. Write errors are ignored, so that they don't
+/// disturb the machine. The writer is flushed after each top-level event.
+pub struct InspectJsonLines<W: Write> {
+    shared: Rc<JsonShared<W>>,
+    fsm: &'static str,
+    event_id: Option<u64>,
+    depth: usize
+}
+
+impl<W: Write> InspectJsonLines<W> {
+    pub fn new(writer: W) -> Self {
+        InspectJsonLines {
+            shared: Rc::new(JsonShared {
+                writer: RefCell::new(writer),
+                started: Instant::now(),
+                next_id: Cell::new(1)
+            }),
+            fsm: "",
+            event_id: None,
+            depth: 0
+        }
+    }
+
+    /// Access to the underlying writer.
+    pub fn with_writer<R, T: FnOnce(&mut W) -> R>(&self, f: T) -> R {
+        f(&mut self.shared.writer.borrow_mut())
+    }
+
+    fn nested(&self, depth: usize) -> Self {
+        InspectJsonLines {
+            shared: self.shared.clone(),
+            fsm: self.fsm,
+            event_id: self.event_id,
+            depth
+        }
+    }
+
+    fn line(&self, hook: &str) -> JsonLine<'_, W> {
+        let ts = self.shared.started.elapsed().as_micros();
+        let mut line = JsonLine { inspect: self, buf: String::with_capacity(128) };
+        line.buf.push_str(&format!("{{\"ts_us\":{},\"id\":{},\"depth\":{}", ts, self.event_id.unwrap_or(0), self.depth));
+        line.str("fsm", self.fsm).str("hook", hook)
+    }
+}
+
+impl<W: Write> Clone for InspectJsonLines<W> {
+    fn clone(&self) -> Self {
+        self.nested(self.depth)
+    }
+}
+
+/// Appends the string as a quoted and escaped JSON string.
+pub(crate) fn push_json_str(buf: &mut String, value: &str) {
+    buf.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            c if (c as u32) < 0x20 => buf.push_str(&format!("\\u{:04x}", c as u32)),
+            c => buf.push(c)
+        }
+    }
+    buf.push('"');
+}
+
+/// A single line that is being built.
+struct JsonLine<'a, W: Write> {
+    inspect: &'a InspectJsonLines<W>,
+    buf: String
+}
+
+impl<'a, W: Write> JsonLine<'a, W> {
+    fn str(mut self, key: &str, value: &str) -> Self {
+        self.buf.push(',');
+        push_json_str(&mut self.buf, key);
+        self.buf.push(':');
+        push_json_str(&mut self.buf, value);
+        self
+    }
+
+    fn debug(self, key: &str, value: &dyn Debug) -> Self {
+        self.str(key, &format!("{:?}", value))
+    }
+
+    fn bool(mut self, key: &str, value: bool) -> Self {
+        self.buf.push_str(&format!(",\"{}\":{}", key, value));
+        self
+    }
+
+    fn num(mut self, key: &str, value: u64) -> Self {
+        self.buf.push_str(&format!(",\"{}\":{}", key, value));
+        self
+    }
+
+    fn write(mut self) {
+        self.buf.push_str("}\n");
+        let _ = self.inspect.shared.writer.borrow_mut().write_all(self.buf.as_bytes());
+    }
+}
+
+impl<W: Write> Inspect for InspectJsonLines<W> {
+    fn new_event<F: FsmBackend>(&self, event: &FsmEvent<<F as FsmBackend>::Events>, envelope: Option<&FsmEventEnvelope>) -> Self {
+        let (event_id, depth) = match self.event_id {
+            Some(id) => (id, self.depth + 1),
+            None => {
+                let id = self.shared.next_id.get();
+                self.shared.next_id.set(id + 1);
+                (id, 0)
+            }
+        };
+        let inspect = InspectJsonLines {
+            shared: self.shared.clone(),
+            fsm: type_name::<F>(),
+            event_id: Some(event_id),
+            depth
+        };
+        let mut line = inspect.line("event").str("event", event.as_ref());
+        if let Some(envelope) = envelope {
+            line = line.num("event_id", envelope.id).num("root_id", envelope.root);
+            if let Some(parent) = envelope.parent {
+                line = line.num("parent_id", parent);
+            }
+            if let Some(enqueued_at) = envelope.enqueued_at {
+                line = line.num("enqueued_at", enqueued_at);
+            }
+        }
+        line.write();
+        inspect
+    }
+
+    fn event_done(self) {
+        self.line("done").write();
+        if self.depth == 0 {
+            let _ = self.shared.writer.borrow_mut().flush();
+        }
+    }
+
+    fn for_region<F: FsmBackend>(&self, region: FsmRegionId) -> Self {
+        let inspect = self.nested(self.depth + 1);
+        inspect.line("region").str("region", F::REGION_NAMES[region]).write();
+        inspect
+    }
+
+    fn for_transition<T>(&self) -> Self {
+        let inspect = self.nested(self.depth + 1);
+        inspect.line("transition").str("name", type_name::<T>()).write();
+        inspect
+    }
+
+    fn for_sub_machine<FSub: FsmBackend>(&self) -> Self {
+        self.line("sub_machine").str("name", type_name::<FSub>()).write();
+        self.nested(self.depth)
+    }
+
+    fn on_guard<T>(&self, guard_result: bool) {
+        self.line("guard").str("name", type_name::<T>()).bool("result", guard_result).write();
+    }
+
+    fn on_state_enter<S>(&self) {
+        self.line("enter").str("name", type_name::<S>()).write();
+    }
+
+    fn on_state_exit<S>(&self) {
+        self.line("exit").str("name", type_name::<S>()).write();
+    }
+
+    fn on_action<S>(&self) {
+        self.line("action").str("name", type_name::<S>()).write();
+    }
+
+    fn on_event_data(&self, event: &dyn Debug) {
+        self.line("event_data").debug("data", event).write();
+    }
+
+    fn on_state_enter_data<S>(&self, state: &dyn Debug, context: &dyn Debug) {
+        self.line("enter_data").str("name", type_name::<S>()).debug("data", state).debug("context", context).write();
+    }
+
+    fn on_state_exit_data<S>(&self, state: &dyn Debug, context: &dyn Debug) {
+        self.line("exit_data").str("name", type_name::<S>()).debug("data", state).debug("context", context).write();
+    }
+
+    fn on_action_data<T>(&self, context: &dyn Debug) {
+        self.line("action_data").str("name", type_name::<T>()).debug("context", context).write();
+    }
+
+    fn on_no_transition(&self) {
+        self.line("no_transition").write();
+    }
+
+    fn on_error(&self, error: &FsmError) {
+        self.line("error").debug("error", error).write();
+    }
+
+    fn on_queue_enqueue<F: FsmBackend>(&self, event: &<F as FsmBackend>::Events) {
+        self.line("enqueue").str("event", event.as_ref()).write();
+    }
+}
@@ -0,0 +1,317 @@
+//! Records the dispatched events into a trace, for asserting the behaviour of the machines in tests.
+//!
+//! ```rust
+//! use finny::{assert_trace, finny_fsm, FsmEventQueueVec, FsmFactory, FsmResult, decl::{BuiltFsm, FsmBuilder}, inspect_recorder::InspectRecorder};
+//!
+//! #[derive(Default)]
+//! pub struct Closed;
+//! #[derive(Default)]
+//! pub struct Opened;
+//! #[derive(Clone)]
+//! pub struct Open;
+//!
+//! #[finny_fsm]
+//! fn build_fsm(mut fsm: FsmBuilder<Door, ()>) -> BuiltFsm {
+//!     fsm.initial_state::<Closed>();
+//!     fsm.state::<Closed>().on_event::<Open>().transition_to::<Opened>();
+//!     fsm.state::<Opened>();
+//!     fsm.build()
+//! }
+//!
+//! fn main() -> FsmResult<()> {
+//!     let recorder = InspectRecorder::new();
+//!     let mut fsm = Door::new_with((), FsmEventQueueVec::new(), recorder.clone())?;
+//!     fsm.start()?;
+//!     recorder.take();
+//!
+//!     fsm.dispatch(Open)?;
+//!     assert_trace!(recorder, [Event(Open), Exit(Closed), Action(..), Enter(Opened), Done]);
+//!     Ok(())
+//! }
+//! ```
+
+use std::{cell::RefCell, env, fs, path::Path, rc::Rc};
+
+use crate::{FsmBackend, FsmError, FsmEvent, FsmEventEnvelope, FsmRegionId, Inspect, short_type_name};
+use super::lib::*;
+
+/// A single recorded step of the dispatch. The types are named without their module paths.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FsmTraceEntry {
+    /// The start of an event's dispatch, with the event's name. Sub-machines record their own events.
+    Event(String),
+    /// The transition whose guard was evaluated, and the result.
+    Guard(String, bool),
+    Exit(String),
+    /// The transition whose action was executed.
+    Action(String),
+    Enter(String),
+    /// The event is being dispatched to this sub-machine.
+    SubMachine(String),
+    /// An action enqueued this event.
+    Enqueue(String),
+    NoTransition,
+    Error(String),
+    /// The end of an event's dispatch.
+    Done
+}
+
+impl FsmTraceEntry {
+    /// The name of the variant, as used by the `assert_trace!` patterns.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FsmTraceEntry::Event(_) => "Event",
+            FsmTraceEntry::Guard(_, _) => "Guard",
+            FsmTraceEntry::Exit(_) => "Exit",
+            FsmTraceEntry::Action(_) => "Action",
+            FsmTraceEntry::Enter(_) => "Enter",
+            FsmTraceEntry::SubMachine(_) => "SubMachine",
+            FsmTraceEntry::Enqueue(_) => "Enqueue",
+            FsmTraceEntry::NoTransition => "NoTransition",
+            FsmTraceEntry::Error(_) => "Error",
+            FsmTraceEntry::Done => "Done"
+        }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            FsmTraceEntry::Event(n) | FsmTraceEntry::Guard(n, _) | FsmTraceEntry::Exit(n) | FsmTraceEntry::Action(n) |
+            FsmTraceEntry::Enter(n) | FsmTraceEntry::SubMachine(n) | FsmTraceEntry::Enqueue(n) | FsmTraceEntry::Error(n) => Some(n),
+            FsmTraceEntry::NoTransition | FsmTraceEntry::Done => None
+        }
+    }
+}
+
+impl fmt::Display for FsmTraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FsmTraceEntry::Event(n) => write!(f, "event {}", n),
+            FsmTraceEntry::Guard(n, result) => write!(f, "guard {} {}", n, result),
+            FsmTraceEntry::Exit(n) => write!(f, "exit {}", n),
+            FsmTraceEntry::Action(n) => write!(f, "action {}", n),
+            FsmTraceEntry::Enter(n) => write!(f, "enter {}", n),
+            FsmTraceEntry::SubMachine(n) => write!(f, "sub_machine {}", n),
+            FsmTraceEntry::Enqueue(n) => write!(f, "enqueue {}", n),
+            FsmTraceEntry::NoTransition => write!(f, "no_transition"),
+            FsmTraceEntry::Error(n) => write!(f, "error {}", n),
+            FsmTraceEntry::Done => write!(f, "done")
+        }
+    }
+}
+
+/// The trace as text, one entry per line. The entries within an event's dispatch are indented,
+/// which shows the nesting of the sub-machines.
+pub fn trace_to_text(trace: &[FsmTraceEntry]) -> String {
+    let mut text = String::new();
+    let mut depth = 0usize;
+    for entry in trace {
+        if let FsmTraceEntry::Done = entry {
+            depth = depth.saturating_sub(1);
+        }
+        for _ in 0..depth {
+            text.push_str("  ");
+        }
+        text.push_str(&entry.to_string());
+        text.push('\n');
+        if let FsmTraceEntry::Event(_) = entry {
+            depth += 1;
+        }
+    }
+    text
+}
+
+/// Records every dispatch into a shared trace. Clone it before handing it to the machine, and use the
+/// clone to read the trace.
+#[derive(Clone, Default)]
+pub struct InspectRecorder {
+    trace: Rc<RefCell<Vec<FsmTraceEntry>>>
+}
+
+impl InspectRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn record(&self, entry: FsmTraceEntry) {
+        self.trace.borrow_mut().push(entry);
+    }
+
+    /// A copy of the recorded trace.
+    pub fn trace(&self) -> Vec<FsmTraceEntry> {
+        self.trace.borrow().clone()
+    }
+
+    /// Returns the recorded trace and clears it.
+    pub fn take(&self) -> Vec<FsmTraceEntry> {
+        self.trace.borrow_mut().drain(..).collect()
+    }
+
+    /// The recorded trace, formatted with [`trace_to_text`].
+    pub fn to_text(&self) -> String {
+        trace_to_text(&self.trace.borrow())
+    }
+
+    /// Compares the recorded trace with the golden file, relative to the working directory. With the
+    /// `FINNY_BLESS` environment variable set, the file is written instead.
+    pub fn assert_golden<P: AsRef<Path>>(&self, path: P) {
+        let path = path.as_ref();
+        let actual = self.to_text();
+
+        if env::var_os("FINNY_BLESS").is_some() {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).expect("Can't create the golden trace's directory");
+            }
+            fs::write(path, &actual).expect("Can't write the golden trace");
+            return;
+        }
+
+        let expected = match fs::read_to_string(path) {
+            Ok(expected) => expected,
+            Err(e) => panic!("Can't read the golden trace {}: {}. Run with FINNY_BLESS=1 to create it.", path.display(), e)
+        };
+        if expected.replace("\r\n", "\n") != actual {
+            panic!("The trace doesn't match the golden trace {}. Run with FINNY_BLESS=1 to update it.\n\nExpected:\n{}\nActual:\n{}", path.display(), expected, actual);
+        }
+    }
+}
+
+impl Inspect for InspectRecorder {
+    fn new_event<F: FsmBackend>(&self, event: &FsmEvent<<F as FsmBackend>::Events>, _envelope: Option<&FsmEventEnvelope>) -> Self {
+        self.record(FsmTraceEntry::Event(event.as_ref().to_string()));
+        self.clone()
+    }
+
+    fn event_done(self) {
+        self.record(FsmTraceEntry::Done);
+    }
+
+    fn for_region<F: FsmBackend>(&self, _region: FsmRegionId) -> Self {
+        self.clone()
+    }
+
+    fn for_transition<T>(&self) -> Self {
+        self.clone()
+    }
+
+    fn for_sub_machine<FSub: FsmBackend>(&self) -> Self {
+        self.record(FsmTraceEntry::SubMachine(short_type_name::<FSub>().to_string()));
+        self.clone()
+    }
+
+    fn on_guard<T>(&self, guard_result: bool) {
+        self.record(FsmTraceEntry::Guard(short_type_name::<T>().to_string(), guard_result));
+    }
+
+    fn on_state_enter<S>(&self) {
+        self.record(FsmTraceEntry::Enter(short_type_name::<S>().to_string()));
+    }
+
+    fn on_state_exit<S>(&self) {
+        self.record(FsmTraceEntry::Exit(short_type_name::<S>().to_string()));
+    }
+
+    fn on_action<S>(&self) {
+        self.record(FsmTraceEntry::Action(short_type_name::<S>().to_string()));
+    }
+
+    fn on_no_transition(&self) {
+        self.record(FsmTraceEntry::NoTransition);
+    }
+
+    fn on_error(&self, error: &FsmError) {
+        self.record(FsmTraceEntry::Error(format!("{:?}", error)));
+    }
+
+    fn on_queue_enqueue<F: FsmBackend>(&self, event: &<F as FsmBackend>::Events) {
+        self.record(FsmTraceEntry::Enqueue(event.as_ref().to_string()));
+    }
+}
+
+/// An expected trace entry, built by `assert_trace!`. Missing names and guard results match anything.
+#[derive(Clone, Debug)]
+pub struct FsmTracePattern {
+    pub kind: &'static str,
+    pub name: Option<String>,
+    pub result: Option<bool>
+}
+
+impl FsmTracePattern {
+    pub fn matches(&self, entry: &FsmTraceEntry) -> bool {
+        if entry.kind() != self.kind {
+            return false;
+        }
+        if let Some(ref name) = self.name {
+            if entry.name() != Some(name.as_str()) {
+                return false;
+            }
+        }
+        match (self.result, entry) {
+            (Some(expected), FsmTraceEntry::Guard(_, result)) => expected == *result,
+            _ => true
+        }
+    }
+}
+
+impl fmt::Display for FsmTracePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let "NoTransition" | "Done" = self.kind {
+            return write!(f, "{}", self.kind);
+        }
+        write!(f, "{}({}", self.kind, self.name.as_deref().unwrap_or(".."))?;
+        if let Some(result) = self.result {
+            write!(f, ", {}", result)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Panics unless every entry of the trace matches the pattern at the same position.
+pub fn assert_trace_matches(trace: &[FsmTraceEntry], expected: &[FsmTracePattern]) {
+    let matches = trace.len() == expected.len() && trace.iter().zip(expected).all(|(entry, pattern)| pattern.matches(entry));
+    if !matches {
+        let expected: Vec<_> = expected.iter().map(|p| p.to_string()).collect();
+        panic!("The trace doesn't match.\n\nExpected:\n{}\n\nActual:\n{}", expected.join("\n"), trace_to_text(trace));
+    }
+}
+
+/// Asserts that the trace recorded since the last `take()` matches the entries exactly, and clears it.
+///
+/// The entries are written as `Kind(Type)`, `Kind("name")` or `Kind(..)` to match any name. Guards can
+/// also check the result, `Guard(.., true)`. `NoTransition` and `Done` have no arguments.
+#[macro_export]
+macro_rules! assert_trace {
+    ($recorder:expr, [ $( $kind:ident $( ( $($arg:tt)* ) )? ),* $(,)? ]) => {{
+        let trace = $recorder.take();
+        let expected = vec![ $( $crate::__finny_trace_pattern!($kind $( ( $($arg)* ) )? ) ),* ];
+        $crate::inspect_recorder::assert_trace_matches(&trace, &expected);
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __finny_trace_pattern {
+    ($kind:ident) => {
+        $crate::__finny_trace_pattern!(@new $kind, None, None)
+    };
+    ($kind:ident (..)) => {
+        $crate::__finny_trace_pattern!(@new $kind, None, None)
+    };
+    ($kind:ident (.., $result:expr)) => {
+        $crate::__finny_trace_pattern!(@new $kind, None, Some($result))
+    };
+    ($kind:ident ($name:literal)) => {
+        $crate::__finny_trace_pattern!(@new $kind, Some(String::from($name)), None)
+    };
+    ($kind:ident ($name:literal, $result:expr)) => {
+        $crate::__finny_trace_pattern!(@new $kind, Some(String::from($name)), Some($result))
+    };
+    ($kind:ident ($ty:ty)) => {
+        $crate::__finny_trace_pattern!(@new $kind, Some($crate::short_type_name::<$ty>().to_string()), None)
+    };
+    ($kind:ident ($ty:ty, $result:expr)) => {
+        $crate::__finny_trace_pattern!(@new $kind, Some($crate::short_type_name::<$ty>().to_string()), Some($result))
+    };
+    (@new $kind:ident, $name:expr, $result:expr) => {
+        $crate::inspect_recorder::FsmTracePattern { kind: stringify!($kind), name: $name, result: $result }
+    };
+}
@@ -1,5 +1,5 @@
 use slog::{info, o};
-use crate::{FsmBackend, FsmEvent, Inspect};
+use crate::{FsmBackend, FsmError, FsmEvent, FsmEventEnvelope, FsmRegionId, Inspect};
 use super::lib::*;
 use AsRef;
 
@@ -16,12 +16,22 @@ impl InspectSlog {
 }
 
 impl Inspect for InspectSlog {
-    fn new_event<F: FsmBackend>(&self, event: &FsmEvent<<F as FsmBackend>::Events>) -> Self {
+    fn new_event<F: FsmBackend>(&self, event: &FsmEvent<<F as FsmBackend>::Events>, envelope: Option<&FsmEventEnvelope>) -> Self {
         let event = event.as_ref().to_string();
-        let kv = o!("event" => event);
-        info!(self.logger, "Dispatching"; &kv);
+        let logger = match envelope {
+            Some(envelope) => self.logger.new(o!("event" => event, "event_id" => envelope.id, "parent_id" => envelope.parent, "root_id" => envelope.root)),
+            None => self.logger.new(o!("event" => event))
+        };
+        info!(logger, "Dispatching");
         InspectSlog {
-            logger: self.logger.new(kv)
+            logger
+        }
+    }
+
+    fn for_region<F: FsmBackend>(&self, region: FsmRegionId) -> Self {
+        let region = F::REGION_NAMES[region];
+        InspectSlog {
+            logger: self.logger.new(o!("region" => region))
         }
     }
 
@@ -63,6 +73,23 @@ impl Inspect for InspectSlog {
         info!(self.logger, "Executing {action}", action = action);
     }
 
+    fn on_event_data(&self, event: &dyn Debug) {
+        info!(self.logger, "Event data: {:?}", event);
+    }
+
+    fn on_no_transition(&self) {
+        info!(self.logger, "No transition matched the event");
+    }
+
+    fn on_error(&self, error: &FsmError) {
+        info!(self.logger, "Dispatch failed: {:?}", error);
+    }
+
+    fn on_queue_enqueue<F: FsmBackend>(&self, event: &<F as FsmBackend>::Events) {
+        let event = event.as_ref();
+        info!(self.logger, "Enqueued {event}", event = event);
+    }
+
     fn event_done(self) {
         info!(self.logger, "Dispatch done");
     }
@@ -0,0 +1,117 @@
+use tracing::{debug, field::Empty, info_span, span::EnteredSpan, trace};
+use crate::{FsmBackend, FsmError, FsmEvent, FsmEventEnvelope, FsmRegionId, Inspect};
+use super::lib::*;
+
+/// Inspects the machine with the `tracing` crate. Each dispatched event opens a `finny_event` span,
+/// with nested spans for the regions (`finny_region`), the matched transitions (`finny_transition`)
+/// and the sub-machines (`finny_sub_machine`). The spans are entered while they are active, so the
+/// events emitted by the actions are nested within them.
+///
+/// The region's span records the last evaluated guard and its result in the `guard` and
+/// `guard_result` fields. The event's span records the event's data, with `fsm.inspect_debug()`, and
+/// the dispatch's error. With [`crate::FsmEventQueueCausal`], it also records the `event_id`, `parent_id`
+/// and `root_id` of the event's envelope.
+pub struct InspectTracing {
+    span: Option<EnteredSpan>
+}
+
+impl InspectTracing {
+    /// The event spans are nested within the span that is current when the event is dispatched.
+    pub fn new() -> Self {
+        InspectTracing {
+            span: None
+        }
+    }
+}
+
+impl Default for InspectTracing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Inspect for InspectTracing {
+    fn new_event<F: FsmBackend>(&self, event: &FsmEvent<<F as FsmBackend>::Events>, envelope: Option<&FsmEventEnvelope>) -> Self {
+        let span = match self.span {
+            Some(ref parent) => info_span!(parent: parent.id(), "finny_event", fsm = type_name::<F>(), event = event.as_ref(), data = Empty, error = Empty, event_id = Empty, parent_id = Empty, root_id = Empty),
+            None => info_span!("finny_event", fsm = type_name::<F>(), event = event.as_ref(), data = Empty, error = Empty, event_id = Empty, parent_id = Empty, root_id = Empty)
+        };
+        if let Some(envelope) = envelope {
+            span.record("event_id", envelope.id);
+            if let Some(parent) = envelope.parent {
+                span.record("parent_id", parent);
+            }
+            span.record("root_id", envelope.root);
+        }
+        InspectTracing {
+            span: Some(span.entered())
+        }
+    }
+
+    fn for_region<F: FsmBackend>(&self, region: FsmRegionId) -> Self {
+        let region = F::REGION_NAMES[region];
+        let span = info_span!(parent: self.span.as_ref().and_then(|s| s.id()), "finny_region", region = region, guard = Empty, guard_result = Empty);
+        InspectTracing {
+            span: Some(span.entered())
+        }
+    }
+
+    fn for_transition<T>(&self) -> Self {
+        let span = info_span!(parent: self.span.as_ref().and_then(|s| s.id()), "finny_transition", transition = type_name::<T>());
+        InspectTracing {
+            span: Some(span.entered())
+        }
+    }
+
+    fn for_sub_machine<FSub: FsmBackend>(&self) -> Self {
+        let span = info_span!(parent: self.span.as_ref().and_then(|s| s.id()), "finny_sub_machine", sub_fsm = type_name::<FSub>());
+        InspectTracing {
+            span: Some(span.entered())
+        }
+    }
+
+    fn on_guard<T>(&self, guard_result: bool) {
+        let guard = type_name::<T>();
+        if let Some(ref span) = self.span {
+            span.record("guard", guard);
+            span.record("guard_result", guard_result);
+        }
+        trace!(guard, guard_result, "Guard evaluated");
+    }
+
+    fn on_state_enter<S>(&self) {
+        debug!(state = type_name::<S>(), "Entering");
+    }
+
+    fn on_state_exit<S>(&self) {
+        debug!(state = type_name::<S>(), "Exiting");
+    }
+
+    fn on_action<S>(&self) {
+        debug!(action = type_name::<S>(), "Executing");
+    }
+
+    fn on_event_data(&self, event: &dyn Debug) {
+        if let Some(ref span) = self.span {
+            span.record("data", tracing::field::debug(event));
+        }
+    }
+
+    fn on_no_transition(&self) {
+        debug!("No transition matched the event");
+    }
+
+    fn on_error(&self, error: &FsmError) {
+        if let Some(ref span) = self.span {
+            span.record("error", tracing::field::debug(error));
+        }
+    }
+
+    fn on_queue_enqueue<F: FsmBackend>(&self, event: &<F as FsmBackend>::Events) {
+        debug!(event = event.as_ref(), "Enqueued");
+    }
+
+    fn event_done(self) {
+        debug!("Dispatch done");
+    }
+}
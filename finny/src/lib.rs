@@ -12,7 +12,18 @@
 //! * Transition guards and actions
 //! * FSM regions, also known as orthogonal states
 //! * Event queueing and run-to-completition execution
+//! * Inspection of the dispatched events, with `slog` and `tracing` integrations, `no_std` metrics with a Prometheus renderer, JSON-lines and Chrome trace-event exports, and correlation IDs that link the events to the actions that enqueued them
+//! * Trace recording and golden-trace assertions for testing the machines
+//! * Transition, guard and state coverage reports of the test suites
+//! * Model-based tests that take every transition of the machine, generated from its description
 //! * Submachines, also known as Hieararchical State Machines
+//! * Static description of the states, events and transitions, usable in `no_std`
+//! * Machines described in SCXML or JSON files
+//!
+//! ## Upgrading
+//!
+//! * Custom `Inspect` implementations have to implement the new `for_region` method, which scopes the inspection to the
+//!   region that is handling the event. An inspector that doesn't track the regions can return a clone of itself.
 //!
 //! ## Example
 //!
@@ -66,19 +77,54 @@
 
 pub mod decl;
 mod fsm;
+pub mod render;
 
 
 #[cfg(feature="inspect_slog")]
 pub mod inspect_slog;
 
+#[cfg(feature="inspect_tracing")]
+pub mod inspect_tracing;
+
+#[cfg(feature="std")]
+pub mod inspect_recorder;
+
+#[cfg(feature="std")]
+pub mod inspect_json;
+
+#[cfg(feature="std")]
+pub mod inspect_chrome;
+
+#[cfg(feature="std")]
+pub mod inspect_coverage;
+
+#[cfg(feature="std")]
+pub mod tour;
+
 pub use fsm::*;
 
 extern crate finny_derive;
 extern crate derive_more;
 
 /// The procedural macro that will transform the builder function into the FSM.
+///
+/// With `#[finny_fsm(emit_model = "my_fsm.json")]`, the validated model of the machine is also written
+/// as JSON into that file, with a relative path resolved against the crate's manifest directory. It lists
+/// the states, events, regions and transitions, along with the source locations of the closures, for use
+/// by external tools.
 pub use finny_derive::finny_fsm;
 
+/// Generates the FSM from an SCXML or a JSON description, with the path relative to the crate's
+/// manifest: `finny_fsm_from_file!("door.scxml", context = DoorContext, name = DoorFsm);`.
+///
+/// The states and events are the crate's own types, named by the file. Guards and actions are
+/// bound to functions by name:
+/// * transition guard: `fn(&Event, &Context) -> bool`
+/// * transition action: `fn(&Event, &mut Context)`
+/// * entry and exit actions: `fn(&mut State, &mut Context)`
+/// * initial state guard and action: `fn(&Context) -> bool` and `fn(&mut Context)`
+pub use finny_derive::finny_fsm_from_file;
+
 /// External bundled libraries to be used by the procedural macros.
 pub mod bundled {
     /// Derive_more crate for deriving the enum conversions.
@@ -102,6 +148,7 @@ mod lib {
    pub use self::core::fmt;
    pub use self::core::any::type_name;
    pub use self::core::slice::SliceIndex;
+   pub use self::core::cell::RefCell;
 
    #[cfg(feature="std")]
    pub use std::collections::VecDeque;
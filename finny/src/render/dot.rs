@@ -0,0 +1,92 @@
+//! Graphviz DOT rendering of the transition graph. Regions are rendered as dashed clusters and
+//! sub-machines as nested clusters. Internal transitions are dashed self-loops.
+
+use crate::lib::*;
+use crate::{FsmMeta, FsmTransitionKindMeta};
+use super::{Indent, MetaPath, TransitionLabel};
+
+const SEPARATOR: &str = "/";
+
+/// Write the machine's transition graph in the Graphviz DOT format.
+pub fn write_dot<W: fmt::Write>(meta: &FsmMeta, w: &mut W) -> fmt::Result {
+    writeln!(w, "digraph \"{}\" {{", meta.name)?;
+    writeln!(w, "    compound=true;")?;
+    writeln!(w, "    node [shape=box, style=rounded];")?;
+    write_machine(meta, &MetaPath::root(meta.name), w, 1)?;
+    writeln!(w, "}}")
+}
+
+/// The machine's transition graph in the Graphviz DOT format.
+#[cfg(feature = "std")]
+pub fn to_dot(meta: &FsmMeta) -> String {
+    let mut s = String::new();
+    write_dot(meta, &mut s).expect("Writing into a String can't fail");
+    s
+}
+
+fn write_machine<W: fmt::Write>(meta: &FsmMeta, path: &MetaPath, w: &mut W, depth: usize) -> fmt::Result {
+    let clustered = meta.regions.len() > 1;
+
+    for (region_id, region) in meta.regions.iter().enumerate() {
+        let mut depth = depth;
+        if clustered {
+            writeln!(w, "{}subgraph \"cluster_{}#{}\" {{", Indent(depth), path.id(SEPARATOR), region.name)?;
+            depth += 1;
+            writeln!(w, "{}label=\"{}\";", Indent(depth), region.name)?;
+            writeln!(w, "{}style=dashed;", Indent(depth))?;
+        }
+
+        writeln!(w, "{}\"{}#start{}\" [shape=point];", Indent(depth), path.id(SEPARATOR), region_id)?;
+
+        for state_name in region.states {
+            let state_path = path.child(state_name);
+            match meta.state(state_name).and_then(|s| s.sub_machine) {
+                Some(sub) => {
+                    writeln!(w, "{}subgraph \"cluster_{}\" {{", Indent(depth), state_path.id(SEPARATOR))?;
+                    writeln!(w, "{}label=\"{}\";", Indent(depth + 1), state_name)?;
+                    writeln!(w, "{}style=rounded;", Indent(depth + 1))?;
+                    // the anchor for the edges into and out of the cluster
+                    writeln!(w, "{}\"{}\" [shape=point, style=invis];", Indent(depth + 1), state_path.id(SEPARATOR))?;
+                    write_machine(sub, &state_path, w, depth + 1)?;
+                    writeln!(w, "{}}}", Indent(depth))?;
+                },
+                None => {
+                    writeln!(w, "{}\"{}\" [label=\"{}\"];", Indent(depth), state_path.id(SEPARATOR), state_name)?;
+                }
+            }
+        }
+
+        if clustered {
+            writeln!(w, "{}}}", Indent(depth - 1))?;
+        }
+    }
+
+    for t in meta.transitions {
+        let to = match t.to {
+            Some(to) => to,
+            None => continue
+        };
+        let to_path = path.child(to);
+
+        match t.from {
+            Some(from) => write!(w, "{}\"{}\"", Indent(depth), path.child(from).id(SEPARATOR))?,
+            None => write!(w, "{}\"{}#start{}\"", Indent(depth), path.id(SEPARATOR), t.region)?
+        }
+        write!(w, " -> \"{}\" [label=\"{}\"", to_path.id(SEPARATOR), TransitionLabel(t))?;
+
+        if t.kind == FsmTransitionKindMeta::Internal {
+            write!(w, ", style=dashed")?;
+        }
+        if let Some(from) = t.from {
+            if meta.state(from).and_then(|s| s.sub_machine).is_some() {
+                write!(w, ", ltail=\"cluster_{}\"", path.child(from).id(SEPARATOR))?;
+            }
+        }
+        if meta.state(to).and_then(|s| s.sub_machine).is_some() {
+            write!(w, ", lhead=\"cluster_{}\"", to_path.id(SEPARATOR))?;
+        }
+        writeln!(w, "];")?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+//! Renderers of the machine's static description into diagram formats. They write into any
+//! `core::fmt::Write`, so they can be used without an allocator.
+
+use crate::lib::*;
+use crate::{FsmTransitionEventMeta, FsmTransitionMeta};
+
+mod dot;
+mod scxml;
+mod state_diagram;
+
+pub use self::dot::*;
+pub use self::scxml::*;
+pub use self::state_diagram::*;
+
+/// The unique path of a state within the nested sub-machines.
+#[derive(Copy, Clone)]
+struct MetaPath<'a> {
+    parent: Option<&'a MetaPath<'a>>,
+    name: &'a str
+}
+
+impl<'a> MetaPath<'a> {
+    fn root(name: &'a str) -> Self {
+        MetaPath { parent: None, name }
+    }
+
+    fn child(&'a self, name: &'a str) -> Self {
+        MetaPath { parent: Some(self), name }
+    }
+
+    /// Displays the path with its segments joined by the separator.
+    fn id(&self, separator: &'static str) -> MetaPathId<'_> {
+        MetaPathId { path: self, separator }
+    }
+}
+
+struct MetaPathId<'a> {
+    path: &'a MetaPath<'a>,
+    separator: &'static str
+}
+
+impl<'a> fmt::Display for MetaPathId<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(parent) = self.path.parent {
+            write!(f, "{}{}", parent.id(self.separator), self.separator)?;
+        }
+        write!(f, "{}", self.path.name)
+    }
+}
+
+/// The transition's label: the event, followed by the guard and the action, if they are present.
+struct TransitionLabel<'a>(&'a FsmTransitionMeta);
+
+impl<'a> TransitionLabel<'a> {
+    fn is_empty(&self) -> bool {
+        self.0.event == FsmTransitionEventMeta::Start && !self.0.has_guard && !self.0.has_action
+    }
+}
+
+impl<'a> fmt::Display for TransitionLabel<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let t = self.0;
+        let mut separator = "";
+        match t.event {
+            FsmTransitionEventMeta::Start => (),
+            FsmTransitionEventMeta::Stop => { write!(f, "Stop")?; separator = " "; },
+            FsmTransitionEventMeta::Event(ev) => { write!(f, "{}", ev)?; separator = " "; }
+        }
+        if t.has_guard {
+            write!(f, "{}[guard]", separator)?;
+            separator = " ";
+        }
+        if t.has_action {
+            write!(f, "{}/ action", separator)?;
+        }
+        Ok(())
+    }
+}
+
+struct Indent(usize);
+
+impl fmt::Display for Indent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for _ in 0..self.0 {
+            write!(f, "    ")?;
+        }
+        Ok(())
+    }
+}
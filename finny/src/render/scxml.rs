@@ -0,0 +1,139 @@
+//! W3C SCXML export. Regions are rendered as `<parallel>` states and sub-machines as compound
+//! states. Guards and actions are referenced by placeholder functions named after the generated
+//! transition types, for example `StateMachineTransition2_guard()`.
+//!
+//! SCXML's initial transitions can't have conditions, so guarded initial states and initial
+//! actions are rendered as a transient state with eventless transitions.
+
+use crate::lib::*;
+use crate::{FsmMeta, FsmRegionId, FsmTransitionEventMeta, FsmTransitionKindMeta, FsmTransitionMeta};
+use super::{Indent, MetaPath};
+
+const SEPARATOR: &str = ".";
+
+/// Write the machine as a W3C SCXML document.
+pub fn write_scxml<W: fmt::Write>(meta: &FsmMeta, w: &mut W) -> fmt::Result {
+    let path = MetaPath::root(meta.name);
+
+    writeln!(w, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    write!(w, "<scxml xmlns=\"http://www.w3.org/2005/07/scxml\" version=\"1.0\" datamodel=\"ecmascript\" name=\"{}\"", meta.name)?;
+    if meta.regions.len() == 1 {
+        write!(w, " initial=\"{}\"", InitialId(meta, 0, &path))?;
+    }
+    writeln!(w, ">")?;
+    write_states(meta, &path, w, 1)?;
+    writeln!(w, "</scxml>")
+}
+
+/// The machine as a W3C SCXML document.
+#[cfg(feature = "std")]
+pub fn to_scxml(meta: &FsmMeta) -> String {
+    let mut s = String::new();
+    write_scxml(meta, &mut s).expect("Writing into a String can't fail");
+    s
+}
+
+/// Does the region need a transient state to pick its initial state?
+fn has_initial_choice(meta: &FsmMeta, region: FsmRegionId) -> bool {
+    let mut start_transitions = meta.region_transitions(region).filter(|t| t.event == FsmTransitionEventMeta::Start);
+    match (start_transitions.next(), start_transitions.next()) {
+        (Some(t), None) => t.has_guard || t.has_action,
+        _ => true
+    }
+}
+
+/// The target of the region's initial transition.
+struct InitialId<'a>(&'a FsmMeta, FsmRegionId, &'a MetaPath<'a>);
+
+impl<'a> fmt::Display for InitialId<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let InitialId(meta, region_id, path) = *self;
+        let region = &meta.regions[region_id];
+        match region.initial_states.first() {
+            Some(initial) if !has_initial_choice(meta, region_id) => write!(f, "{}", path.child(initial).id(SEPARATOR)),
+            _ => write!(f, "{}-{}-initial", path.id(SEPARATOR), region.name)
+        }
+    }
+}
+
+fn write_states<W: fmt::Write>(meta: &FsmMeta, path: &MetaPath, w: &mut W, depth: usize) -> fmt::Result {
+    if meta.regions.len() == 1 {
+        return write_region(meta, 0, path, w, depth);
+    }
+
+    writeln!(w, "{}<parallel id=\"{}-regions\">", Indent(depth), path.id(SEPARATOR))?;
+    for (region_id, region) in meta.regions.iter().enumerate() {
+        writeln!(w, "{}<state id=\"{}-{}\" initial=\"{}\">", Indent(depth + 1), path.id(SEPARATOR), region.name, InitialId(meta, region_id, path))?;
+        write_region(meta, region_id, path, w, depth + 2)?;
+        writeln!(w, "{}</state>", Indent(depth + 1))?;
+    }
+    writeln!(w, "{}</parallel>", Indent(depth))
+}
+
+fn write_region<W: fmt::Write>(meta: &FsmMeta, region_id: FsmRegionId, path: &MetaPath, w: &mut W, depth: usize) -> fmt::Result {
+    let region = &meta.regions[region_id];
+
+    if has_initial_choice(meta, region_id) {
+        writeln!(w, "{}<state id=\"{}\">", Indent(depth), InitialId(meta, region_id, path))?;
+        for t in meta.region_transitions(region_id).filter(|t| t.event == FsmTransitionEventMeta::Start) {
+            write_transition(t, path, w, depth + 1)?;
+        }
+        writeln!(w, "{}</state>", Indent(depth))?;
+    }
+
+    for state_name in region.states {
+        let state_path = path.child(state_name);
+        let sub_machine = meta.state(state_name).and_then(|s| s.sub_machine);
+        let mut transitions = meta.region_transitions(region_id).filter(|t| t.from == Some(*state_name)).peekable();
+
+        write!(w, "{}<state id=\"{}\"", Indent(depth), state_path.id(SEPARATOR))?;
+        match sub_machine {
+            Some(sub) if sub.regions.len() == 1 => write!(w, " initial=\"{}\">", InitialId(sub, 0, &state_path))?,
+            None if transitions.peek().is_none() => {
+                writeln!(w, "/>")?;
+                continue;
+            },
+            _ => write!(w, ">")?
+        }
+        writeln!(w)?;
+
+        if let Some(sub) = sub_machine {
+            write_states(sub, &state_path, w, depth + 1)?;
+        }
+        for t in transitions {
+            write_transition(t, path, w, depth + 1)?;
+        }
+
+        writeln!(w, "{}</state>", Indent(depth))?;
+    }
+
+    Ok(())
+}
+
+fn write_transition<W: fmt::Write>(t: &FsmTransitionMeta, path: &MetaPath, w: &mut W, depth: usize) -> fmt::Result {
+    write!(w, "{}<transition", Indent(depth))?;
+
+    match t.event {
+        FsmTransitionEventMeta::Start => (),
+        FsmTransitionEventMeta::Stop => write!(w, " event=\"finny.stop\"")?,
+        FsmTransitionEventMeta::Event(ev) => write!(w, " event=\"{}\"", ev)?
+    }
+
+    // a targetless transition doesn't exit its source state
+    match (t.kind, t.to) {
+        (FsmTransitionKindMeta::Internal, _) | (_, None) => (),
+        (_, Some(to)) => write!(w, " target=\"{}\"", path.child(to).id(SEPARATOR))?
+    }
+
+    if t.has_guard {
+        write!(w, " cond=\"{}_guard()\"", t.name)?;
+    }
+
+    if t.has_action {
+        writeln!(w, ">")?;
+        writeln!(w, "{}<script>{}_action();</script>", Indent(depth + 1), t.name)?;
+        writeln!(w, "{}</transition>", Indent(depth))
+    } else {
+        writeln!(w, "/>")
+    }
+}
@@ -0,0 +1,91 @@
+//! Mermaid and PlantUML state diagrams. Both formats share the syntax for states, composite
+//! states and transitions. Sub-machines are rendered as composite states, with their regions
+//! separated by `--`.
+
+use crate::lib::*;
+use crate::FsmMeta;
+use super::{Indent, MetaPath, TransitionLabel};
+
+const SEPARATOR: &str = "_";
+
+/// Write the machine's transition graph as a Mermaid `stateDiagram-v2`.
+pub fn write_mermaid<W: fmt::Write>(meta: &FsmMeta, w: &mut W) -> fmt::Result {
+    writeln!(w, "stateDiagram-v2")?;
+    write_machine(meta, w)
+}
+
+/// Write the machine's transition graph as a PlantUML state diagram.
+pub fn write_plantuml<W: fmt::Write>(meta: &FsmMeta, w: &mut W) -> fmt::Result {
+    writeln!(w, "@startuml")?;
+    write_machine(meta, w)?;
+    writeln!(w, "@enduml")
+}
+
+/// The machine's transition graph as a Mermaid `stateDiagram-v2`.
+#[cfg(feature = "std")]
+pub fn to_mermaid(meta: &FsmMeta) -> String {
+    let mut s = String::new();
+    write_mermaid(meta, &mut s).expect("Writing into a String can't fail");
+    s
+}
+
+/// The machine's transition graph as a PlantUML state diagram.
+#[cfg(feature = "std")]
+pub fn to_plantuml(meta: &FsmMeta) -> String {
+    let mut s = String::new();
+    write_plantuml(meta, &mut s).expect("Writing into a String can't fail");
+    s
+}
+
+fn write_machine<W: fmt::Write>(meta: &FsmMeta, w: &mut W) -> fmt::Result {
+    let path = MetaPath::root(meta.name);
+
+    // the regions can only be separated within a composite state
+    if meta.regions.len() > 1 {
+        writeln!(w, "    state {} {{", path.id(SEPARATOR))?;
+        write_regions(meta, &path, w, 2)?;
+        writeln!(w, "    }}")
+    } else {
+        write_regions(meta, &path, w, 1)
+    }
+}
+
+fn write_regions<W: fmt::Write>(meta: &FsmMeta, path: &MetaPath, w: &mut W, depth: usize) -> fmt::Result {
+    for (region_id, region) in meta.regions.iter().enumerate() {
+        if region_id > 0 {
+            writeln!(w, "{}--", Indent(depth))?;
+        }
+
+        for state_name in region.states {
+            let state_path = path.child(state_name);
+            writeln!(w, "{}state \"{}\" as {}", Indent(depth), state_name, state_path.id(SEPARATOR))?;
+
+            if let Some(sub) = meta.state(state_name).and_then(|s| s.sub_machine) {
+                writeln!(w, "{}state {} {{", Indent(depth), state_path.id(SEPARATOR))?;
+                write_regions(sub, &state_path, w, depth + 1)?;
+                writeln!(w, "{}}}", Indent(depth))?;
+            }
+        }
+
+        for t in meta.region_transitions(region_id) {
+            let to = match t.to {
+                Some(to) => to,
+                None => continue
+            };
+
+            match t.from {
+                Some(from) => write!(w, "{}{}", Indent(depth), path.child(from).id(SEPARATOR))?,
+                None => write!(w, "{}[*]", Indent(depth))?
+            }
+            write!(w, " --> {}", path.child(to).id(SEPARATOR))?;
+
+            let label = TransitionLabel(t);
+            if !label.is_empty() {
+                write!(w, " : {}", label)?;
+            }
+            writeln!(w)?;
+        }
+    }
+
+    Ok(())
+}
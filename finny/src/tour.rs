@@ -0,0 +1,539 @@
+//! Model-based testing. Generates the event sequences that take every transition of the machine at
+//! least once, from its static description, and runs them against the real machine while checking
+//! the states of its regions after each step.
+//!
+//! The model knows the machine's structure, but not what its guards decide. A guarded transition is
+//! assumed to pass only in the step that targets it, and to reject the events in all the other steps.
+//! The guard hints tell the harness how to make a guard pass: by changing the context, or by
+//! dispatching an event with specific data. The context changes of the hints persist for the rest of
+//! the sequence, so the model assumes that their guards keep passing from then on. The hints name the
+//! transitions by the types of their states and event, or by their generated types when several guarded
+//! transitions share those. The sub-machines are treated as plain states of their parent, tour them with
+//! a harness of their own. The events enqueued by the actions aren't
+//! modelled, they are discarded after each step.
+//!
+//! ```rust
+//! use finny::{finny_fsm, FsmFactory, decl::{BuiltFsm, FsmBuilder}, tour::{FsmTourHarness, FsmTourTransition}};
+//!
+//! #[derive(Default)]
+//! pub struct Locked;
+//! #[derive(Default)]
+//! pub struct Unlocked;
+//! #[derive(Clone)]
+//! pub struct Coin { value: u32 }
+//! #[derive(Clone)]
+//! pub struct Push;
+//!
+//! #[finny_fsm]
+//! fn build_fsm(mut fsm: FsmBuilder<Turnstile, ()>) -> BuiltFsm {
+//!     fsm.initial_state::<Locked>();
+//!     fsm.state::<Locked>()
+//!         .on_event::<Coin>()
+//!         .transition_to::<Unlocked>()
+//!         .guard(|ev, _, _| ev.value >= 10);
+//!     fsm.state::<Unlocked>()
+//!         .on_event::<Push>()
+//!         .transition_to::<Locked>();
+//!     fsm.build()
+//! }
+//!
+//! fn main() {
+//!     FsmTourHarness::new(|| Turnstile::new(()))
+//!         .event(|| Coin { value: 1 })
+//!         .event(|| Push)
+//!         .guard_event(FsmTourTransition::new::<Locked, Coin, Unlocked>(), || Coin { value: 10 })
+//!         .assert_tour();
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{FsmBackend, FsmCurrentState, FsmDescription, FsmEvent, FsmEventQueue, FsmFrontend, FsmMeta, FsmResult, FsmStates, FsmTransitionEventMeta, FsmTransitionKindMeta, FsmTransitionMeta, Inspect, short_type_name};
+use super::lib::*;
+
+/// The states of the regions, `None` while the region is stopped.
+pub type FsmTourState = Vec<Option<&'static str>>;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FsmTourEvent {
+    Start,
+    /// The event's name.
+    Event(&'static str)
+}
+
+impl fmt::Display for FsmTourEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FsmTourEvent::Start => f.write_str("Fsm::Start"),
+            FsmTourEvent::Event(e) => f.write_str(e)
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FsmTourStep {
+    pub event: FsmTourEvent,
+    /// The guarded transition that this step makes pass, using its hint.
+    pub target: Option<&'static FsmTransitionMeta>,
+    /// The transitions that the step is expected to take, at most one per region.
+    pub transitions: Vec<&'static FsmTransitionMeta>,
+    /// The expected states of the regions after the step.
+    pub expected: FsmTourState
+}
+
+/// The steps of a single run, starting from a newly built machine.
+#[derive(Clone, Debug, Default)]
+pub struct FsmTourSequence {
+    pub steps: Vec<FsmTourStep>
+}
+
+/// The sequences that together take every reachable transition and enter every reachable state.
+#[derive(Clone, Debug)]
+pub struct FsmTour {
+    pub meta: &'static FsmMeta,
+    pub sequences: Vec<FsmTourSequence>,
+    /// The transitions that the tour couldn't take, because they were skipped or unreachable.
+    pub unreachable_transitions: Vec<&'static FsmTransitionMeta>,
+    pub unreachable_states: Vec<&'static str>
+}
+
+impl FsmTour {
+    /// Generates the tour of the machine. The skipped transitions are never taken on purpose, and the
+    /// events without a name in `events` are never dispatched. The guards of the `persistent` transitions
+    /// keep passing after the step that targets them.
+    pub fn generate(meta: &'static FsmMeta, events: &[&'static str], skipped: &[&str], persistent: &[&str]) -> Self {
+        let moves = Moves { meta, events, skipped, persistent };
+        let mut uncovered: Vec<&'static FsmTransitionMeta> = meta.transitions.iter().filter(|t| !skipped.contains(&t.name)).collect();
+        let mut sequences = vec![];
+
+        while !uncovered.is_empty() {
+            let mut sequence = FsmTourSequence::default();
+            let mut state = ModelState { regions: vec![None; meta.regions.len()], passing: vec![] };
+
+            while let Some(path) = moves.shortest_path(&state, &uncovered) {
+                for (step, next) in path {
+                    uncovered.retain(|u| !step.transitions.iter().any(|t| core::ptr::eq(*t, *u)));
+                    state = next;
+                    sequence.steps.push(step);
+                }
+            }
+
+            if sequence.steps.is_empty() {
+                break;
+            }
+            sequences.push(sequence);
+        }
+
+        let steps = || sequences.iter().flat_map(|s: &FsmTourSequence| s.steps.iter());
+        let taken: HashSet<_> = steps().flat_map(|s| s.transitions.iter().map(|t| t.name)).collect();
+        let reached: HashSet<_> = steps().flat_map(|s| s.expected.iter().flatten().copied()).collect();
+        let unreachable_transitions = meta.transitions.iter().filter(|t| !taken.contains(t.name)).collect();
+        let unreachable_states = meta.states.iter().map(|s| s.name).filter(|s| !reached.contains(s)).collect();
+
+        FsmTour {
+            meta,
+            sequences,
+            unreachable_transitions,
+            unreachable_states
+        }
+    }
+}
+
+impl fmt::Display for FsmTour {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, sequence) in self.sequences.iter().enumerate() {
+            writeln!(f, "sequence {}:", i + 1)?;
+            for step in &sequence.steps {
+                writeln!(f, "  {}", DisplayStep(step))?;
+            }
+        }
+        for t in &self.unreachable_transitions {
+            writeln!(f, "unreachable transition {}", t.name)?;
+        }
+        for s in &self.unreachable_states {
+            writeln!(f, "unreachable state {}", s)?;
+        }
+        Ok(())
+    }
+}
+
+struct DisplayStep<'a>(&'a FsmTourStep);
+
+impl<'a> fmt::Display for DisplayStep<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.event)?;
+        if let Some(target) = self.0.target {
+            write!(f, " (guard of {})", target.name)?;
+        }
+        write!(f, " -> {}", DisplayState(&self.0.expected))
+    }
+}
+
+struct DisplayState<'a>(&'a [Option<&'static str>]);
+
+impl<'a> fmt::Display for DisplayState<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[")?;
+        for (i, state) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(state.unwrap_or("stopped"))?;
+        }
+        f.write_str("]")
+    }
+}
+
+/// The states of the regions in the model, along with the guards that were made to pass for good.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct ModelState {
+    regions: FsmTourState,
+    /// The names of the transitions, sorted.
+    passing: Vec<&'static str>
+}
+
+/// The possible steps from the machine's states.
+struct Moves<'a> {
+    meta: &'static FsmMeta,
+    events: &'a [&'static str],
+    skipped: &'a [&'a str],
+    persistent: &'a [&'a str]
+}
+
+impl<'a> Moves<'a> {
+    /// Whether the transition is triggered by the event from the region's state.
+    fn triggers(t: &FsmTransitionMeta, event: FsmTourEvent, state: Option<&'static str>) -> bool {
+        let event_matches = match (t.event, event) {
+            (FsmTransitionEventMeta::Start, FsmTourEvent::Start) => true,
+            (FsmTransitionEventMeta::Event(a), FsmTourEvent::Event(b)) => a == b,
+            _ => false
+        };
+        event_matches && t.from == state
+    }
+
+    /// Dispatches the event in the model. Every region takes its first transition that isn't guarded,
+    /// whose guard passes for good, or that is the target.
+    fn step(&self, state: &ModelState, event: FsmTourEvent, target: Option<&'static FsmTransitionMeta>) -> Option<(FsmTourStep, ModelState)> {
+        let mut expected = state.regions.clone();
+        let mut transitions = vec![];
+
+        for (region, current) in state.regions.iter().enumerate() {
+            let taken = self.meta.region_transitions(region)
+                .filter(|t| Self::triggers(t, event, *current))
+                .find(|t| !t.has_guard || state.passing.contains(&t.name) || target.map(|target| core::ptr::eq(*t, target)).unwrap_or(false));
+
+            if let Some(t) = taken {
+                if t.kind == FsmTransitionKindMeta::Normal {
+                    expected[region] = t.to;
+                }
+                transitions.push(t);
+            }
+        }
+
+        if transitions.is_empty() || target.map(|target| !transitions.iter().any(|t| core::ptr::eq(*t, target))).unwrap_or(false) {
+            return None;
+        }
+
+        let mut passing = state.passing.clone();
+        if let Some(target) = target {
+            if self.persistent.contains(&target.name) && !passing.contains(&target.name) {
+                passing.push(target.name);
+                passing.sort_unstable();
+            }
+        }
+
+        let next = ModelState { regions: expected.clone(), passing };
+        Some((FsmTourStep { event, target, transitions, expected }, next))
+    }
+
+    /// All the steps that are possible from this state.
+    fn steps(&self, state: &ModelState) -> Vec<(FsmTourStep, ModelState)> {
+        let events: Vec<FsmTourEvent> = if state.regions.iter().all(|s| s.is_none()) {
+            vec![FsmTourEvent::Start]
+        } else {
+            self.events.iter().map(|e| FsmTourEvent::Event(e)).collect()
+        };
+
+        let mut steps = vec![];
+        for event in events {
+            steps.extend(self.step(state, event, None));
+            let targets = self.meta.transitions.iter()
+                .filter(|t| t.has_guard && !self.skipped.contains(&t.name) && !state.passing.contains(&t.name))
+                .filter(|t| Self::triggers(t, event, state.regions[t.region]));
+            for target in targets {
+                steps.extend(self.step(state, event, Some(target)));
+            }
+        }
+        steps
+    }
+
+    /// The shortest sequence of steps from the state that takes at least one of the transitions.
+    fn shortest_path(&self, from: &ModelState, transitions: &[&'static FsmTransitionMeta]) -> Option<Vec<(FsmTourStep, ModelState)>> {
+        let mut visited: HashMap<ModelState, Option<(ModelState, FsmTourStep)>> = HashMap::new();
+        let mut queue = VecDeque::new();
+        visited.insert(from.clone(), None);
+        queue.push_back(from.clone());
+
+        while let Some(state) = queue.pop_front() {
+            for (step, next) in self.steps(&state) {
+                let covers = step.transitions.iter().any(|t| transitions.iter().any(|u| core::ptr::eq(*t, *u)));
+                if covers {
+                    let mut path = vec![(step, next)];
+                    let mut current = state;
+                    while let Some(Some((previous, step))) = visited.get(&current) {
+                        path.push((step.clone(), current.clone()));
+                        current = previous.clone();
+                    }
+                    path.reverse();
+                    return Some(path);
+                }
+
+                if !visited.contains_key(&next) {
+                    visited.insert(next.clone(), Some((state.clone(), step)));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+type EventFactory<F> = Box<dyn Fn() -> <F as FsmBackend>::Events>;
+type ContextSetup<F> = Box<dyn Fn(&mut <F as FsmBackend>::Context)>;
+
+/// A transition of the machine, named by the types of its states and its event. The internal and self
+/// transitions start and end in the same state. The guarded transitions that share their states and
+/// event are told apart by their generated types.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FsmTourTransition {
+    Endpoints {
+        from: Option<&'static str>,
+        event: FsmTransitionEventMeta,
+        to: Option<&'static str>
+    },
+    /// The name of the generated transition type.
+    Type(&'static str)
+}
+
+impl FsmTourTransition {
+    pub fn new<TFrom, TEvent, TTo>() -> Self {
+        FsmTourTransition::Endpoints {
+            from: Some(base_type_name::<TFrom>()),
+            event: FsmTransitionEventMeta::Event(base_type_name::<TEvent>()),
+            to: Some(base_type_name::<TTo>())
+        }
+    }
+
+    /// The start of the machine into this initial state.
+    pub fn start<TTo>() -> Self {
+        FsmTourTransition::Endpoints {
+            from: None,
+            event: FsmTransitionEventMeta::Start,
+            to: Some(base_type_name::<TTo>())
+        }
+    }
+
+    /// The transition with this generated type, like `MyFsmTransition3`.
+    pub fn of<T>() -> Self {
+        FsmTourTransition::Type(base_type_name::<T>())
+    }
+
+    fn matches(&self, t: &FsmTransitionMeta) -> bool {
+        match *self {
+            FsmTourTransition::Endpoints { from, event, to } => from == t.from && event == t.event && to == t.to,
+            FsmTourTransition::Type(name) => name == t.name
+        }
+    }
+}
+
+/// The type's name as in the machine's description, without the generic arguments.
+fn base_type_name<T>() -> &'static str {
+    let name = short_type_name::<T>();
+    &name[..name.find('<').unwrap_or(name.len())]
+}
+
+/// How to make a guard pass in the step that targets its transition.
+enum FsmGuardHint<F: FsmBackend> {
+    Context(ContextSetup<F>),
+    Event(EventFactory<F>),
+    Never
+}
+
+/// The step of the tour where the machine diverged from the model.
+#[derive(Debug)]
+pub struct FsmTourFailure {
+    /// Zero-based index of the sequence.
+    pub sequence: usize,
+    /// Zero-based index of the step within the sequence.
+    pub step: usize,
+    pub message: String,
+    /// The steps of the sequence, up to and including the failed one.
+    pub steps: Vec<String>
+}
+
+impl fmt::Display for FsmTourFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Step {} of the tour's sequence {} failed: {}", self.step + 1, self.sequence + 1, self.message)?;
+        for step in &self.steps {
+            writeln!(f, "  {}", step)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs the machine's tour against newly built frontends. Register a factory for every event that
+/// the tour should dispatch, and the hints for the guards.
+pub struct FsmTourHarness<F, Q, I>
+    where F: FsmBackend, Q: FsmEventQueue<F>, I: Inspect
+{
+    new_fsm: Box<dyn Fn() -> FsmResult<FsmFrontend<F, Q, I>>>,
+    events: Vec<(&'static str, EventFactory<F>)>,
+    hints: Vec<(FsmTourTransition, FsmGuardHint<F>)>
+}
+
+impl<F, Q, I> FsmTourHarness<F, Q, I>
+    where F: FsmBackend + FsmDescription, Q: FsmEventQueue<F>, I: Inspect
+{
+    /// The machines are built with this closure, a new one for every sequence.
+    pub fn new<N>(new_fsm: N) -> Self
+        where N: Fn() -> FsmResult<FsmFrontend<F, Q, I>> + 'static
+    {
+        FsmTourHarness {
+            new_fsm: Box::new(new_fsm),
+            events: vec![],
+            hints: vec![]
+        }
+    }
+
+    /// The event that the tour dispatches when it doesn't target a guard with an event hint.
+    pub fn event<E, T>(mut self, event: T) -> Self
+        where E: Into<<F as FsmBackend>::Events>, T: Fn() -> E + 'static
+    {
+        let name = F::event_name(&event().into());
+        self.events.retain(|(n, _)| *n != name);
+        self.events.push((name, Box::new(move || event().into())));
+        self
+    }
+
+    fn hint(mut self, transition: FsmTourTransition, hint: FsmGuardHint<F>) -> Self {
+        let guarded: Vec<_> = F::describe().transitions.iter().filter(|t| t.has_guard && transition.matches(t)).map(|t| t.name).collect();
+        if guarded.len() > 1 {
+            panic!("The hint for {:?} matches the guarded transitions {}, name one of them with FsmTourTransition::of.", transition, guarded.join(", "));
+        }
+
+        self.hints.retain(|(t, _)| *t != transition);
+        self.hints.push((transition, hint));
+        self
+    }
+
+    /// Changes the context before the step that targets this guarded transition, so that it passes. The
+    /// change persists, the guard is expected to pass for the rest of the sequence. Panics if the
+    /// transition matches several guarded ones, as do the other hints.
+    pub fn guard_context<T>(self, transition: FsmTourTransition, setup: T) -> Self
+        where T: Fn(&mut <F as FsmBackend>::Context) + 'static
+    {
+        self.hint(transition, FsmGuardHint::Context(Box::new(setup)))
+    }
+
+    /// Dispatches this event in the step that targets this guarded transition, its data makes the guard pass.
+    pub fn guard_event<E, T>(self, transition: FsmTourTransition, event: T) -> Self
+        where E: Into<<F as FsmBackend>::Events>, T: Fn() -> E + 'static
+    {
+        self.hint(transition, FsmGuardHint::Event(Box::new(move || event().into())))
+    }
+
+    /// The guard can't be made to pass, the transition is left out of the tour.
+    pub fn guard_never(self, transition: FsmTourTransition) -> Self {
+        self.hint(transition, FsmGuardHint::Never)
+    }
+
+    /// The hint for the described transition.
+    fn hint_for(&self, t: &FsmTransitionMeta) -> Option<&FsmGuardHint<F>> {
+        self.hints.iter().find(|(transition, _)| transition.matches(t)).map(|(_, h)| h)
+    }
+
+    /// The tour of the machine, with the registered events and hints.
+    pub fn tour(&self) -> FsmTour {
+        let meta = F::describe();
+        let events: Vec<_> = self.events.iter().map(|(n, _)| *n).collect();
+        let transitions_with = |f: fn(&FsmGuardHint<F>) -> bool| -> Vec<&'static str> {
+            meta.transitions.iter().filter(|t| self.hint_for(t).map(f).unwrap_or(false)).map(|t| t.name).collect()
+        };
+        let skipped = transitions_with(|h| matches!(h, FsmGuardHint::Never));
+        let persistent = transitions_with(|h| matches!(h, FsmGuardHint::Context(_)));
+        FsmTour::generate(meta, &events, &skipped, &persistent)
+    }
+
+    /// Runs every sequence of the tour and checks the states after each step. Returns the tour
+    /// that passed, check its unreachable items for the parts of the machine that it didn't cover.
+    pub fn run(&self) -> Result<FsmTour, FsmTourFailure> {
+        let tour = self.tour();
+
+        for (i, sequence) in tour.sequences.iter().enumerate() {
+            let fail = |step: usize, message: String| FsmTourFailure {
+                sequence: i,
+                step,
+                message,
+                steps: sequence.steps[..=step].iter().map(|s| DisplayStep(s).to_string()).collect()
+            };
+
+            let mut fsm = (self.new_fsm)().map_err(|e| fail(0, format!("building the machine failed with {:?}", e)))?;
+
+            for (j, step) in sequence.steps.iter().enumerate() {
+                let mut event = None;
+                match step.target.and_then(|t| self.hint_for(t)) {
+                    Some(FsmGuardHint::Context(setup)) => setup(&mut fsm.backend.context),
+                    Some(FsmGuardHint::Event(hinted)) => event = Some(hinted()),
+                    _ => ()
+                }
+
+                let result = match step.event {
+                    FsmTourEvent::Start => fsm.start(),
+                    FsmTourEvent::Event(name) => {
+                        let event = match event {
+                            Some(event) => event,
+                            None => match self.events.iter().find(|(n, _)| *n == name) {
+                                Some((_, new_event)) => new_event(),
+                                None => return Err(fail(j, format!("no event factory for {}", name)))
+                            }
+                        };
+                        fsm.dispatch_single_event(FsmEvent::Event(event))
+                    }
+                };
+                if let Err(e) = result {
+                    return Err(fail(j, format!("the dispatch failed with {:?}", e)));
+                }
+
+                let actual = state_names::<F>(&fsm.backend.get_current_states());
+                let expected: Vec<&str> = step.expected.iter().map(|s| s.unwrap_or("stopped")).collect();
+                if actual != expected {
+                    return Err(fail(j, format!("expected the states {:?}, but the machine is in {:?}", expected, actual)));
+                }
+
+                while fsm.queue.dequeue().is_some() { }
+            }
+        }
+
+        Ok(tour)
+    }
+
+    /// Runs the tour, panics on a failure or if any transition or state wasn't covered.
+    pub fn assert_tour(&self) {
+        let tour = match self.run() {
+            Ok(tour) => tour,
+            Err(failure) => panic!("{}", failure)
+        };
+        if !tour.unreachable_transitions.is_empty() || !tour.unreachable_states.is_empty() {
+            panic!("The tour didn't cover the whole machine.\n\n{}", tour);
+        }
+    }
+}
+
+/// The names of the regions' current states, as in the machine's description.
+fn state_names<F: FsmBackend>(states: &<<F as FsmBackend>::States as FsmStates<F>>::CurrentState) -> Vec<String> {
+    states.as_ref().iter().map(|s| match s {
+        FsmCurrentState::Stopped => "stopped".to_string(),
+        FsmCurrentState::State(kind) => format!("{:?}", kind)
+    }).collect()
+}
@@ -1,10 +1,11 @@
-use proc_macro2::{TokenStream};
+use proc_macro2::{Span, TokenStream};
 use quote::{TokenStreamExt, quote};
-use crate::{fsm::FsmTypes, parse::{FsmState, FsmStateKind}, utils::{remap_closure_inputs}};
+use syn::spanned::Spanned;
+use finny_model::{fsm::FsmTypes, parse::{FsmState, FsmStateKind}, utils::{remap_closure_inputs}};
 
-use crate::{parse::{FsmFnInput, FsmStateTransition, FsmTransitionState, FsmTransitionType}, utils::ty_append};
+use finny_model::{parse::{FsmFnInput, FsmStateTransition, FsmTransitionEndpoints, FsmTransitionKind, FsmTransitionState, FsmTransitionType}, utils::{ty_append, type_name}};
 
-pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream) -> syn::Result<TokenStream> {
+pub fn generate_fsm_code(fsm: &FsmFnInput, input: TokenStream) -> syn::Result<TokenStream> {
     let fsm_ty = &fsm.base.fsm_ty;
     let fsm_types = FsmTypes::new(&fsm.base.fsm_ty, &fsm.base.fsm_generics);
     //let fsm_mod = to_field_name(&ty_append(fsm_ty, "Finny"))?;
@@ -24,71 +25,200 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
         let mut new_state_fields = TokenStream::new();
         let mut state_variants = TokenStream::new();
         let mut state_accessors = TokenStream::new();
+        let mut region_enums = TokenStream::new();
 
-        for (i, (_, state)) in fsm.fsm.states.iter().enumerate() {
+        // the storage of the active state of each region, with the union storage
+        let region_storage: Vec<_> = fsm.fsm.regions.iter().map(|region| {
+            let field = syn::Ident::new(&format!("region_{}", region.region_id), Span::call_site());
+            let enum_ty = ty_append(&fsm.base.fsm_ty, &format!("Region{}States", region.region_id));
+            (region, field, enum_ty)
+        }).collect();
+
+        if fsm.fsm.codegen_options.state_storage_union {
+            for (region, field, enum_ty) in &region_storage {
+                let mut variants = TokenStream::new();
+                for state_ty in &region.states {
+                    let state = fsm.fsm.states.get(state_ty).ok_or(syn::Error::new(state_ty.span(), "State not found, codegen bug!"))?;
+                    let state_fsm_ty = FsmTypes::new(&state.ty, &fsm.base.fsm_generics);
+                    let ty = state_fsm_ty.get_fsm_ty();
+                    let variant = state_fsm_ty.get_fsm_no_generics_ty();
+                    variants.append_all(quote! { #variant ( #ty ), });
+                }
+
+                region_enums.append_all(quote! {
+                    pub enum #enum_ty #fsm_generics_type #fsm_generics_where {
+                        #variants
+                        #[doc(hidden)]
+                        _Phantom(core::convert::Infallible, core::marker::PhantomData< #fsm_ty #fsm_generics_type >)
+                    }
+                });
+
+                code_fields.append_all(quote! { #field: Option< #enum_ty #fsm_generics_type >, });
+                new_state_fields.append_all(quote! { #field: None, });
+            }
+        }
+
+        for (_, state) in fsm.fsm.states.iter() {
             let name = &state.state_storage_field;
             let state_ty = FsmTypes::new(&state.ty,&fsm.base.fsm_generics);
             let ty = state_ty.get_fsm_ty();
             let ty_name = state_ty.get_fsm_no_generics_ty();
 
-            code_fields.append_all(quote! { #name: #ty, });
             state_variants.append_all(quote!{ #ty_name, });
 
-            let new_state_field = match state.kind {
-                FsmStateKind::Normal => {
-                    quote! {
-                        #name: < #ty as finny::FsmStateFactory< #fsm_ty #fsm_generics_type > >::new_state(context)?,
-                    }
-                }
-                FsmStateKind::SubMachine(ref sub) => {
+            let constructed = fsm.fsm.constructed_states.contains(&state.ty);
+            let new_state = new_state_codegen(fsm, state)?;
 
-                    let ctx_codegen = match &sub.context_constructor {
-                        Some(c) => {
-                            let remap = remap_closure_inputs(&c.inputs, &[quote!{ context }])?;
-                            let body = &c.body;
+            if fsm.fsm.codegen_options.state_storage_union {
+                let region = region_storage.iter().find(|(r, _, _)| r.states.contains(&state.ty));
+
+                let accessors = match region {
+                    Some((_, field, enum_ty)) => {
+                        // a constructed state was already built by the transition that enters it
+                        let construct_state = if constructed {
                             quote! {
-                                #remap
-                                {
-                                    #body
+                                fn construct_state(&mut self, _context: & #ctx_ty) -> finny::FsmResult<()> {
+                                    match self. #field {
+                                        Some(#enum_ty :: #ty_name (_)) => Ok(()),
+                                        _ => Err(finny::FsmError::StateNotAllocated)
+                                    }
                                 }
                             }
-                        },
-                        None => {
+                        } else {
                             quote! {
-                                Default::default()
+                                fn construct_state(&mut self, context: & #ctx_ty) -> finny::FsmResult<()> {
+                                    self. #field = None;
+                                    self. #field = Some(#enum_ty :: #ty_name ( #new_state ));
+                                    Ok(())
+                                }
+                            }
+                        };
+
+                        quote! {
+                            fn try_state_ref(&self) -> Option<& #ty> {
+                                match self. #field {
+                                    Some(#enum_ty :: #ty_name (ref state)) => Some(state),
+                                    _ => None
+                                }
+                            }
+
+                            fn try_state_mut(&mut self) -> Option<&mut #ty> {
+                                match self. #field {
+                                    Some(#enum_ty :: #ty_name (ref mut state)) => Some(state),
+                                    _ => None
+                                }
+                            }
+
+                            #construct_state
+
+                            fn destruct_state(&mut self) {
+                                self. #field = None;
                             }
                         }
-                    };
+                    },
+                    // a state that can't be reached is never constructed
+                    None => {
+                        quote! {
+                            fn try_state_ref(&self) -> Option<& #ty> {
+                                None
+                            }
+
+                            fn try_state_mut(&mut self) -> Option<&mut #ty> {
+                                None
+                            }
+
+                            fn construct_state(&mut self, _context: & #ctx_ty) -> finny::FsmResult<()> {
+                                Ok(())
+                            }
+
+                            fn destruct_state(&mut self) {
+
+                            }
+                        }
+                    }
+                };
+
+                state_accessors.append_all(quote! {
+                    impl #fsm_generics_impl finny::FsmStateStorage< #fsm_ty #fsm_generics_type, #ty > for #states_store_ty #fsm_generics_type #fsm_generics_where {
+                        #accessors
+                    }
+                });
+            } else if constructed {
+                // stored lazily, the transitions that enter the state construct it
+                code_fields.append_all(quote! { #name: Option<#ty>, });
+                new_state_fields.append_all(quote! { #name: None, });
+
+                state_accessors.append_all(quote! {
+                    impl #fsm_generics_impl finny::FsmStateStorage< #fsm_ty #fsm_generics_type, #ty > for #states_store_ty #fsm_generics_type #fsm_generics_where {
+                        fn try_state_ref(&self) -> Option<& #ty> {
+                            self. #name .as_ref()
+                        }
+
+                        fn try_state_mut(&mut self) -> Option<&mut #ty> {
+                            self. #name .as_mut()
+                        }
+
+                        fn construct_state(&mut self, _context: & #ctx_ty) -> finny::FsmResult<()> {
+                            match self. #name {
+                                Some(_) => Ok(()),
+                                None => Err(finny::FsmError::StateNotAllocated)
+                            }
+                        }
+
+                        fn destruct_state(&mut self) {
+
+                        }
+                    }
+                });
+            } else {
+                code_fields.append_all(quote! { #name: #ty, });
+                new_state_fields.append_all(quote! { #name: #new_state, });
 
+                let construct_state = if state.reset_on_entry {
                     quote! {
-                        #name: {
-                            use finny::{FsmFactory};
-                            
-                            let sub_ctx = {
-                                #ctx_codegen
-                            };
-                            let fsm_backend = finny::FsmBackendImpl::<#ty>::new(sub_ctx)?;
-                            let fsm = <#ty>::new_submachine_backend(fsm_backend)?;
-                            fsm
-                        },
+                        fn construct_state(&mut self, context: & #ctx_ty) -> finny::FsmResult<()> {
+                            self. #name = #new_state;
+                            Ok(())
+                        }
                     }
-                }
-            };
-            new_state_fields.append_all(new_state_field);
+                } else {
+                    quote! {
+                        fn construct_state(&mut self, _context: & #ctx_ty) -> finny::FsmResult<()> {
+                            Ok(())
+                        }
+                    }
+                };
 
-            state_accessors.append_all(quote! {
-                impl #fsm_generics_impl core::convert::AsRef<#ty> for #states_store_ty #fsm_generics_type #fsm_generics_where {
-                    fn as_ref(&self) -> & #ty {
-                        &self. #name
+                state_accessors.append_all(quote! {
+                    impl #fsm_generics_impl core::convert::AsRef<#ty> for #states_store_ty #fsm_generics_type #fsm_generics_where {
+                        fn as_ref(&self) -> & #ty {
+                            &self. #name
+                        }
                     }
-                }
 
-                impl #fsm_generics_impl core::convert::AsMut<#ty> for #states_store_ty #fsm_generics_type #fsm_generics_where {
-                    fn as_mut(&mut self) -> &mut #ty {
-                        &mut self. #name
+                    impl #fsm_generics_impl core::convert::AsMut<#ty> for #states_store_ty #fsm_generics_type #fsm_generics_where {
+                        fn as_mut(&mut self) -> &mut #ty {
+                            &mut self. #name
+                        }
                     }
-                }
-            });
+
+                    impl #fsm_generics_impl finny::FsmStateStorage< #fsm_ty #fsm_generics_type, #ty > for #states_store_ty #fsm_generics_type #fsm_generics_where {
+                        fn try_state_ref(&self) -> Option<& #ty> {
+                            Some(&self. #name)
+                        }
+
+                        fn try_state_mut(&mut self) -> Option<&mut #ty> {
+                            Some(&mut self. #name)
+                        }
+
+                        #construct_state
+
+                        fn destruct_state(&mut self) {
+
+                        }
+                    }
+                });
+            }
         }
 
         let mut transition_states = TokenStream::new();
@@ -103,17 +233,98 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
                                 let state_from_ty = &state_from.ty;
                                 let state_to_ty = &state_to.ty;
 
-                                let state_from_field = &state_from.state_storage_field;
-                                let state_to_field = &state_to.state_storage_field;
-
-                                transition_states.append_all(quote! {
-                                    impl #fsm_generics_impl finny::FsmStateTransitionAsMut<#state_from_ty, #state_to_ty> for #states_store_ty #fsm_generics_type #fsm_generics_where {
-                                        fn as_state_transition_mut(&mut self) -> (&mut #state_from_ty, &mut #state_to_ty) {
-                                            (&mut self. #state_from_field, &mut self. #state_to_field)
+                                if fsm.fsm.codegen_options.state_storage_union {
+                                    let (_, field, enum_ty) = region_storage.iter().find(|(r, _, _)| r.region_id == region.region_id)
+                                        .ok_or(syn::Error::new(state_from_ty.span(), "Region not found, codegen bug!"))?;
+
+                                    let fsm_state_from = FsmTypes::new(state_from_ty, &fsm.base.fsm_generics);
+                                    let fsm_state_to = FsmTypes::new(state_to_ty, &fsm.base.fsm_generics);
+                                    let variant_from = fsm_state_from.get_fsm_no_generics_ty();
+                                    let variant_to = fsm_state_to.get_fsm_no_generics_ty();
+                                    let new_state = if fsm.fsm.constructed_states.contains(state_to_ty) {
+                                        quote! { return Err(finny::FsmError::StateNotAllocated) }
+                                    } else {
+                                        new_state_codegen(fsm, state_to)?
+                                    };
+
+                                    // the target state is constructed before the action runs, the previous state is dropped after it
+                                    transition_states.append_all(quote! {
+                                        impl #fsm_generics_impl finny::FsmStateTransitionStorage<#fsm_ty #fsm_generics_type, #state_from_ty, #state_to_ty> for #states_store_ty #fsm_generics_type #fsm_generics_where {
+                                            fn transition_states<C, A>(&mut self, context: &mut #ctx_ty, construct: C, action: A) -> finny::FsmResult<()>
+                                                where C: FnOnce(& #ctx_ty, & #state_from_ty) -> Option<#state_to_ty>,
+                                                      A: FnOnce(&mut #ctx_ty, &mut #state_from_ty, &mut #state_to_ty)
+                                            {
+                                                let from = match self. #field {
+                                                    Some(#enum_ty :: #variant_from (ref mut from)) => from,
+                                                    _ => return Err(finny::FsmError::StateNotAllocated)
+                                                };
+                                                let mut to = match construct(&*context, from) {
+                                                    Some(to) => to,
+                                                    None => {
+                                                        let context: & #ctx_ty = &*context;
+                                                        #new_state
+                                                    }
+                                                };
+                                                action(context, from, &mut to);
+                                                self. #field = Some(#enum_ty :: #variant_to (to));
+                                                Ok(())
+                                            }
+                                        }
+                                    });
+                                } else {
+                                    let state_from_field = &state_from.state_storage_field;
+                                    let state_to_field = &state_to.state_storage_field;
+
+                                    let from_constructed = fsm.fsm.constructed_states.contains(state_from_ty);
+                                    let to_constructed = fsm.fsm.constructed_states.contains(state_to_ty);
+
+                                    let reset_state_to = if to_constructed {
+                                        quote! { return Err(finny::FsmError::StateNotAllocated); }
+                                    } else if state_to.reset_on_entry {
+                                        let new_state = new_state_codegen(fsm, state_to)?;
+                                        quote! {
+                                            self. #state_to_field = {
+                                                let context: & #ctx_ty = &*context;
+                                                #new_state
+                                            };
                                         }
+                                    } else {
+                                        TokenStream::new()
+                                    };
+
+                                    let (from, to, assign_to) = (
+                                        if from_constructed { quote! { self. #state_from_field .as_mut().ok_or(finny::FsmError::StateNotAllocated)? } } else { quote! { &mut self. #state_from_field } },
+                                        if to_constructed { quote! { self. #state_to_field .as_mut().ok_or(finny::FsmError::StateNotAllocated)? } } else { quote! { &mut self. #state_to_field } },
+                                        if to_constructed { quote! { Some(to) } } else { quote! { to } }
+                                    );
+
+                                    if !from_constructed && !to_constructed {
+                                        transition_states.append_all(quote! {
+                                            impl #fsm_generics_impl finny::FsmStateTransitionAsMut<#state_from_ty, #state_to_ty> for #states_store_ty #fsm_generics_type #fsm_generics_where {
+                                                fn as_state_transition_mut(&mut self) -> (&mut #state_from_ty, &mut #state_to_ty) {
+                                                    (&mut self. #state_from_field, &mut self. #state_to_field)
+                                                }
+                                            }
+                                        });
                                     }
-                                });
 
+                                    transition_states.append_all(quote! {
+                                        impl #fsm_generics_impl finny::FsmStateTransitionStorage<#fsm_ty #fsm_generics_type, #state_from_ty, #state_to_ty> for #states_store_ty #fsm_generics_type #fsm_generics_where {
+                                            fn transition_states<C, A>(&mut self, context: &mut #ctx_ty, construct: C, action: A) -> finny::FsmResult<()>
+                                                where C: FnOnce(& #ctx_ty, & #state_from_ty) -> Option<#state_to_ty>,
+                                                      A: FnOnce(&mut #ctx_ty, &mut #state_from_ty, &mut #state_to_ty)
+                                            {
+                                                let from = #from;
+                                                match construct(&*context, &*from) {
+                                                    Some(to) => { self. #state_to_field = #assign_to; },
+                                                    None => { #reset_state_to }
+                                                }
+                                                action(context, from, #to);
+                                                Ok(())
+                                            }
+                                        }
+                                    });
+                                }
                             },
                             _ => ()
                         }
@@ -128,6 +339,8 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
                 #code_fields
                 _fsm: core::marker::PhantomData< #fsm_ty #fsm_generics_type >
             }
+
+            #region_enums
             
             impl #fsm_generics_impl finny::FsmStateFactory< #fsm_ty #fsm_generics_type > for #states_store_ty #fsm_generics_type #fsm_generics_where {
                 fn new_state(context: & #ctx_ty ) -> finny::FsmResult<Self> {
@@ -155,8 +368,7 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
         }
     };
     
-
-    let events_enum = {
+    let (events_enum, event_names) = {
 
         let submachines: Vec<_> = fsm.fsm.states.iter().filter_map(|(_, state)| {
             match &state.kind {
@@ -171,7 +383,7 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
         let mut as_ref_str = TokenStream::new();
 
         for (ty, _ev) in  fsm.fsm.events.iter() {
-            let ty_str = crate::utils::tokens_to_string(ty);
+            let ty_str = finny_model::utils::tokens_to_string(ty);
 
             variants.append_all(quote! { #ty ( #ty ),  });            
             as_ref_str.append_all(quote! { #event_enum_ty:: #ty(_) => #ty_str, });
@@ -181,7 +393,7 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
             let sub_fsm_event_ty = sub_fsm.get_fsm_events_ty();
             let sub_fsm_ty = sub_fsm.get_fsm_no_generics_ty();            
 
-            let sub_fsm_event_ty_str = crate::utils::tokens_to_string(&sub_fsm_event_ty);
+            let sub_fsm_event_ty_str = finny_model::utils::tokens_to_string(&sub_fsm_event_ty);
 
             variants.append_all(quote! {
                 #sub_fsm_ty ( #sub_fsm_event_ty ),
@@ -192,7 +404,7 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
         }
 
         let mut derives = TokenStream::new();
-        if fsm.fsm.codegen_options.event_debug {
+        if fsm.fsm.codegen_options.event_debug || fsm.fsm.codegen_options.inspect_debug {
             derives.append_all(quote! {
                 #[derive(Debug)]
             });
@@ -215,7 +427,7 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
             }
         };
 
-        evs
+        (evs, as_ref_str)
     };
     
     let transition_types = {
@@ -295,9 +507,48 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
                     FsmTransitionType::StateTransition(s @ FsmStateTransition { state_from: FsmTransitionState::None, .. }) => {
                         let initial_state_ty = &s.state_to.get_fsm_state()?.ty;
 
+                        if let Some(ref guard) = s.action.guard {
+                            let remap = remap_closure_inputs(&guard.inputs, vec![
+                                quote! { context }
+                            ].as_slice())?;
+
+                            let body = &guard.body;
+
+                            q.append_all(quote! {
+                                impl #fsm_generics_impl finny::FsmTransitionGuard<#fsm_ty #fsm_generics_type, finny::FsmEvent<#event_enum_ty>> for #ty #fsm_generics_where {
+                                    fn guard<'fsm_event, Q>(_event: &finny::FsmEvent<#event_enum_ty>, context: &finny::EventContext<'fsm_event, #fsm_ty #fsm_generics_type, Q>, _states: & #states_store_ty #fsm_generics_type) -> bool
+                                        where Q: finny::FsmEventQueue<#fsm_ty #fsm_generics_type>
+                                    {
+                                        #remap
+                                        let result = { #body };
+                                        result
+                                    }
+                                }
+                            });
+                        }
+
+                        let action_body = if let Some(ref action) = s.action.action {
+                            let remap = remap_closure_inputs(&action.inputs, vec![
+                                quote! { context }, quote! { state }
+                            ].as_slice())?;
+
+                            let body = &action.body;
+
+                            quote! {
+                                #remap
+                                { #body }
+                            }
+                        } else {
+                            TokenStream::new()
+                        };
+
                         q.append_all(quote! {
                             impl #fsm_generics_impl finny::FsmTransitionFsmStart<#fsm_ty #fsm_generics_type, #initial_state_ty > for #ty #fsm_generics_where {
-
+                                fn action<'fsm_event, Q>(context: &mut finny::EventContext<'fsm_event, #fsm_ty #fsm_generics_type, Q >, state: &mut #initial_state_ty)
+                                    where Q: finny::FsmEventQueue<#fsm_ty #fsm_generics_type>
+                                {
+                                    #action_body
+                                }
                             }
                         });
 
@@ -352,6 +603,22 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
                         let state_from_ty = &state_from.ty;
                         let state_to_ty = &state_to.ty;
 
+                        let construct_body = if let Some(ref construct) = s.action.construct {
+                            let remap = remap_closure_inputs(&construct.inputs, vec![
+                                quote! { event }, quote! { context }, quote! { from }
+                            ].as_slice())?;
+
+                            let body = &construct.body;
+
+                            quote! {
+                                #remap
+                                let state: #state_to_ty = { #body };
+                                Some(state)
+                            }
+                        } else {
+                            quote! { None }
+                        };
+
                         let a = quote! {
                             impl #fsm_generics_impl finny::FsmTransitionAction<#fsm_ty #fsm_generics_type, #event_ty, #state_from_ty, #state_to_ty> for #ty #fsm_generics_where {
                                 fn action<'fsm_event, Q>(event: & #event_ty , context: &mut finny::EventContext<'fsm_event, #fsm_ty #fsm_generics_type, Q >, from: &mut #state_from_ty, to: &mut #state_to_ty)
@@ -359,6 +626,10 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
                                 {
                                     #action_body
                                 }
+
+                                fn construct(event: & #event_ty, context: & #ctx_ty, from: & #state_from_ty) -> Option<#state_to_ty> {
+                                    #construct_body
+                                }
                             }
                         };
 
@@ -410,9 +681,9 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
                     };
 
                     match event {
-                        crate::parse::FsmTransitionEvent::Start => quote! { ev @ finny::FsmEvent::Start },
-                        crate::parse::FsmTransitionEvent::Stop => quote ! { ev @ finny::FsmEvent::Stop },
-                        crate::parse::FsmTransitionEvent::Event(ref ev) => {
+                        finny_model::parse::FsmTransitionEvent::Start => quote! { ev @ finny::FsmEvent::Start },
+                        finny_model::parse::FsmTransitionEvent::Stop => quote ! { ev @ finny::FsmEvent::Stop },
+                        finny_model::parse::FsmTransitionEvent::Event(ref ev) => {
                             let kind = &ev.ty;
                             quote! { finny::FsmEvent::Event(#event_enum_ty::#kind(ref ev)) }
                         }
@@ -441,7 +712,7 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
                 let fsm_sub_entry = match transition.ty {
                     FsmTransitionType::StateTransition(FsmStateTransition { state_to: FsmTransitionState::State(FsmState { kind: FsmStateKind::SubMachine(_), .. }), .. }) => {
                         quote! {
-                            <#transition_ty>::execute_on_sub_entry(&mut ctx, #region_id, &mut inspect_event_ctx);
+                            <#transition_ty>::execute_on_sub_entry(&mut ctx, #region_id, &mut inspect_event_ctx)?;
                         }
                     },
                     _ => TokenStream::new()
@@ -450,7 +721,7 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
                 let m = quote! {
                     ( #match_state , #match_event ) #guard => {
 
-                        <#transition_ty>::execute_transition(&mut ctx, &ev, #region_id, &mut inspect_event_ctx);
+                        <#transition_ty>::execute_transition(&mut ctx, &ev, #region_id, &mut inspect_event_ctx)?;
 
                         #fsm_sub_entry
                         
@@ -491,20 +762,110 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
                 sub_matches
             };
 
+            // exit the active state when stopping the machine
+            let region_stop = {
+                let mut stops = TokenStream::new();
+
+                for state_ty in &region.states {
+                    let state = fsm.fsm.states.get(state_ty).ok_or(syn::Error::new(state_ty.span(), "State not found, codegen bug!"))?;
+                    let fsm_state = FsmTypes::new(&state.ty, &fsm.base.fsm_generics);
+                    let variant = fsm_state.get_fsm_no_generics_ty();
+                    let ty = &state.ty;
+
+                    let stop_sub = match state.kind {
+                        FsmStateKind::SubMachine(_) => quote! {
+                            finny::stop_submachine::<_, #ty, _, _>(&mut ctx, &mut inspect_event_ctx)?;
+                        },
+                        FsmStateKind::Normal => TokenStream::new()
+                    };
+
+                    stops.append_all(quote! {
+                        ( finny::FsmCurrentState::State(#states_enum_ty :: #variant), finny::FsmEvent::Stop ) => {
+                            #stop_sub
+                            <#ty>::execute_on_stop(&mut ctx, #region_id, &mut inspect_event_ctx)?;
+                        },
+                    });
+                }
+
+                stops
+            };
+
+            // every initial state is guarded and all of the guards rejected the start
+            let region_no_initial_state = {
+                let starts: Vec<_> = region.transitions.iter().filter_map(|t| match &t.ty {
+                    FsmTransitionType::StateTransition(s) if matches!(s.event, finny_model::parse::FsmTransitionEvent::Start) => Some(s.action.guard.is_some()),
+                    _ => None
+                }).collect();
+
+                if !starts.is_empty() && starts.iter().all(|guarded| *guarded) {
+                    quote! {
+                        ( finny::FsmCurrentState::Stopped, finny::FsmEvent::Start ) => {
+                            no_initial_state = true;
+                        },
+                    }
+                } else {
+                    TokenStream::new()
+                }
+            };
+
             regions.append_all(quote! {
-                match (ctx.backend.current_states[#region_id], &event) {
+                {
+                    let mut inspect_event_ctx = inspect_event_ctx.for_region::<Self>(#region_id);
 
-                    #region_submachines
-                    
-                    #region_transitions
+                    match (ctx.backend.current_states[#region_id], &event) {
+
+                        #region_submachines
+                        
+                        #region_transitions
+
+                        #region_no_initial_state
 
-                    _ => {
-                        transition_misses += 1;
+                        #region_stop
+
+                        _ => {
+                            transition_misses += 1;
+                        }
                     }
                 }
             });
         }
 
+        let region_names: Vec<_> = fsm.fsm.regions.iter().map(|r| &r.name).collect();
+
+        let inspect_data = if fsm.fsm.codegen_options.inspect_debug {
+            let mut state_arms = TokenStream::new();
+            for (ty, state) in fsm.fsm.states.iter() {
+                let state_ty = FsmTypes::new(ty, &fsm.base.fsm_generics);
+                let variant = state_ty.get_fsm_no_generics_ty();
+                state_arms.append_all(match state.kind {
+                    FsmStateKind::Normal => quote! {
+                        #states_enum_ty :: #variant => <Self::States as finny::FsmStateStorage<Self, #ty>>::try_state_ref(states).map(|s| s as &dyn core::fmt::Debug),
+                    },
+                    FsmStateKind::SubMachine(_) => quote! {
+                        #states_enum_ty :: #variant => None,
+                    }
+                });
+            }
+
+            quote! {
+                fn inspect_event(event: &Self::Events) -> Option<&dyn core::fmt::Debug> {
+                    Some(event)
+                }
+
+                fn inspect_state(states: &Self::States, state: #states_enum_ty) -> Option<&dyn core::fmt::Debug> {
+                    match state {
+                        #state_arms
+                    }
+                }
+
+                fn inspect_context(context: &Self::Context) -> Option<&dyn core::fmt::Debug> {
+                    Some(context)
+                }
+            }
+        } else {
+            TokenStream::new()
+        };
+
         quote! {
               
             impl #fsm_generics_impl finny::FsmBackend for #fsm_ty #fsm_generics_type
@@ -513,6 +874,7 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
                 type Context = #ctx_ty;
                 type States = #states_store_ty #fsm_generics_type;
                 type Events = #event_enum_ty;
+                const REGION_NAMES: &'static [&'static str] = &[ #(#region_names),* ];
 
                 fn dispatch_event<Q, I>(mut ctx: finny::DispatchContext<Self, Q, I>, event: finny::FsmEvent<Self::Events>) -> finny::FsmDispatchResult
                     where Q: finny::FsmEventQueue<Self>,
@@ -521,21 +883,49 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
                     use finny::{FsmTransitionGuard, FsmTransitionAction, FsmAction, FsmState, FsmTransitionFsmStart};
 
                     let mut transition_misses = 0;
+                    #[allow(unused_mut)]
+                    let mut no_initial_state = false;
+
+                    let envelope = ctx.queue.current_envelope();
+                    let mut inspect_event_ctx = ctx.inspect.new_event::<Self>(&event, envelope.as_ref());
+                    if let finny::FsmEvent::Event(ref ev) = event {
+                        if let Some(data) = Self::inspect_event(ev) {
+                            inspect_event_ctx.on_event_data(data);
+                        }
+                    }
 
-                    let mut inspect_event_ctx = ctx.inspect.new_event::<Self>(&event);
-                    
-                    #regions
+                    let mut dispatch = || -> finny::FsmDispatchResult {
+                        #regions
 
-                    let result = if transition_misses == #region_count {
-                        Err(finny::FsmError::NoTransition)
-                    } else {
-                        Ok(())
+                        if no_initial_state {
+                            Err(finny::FsmError::NoInitialState)
+                        } else if transition_misses == #region_count {
+                            Err(finny::FsmError::NoTransition)
+                        } else {
+                            Ok(())
+                        }
                     };
+                    let result = dispatch();
+
+                    if let Err(ref e) = result {
+                        if let finny::FsmError::NoTransition = e {
+                            inspect_event_ctx.on_no_transition();
+                        }
+                        inspect_event_ctx.on_error(e);
+                    }
 
                     inspect_event_ctx.event_done();
 
                     result
                 }
+
+                fn event_name(event: &Self::Events) -> &'static str {
+                    match event {
+                        #event_names
+                    }
+                }
+
+                #inspect_data
             }
         }
     };
@@ -591,6 +981,112 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
         states
     };
 
+    let region_markers = {
+        let mut markers = TokenStream::new();
+
+        for region in &fsm.fsm.regions {
+            if let Some(ref marker_ty) = region.marker_ty {
+                let region_id = region.region_id;
+                let name = &region.name;
+
+                markers.append_all(quote! {
+                    pub struct #marker_ty;
+
+                    impl #fsm_generics_impl finny::FsmRegion<#fsm_ty #fsm_generics_type> for #marker_ty #fsm_generics_where {
+                        const REGION_ID: finny::FsmRegionId = #region_id;
+                        const NAME: &'static str = #name;
+                    }
+                });
+            }
+        }
+
+        markers
+    };
+
+    let description = {
+        let state_name_of = |st: &FsmTransitionState| match st.state_name() {
+            None => quote! { None },
+            Some(name) => quote! { Some(#name) }
+        };
+
+        let fsm_name = type_name(fsm_ty);
+
+        let states: Vec<_> = fsm.fsm.states.iter().map(|(ty, state)| {
+            let name = type_name(ty);
+            let sub_machine = match state.kind {
+                FsmStateKind::Normal => quote! { None },
+                FsmStateKind::SubMachine(_) => quote! { Some(<#ty as finny::FsmDescription>::META) }
+            };
+            quote! {
+                finny::FsmStateMeta { name: #name, sub_machine: #sub_machine }
+            }
+        }).collect();
+
+        let events: Vec<_> = fsm.fsm.events.keys().map(type_name).collect();
+
+        let regions: Vec<_> = fsm.fsm.regions.iter().map(|region| {
+            let name = &region.name;
+            let initial_states: Vec<_> = region.initial_states.iter().map(type_name).collect();
+            let states: Vec<_> = region.states.iter().map(type_name).collect();
+            quote! {
+                finny::FsmRegionMeta { name: #name, initial_states: &[ #(#initial_states),* ], states: &[ #(#states),* ] }
+            }
+        }).collect();
+
+        let mut transitions = vec![];
+        for region in &fsm.fsm.regions {
+            let region_id = region.region_id;
+            for transition in &region.transitions {
+                let name = type_name(&transition.transition_ty);
+
+                let FsmTransitionEndpoints { kind, event, from, to, action } = transition.endpoints();
+                let kind = match kind {
+                    FsmTransitionKind::Internal => quote! { Internal },
+                    FsmTransitionKind::SelfTransition => quote! { SelfTransition },
+                    FsmTransitionKind::Normal => quote! { Normal }
+                };
+
+                let event = match event {
+                    finny_model::parse::FsmTransitionEvent::Start => quote! { finny::FsmTransitionEventMeta::Start },
+                    finny_model::parse::FsmTransitionEvent::Stop => quote! { finny::FsmTransitionEventMeta::Stop },
+                    finny_model::parse::FsmTransitionEvent::Event(ev) => {
+                        let ev = type_name(&ev.ty);
+                        quote! { finny::FsmTransitionEventMeta::Event(#ev) }
+                    }
+                };
+                let from = state_name_of(from);
+                let to = state_name_of(to);
+                let has_guard = action.guard.is_some();
+                let has_action = action.action.is_some();
+
+                transitions.push(quote! {
+                    finny::FsmTransitionMeta {
+                        name: #name,
+                        region: #region_id,
+                        kind: finny::FsmTransitionKindMeta:: #kind,
+                        event: #event,
+                        from: #from,
+                        to: #to,
+                        has_guard: #has_guard,
+                        has_action: #has_action
+                    }
+                });
+            }
+        }
+
+        quote! {
+            impl #fsm_generics_impl finny::FsmDescription for #fsm_ty #fsm_generics_type #fsm_generics_where {
+                const META: &'static finny::FsmMeta = &finny::FsmMeta {
+                    name: #fsm_name,
+                    states: &[ #(#states),* ],
+                    events: &[ #(#events),* ],
+                    regions: &[ #(#regions),* ],
+                    transitions: &[ #(#transitions),* ]
+                };
+            }
+        }
+    };
+
     let builder = {
 
         quote! {
@@ -637,6 +1133,10 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
 
         #dispatch
 
+        #region_markers
+
+        #description
+
         #builder
     };
 
@@ -645,8 +1145,72 @@ pub fn generate_fsm_code(fsm: &FsmFnInput, attr: TokenStream, input: TokenStream
         #[allow(dead_code)]
     });
 
-    q.append_all(attr);
     q.append_all(input);
 
     Ok(q.into())
-}
\ No newline at end of file
+}
+/// The expression that builds a new instance of the state, with the FSM's context in scope as `context`.
+fn new_state_codegen(fsm: &FsmFnInput, state: &FsmState) -> syn::Result<TokenStream> {
+    let fsm_ty = &fsm.base.fsm_ty;
+    let (_, fsm_generics_type, _) = fsm.base.fsm_generics.split_for_impl();
+    let state_ty = FsmTypes::new(&state.ty, &fsm.base.fsm_generics);
+    let ty = state_ty.get_fsm_ty();
+
+    let q = match state.kind {
+        FsmStateKind::Normal => {
+            match &state.constructor {
+                Some(c) => {
+                    let remap = remap_closure_inputs(&c.inputs, &[quote!{ context }])?;
+                    let body = &c.body;
+                    quote! {
+                        {
+                            #remap
+                            let state: #ty = { #body };
+                            state
+                        }
+                    }
+                },
+                None => {
+                    quote! {
+                        < #ty as finny::FsmStateFactory< #fsm_ty #fsm_generics_type > >::new_state(context)?
+                    }
+                }
+            }
+        }
+        FsmStateKind::SubMachine(ref sub) => {
+
+            let ctx_codegen = match &sub.context_constructor {
+                Some(c) => {
+                    let remap = remap_closure_inputs(&c.inputs, &[quote!{ context }])?;
+                    let body = &c.body;
+                    quote! {
+                        #remap
+                        {
+                            #body
+                        }
+                    }
+                },
+                None => {
+                    quote! {
+                        Default::default()
+                    }
+                }
+            };
+
+            quote! {
+                {
+                    use finny::{FsmFactory};
+                    
+                    let sub_ctx = {
+                        #ctx_codegen
+                    };
+                    let fsm_backend = finny::FsmBackendImpl::<#ty>::new(sub_ctx)?;
+                    let fsm = <#ty>::new_submachine_backend(fsm_backend)?;
+                    fsm
+                }
+            }
+        }
+    };
+
+    Ok(q)
+}
@@ -0,0 +1,648 @@
+//! Machines described in SCXML or JSON files. The description is translated into a builder
+//! function, which then goes through the same parser and code generator as `#[finny_fsm]`.
+
+use std::path::PathBuf;
+
+use proc_macro2::TokenStream;
+use quote::{TokenStreamExt, quote};
+use syn::{Token, parse::{Parse, ParseStream}};
+
+use finny_model::{parse::FsmFnInput, utils::to_field_name};
+
+use crate::codegen::generate_fsm_code;
+
+/// The input of the `finny_fsm_from_file!` macro: `"path", context = Type, name = Ident`. The
+/// path is relative to the crate's manifest directory.
+pub struct FsmFromFileInput {
+    pub path: syn::LitStr,
+    pub context_ty: syn::Type,
+    pub fsm_ty: Option<syn::Ident>
+}
+
+impl Parse for FsmFromFileInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path: syn::LitStr = input.parse()?;
+        let mut context_ty = None;
+        let mut fsm_ty = None;
+
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() { break; }
+
+            let key: syn::Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            match key.to_string().as_str() {
+                "context" => { context_ty = Some(input.parse()?); },
+                "name" => { fsm_ty = Some(input.parse()?); },
+                _ => { return Err(syn::Error::new(key.span(), "Unsupported option, expected 'context' or 'name'.")); }
+            }
+        }
+
+        let context_ty = context_ty.ok_or(syn::Error::new(path.span(), "Missing the context type, use 'context = MyContext'."))?;
+
+        Ok(FsmFromFileInput {
+            path,
+            context_ty,
+            fsm_ty
+        })
+    }
+}
+
+/// A machine, as described in a file.
+#[derive(Debug, Default)]
+pub struct FileFsm {
+    pub name: Option<String>,
+    /// A single unnamed region, or the named ones.
+    pub regions: Vec<FileRegion>,
+    pub states: Vec<FileState>
+}
+
+#[derive(Debug, Default)]
+pub struct FileRegion {
+    pub name: Option<String>,
+    pub initial_states: Vec<FileInitialState>,
+    pub states: Vec<String>
+}
+
+#[derive(Debug, Default)]
+pub struct FileInitialState {
+    pub state: String,
+    pub guard: Option<String>,
+    pub action: Option<String>
+}
+
+#[derive(Debug, Default)]
+pub struct FileState {
+    pub name: String,
+    pub on_entry: Option<String>,
+    pub on_exit: Option<String>,
+    pub transitions: Vec<FileTransition>
+}
+
+#[derive(Debug)]
+pub enum FileTransitionKind {
+    Normal(String),
+    SelfTransition,
+    Internal
+}
+
+#[derive(Debug)]
+pub struct FileTransition {
+    pub event: String,
+    pub kind: FileTransitionKind,
+    pub guard: Option<String>,
+    pub action: Option<String>
+}
+
+pub fn generate_fsm_from_file(input: TokenStream) -> syn::Result<TokenStream> {
+    let input: FsmFromFileInput = syn::parse2(input)?;
+    let err = |msg: String| syn::Error::new(input.path.span(), msg);
+
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").map_err(|_| err("The CARGO_MANIFEST_DIR variable is not set.".into()))?;
+    let path = PathBuf::from(manifest_dir).join(input.path.value());
+    let contents = std::fs::read_to_string(&path).map_err(|e| err(format!("Can't read '{}': {}", path.display(), e)))?;
+
+    let model = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => parse_json(&contents),
+        Some("scxml") | Some("xml") => parse_scxml(&contents),
+        _ => Err("Unsupported file type, expected a '.scxml' or a '.json' file.".into())
+    }.map_err(|e| err(format!("Invalid machine in '{}': {}", path.display(), e)))?;
+
+    let fsm_ty = match (&input.fsm_ty, &model.name) {
+        (Some(ty), _) => ty.clone(),
+        (None, Some(name)) => syn::parse_str(name).map_err(|_| err(format!("The machine's name '{}' isn't a valid type name, use 'name = MyFsm'.", name)))?,
+        (None, None) => { return Err(err("The file doesn't name the machine, use 'name = MyFsm'.".into())); }
+    };
+    let fsm_ty: syn::Type = syn::parse_quote! { #fsm_ty };
+    let context_ty = &input.context_ty;
+
+    let body = builder_body(&model).map_err(err)?;
+    let fn_name = syn::Ident::new(&format!("build_{}", to_field_name(&fsm_ty)?), input.path.span());
+
+    // the parser expects the builder's types by name, the emitted function uses the full paths
+    let parse_fn = quote! {
+        fn #fn_name(mut fsm: FsmBuilder<#fsm_ty, #context_ty>) -> BuiltFsm {
+            #body
+            fsm.build()
+        }
+    };
+    let emit_fn = quote! {
+        fn #fn_name(mut fsm: finny::decl::FsmBuilder<#fsm_ty, #context_ty>) -> finny::decl::BuiltFsm {
+            #body
+            fsm.build()
+        }
+    };
+
+    let parsed = FsmFnInput::parse(TokenStream::new(), parse_fn)?;
+    let mut q = generate_fsm_code(&parsed, emit_fn)?;
+
+    // rebuild when the file changes
+    let path_str = path.to_string_lossy().into_owned();
+    q.append_all(quote! {
+        const _: &[u8] = include_bytes!(#path_str);
+    });
+
+    Ok(q)
+}
+
+fn parse_ty(name: &str) -> Result<syn::Type, String> {
+    syn::parse_str(name).map_err(|_| format!("'{}' isn't a valid type name.", name))
+}
+
+fn parse_fn(name: &str) -> Result<syn::Path, String> {
+    let name = name.trim().trim_end_matches(';').trim_end().trim_end_matches("()");
+    syn::parse_str(name).map_err(|_| format!("'{}' isn't a valid function name.", name))
+}
+
+/// The builder calls that declare the machine. Guards and actions are bound to the functions by name:
+///
+/// * initial state guard: `fn(&Context) -> bool`
+/// * initial state action: `fn(&mut Context)`
+/// * entry and exit actions: `fn(&mut State, &mut Context)`
+/// * transition guard: `fn(&Event, &Context) -> bool`
+/// * transition action: `fn(&Event, &mut Context)`
+fn builder_body(model: &FileFsm) -> Result<TokenStream, String> {
+    let mut q = TokenStream::new();
+
+    // every referenced state has to be declared in the file
+    let known_state = |name: &str, referenced_by: &str| -> Result<(), String> {
+        match model.states.iter().any(|s| s.name == name) {
+            true => Ok(()),
+            false => Err(format!("The {} references the unknown state '{}'.", referenced_by, name))
+        }
+    };
+    for region in &model.regions {
+        for initial in &region.initial_states {
+            known_state(&initial.state, "initial state")?;
+        }
+        for state in &region.states {
+            known_state(state, "region")?;
+        }
+    }
+    for state in &model.states {
+        for transition in &state.transitions {
+            if let FileTransitionKind::Normal(ref target) = transition.kind {
+                known_state(target, &format!("transition from '{}'", state.name))?;
+            }
+        }
+    }
+
+    let named_regions = match model.regions.as_slice() {
+        [FileRegion { name: None, .. }] => false,
+        regions if regions.iter().all(|r| r.name.is_some()) => true,
+        _ => { return Err("Either declare a single region or name all of them.".into()); }
+    };
+
+    for region in &model.regions {
+        let region_builder = match region.name {
+            Some(ref name) if named_regions => {
+                let ty = parse_ty(name)?;
+                quote! { fsm.region::<#ty>() }
+            },
+            _ => quote! { fsm }
+        };
+
+        if named_regions && !region.states.is_empty() {
+            let states = region.states.iter().map(|s| parse_ty(s)).collect::<Result<Vec<_>, _>>()?;
+            q.append_all(quote! { #region_builder #( .state::<#states>() )*; });
+        }
+
+        for initial in &region.initial_states {
+            let ty = parse_ty(&initial.state)?;
+            let guard = match initial.guard {
+                Some(ref guard) => {
+                    let guard = parse_fn(guard)?;
+                    quote! { .guard(|ctx| #guard(&**ctx)) }
+                },
+                None => TokenStream::new()
+            };
+            let action = match initial.action {
+                Some(ref action) => {
+                    let action = parse_fn(action)?;
+                    quote! { .action(|ctx, _state| #action(&mut **ctx)) }
+                },
+                None => TokenStream::new()
+            };
+            q.append_all(quote! { #region_builder.initial_state::<#ty>() #guard #action; });
+        }
+    }
+
+    for state in &model.states {
+        let ty = parse_ty(&state.name)?;
+
+        let on_entry = match state.on_entry {
+            Some(ref f) => {
+                let f = parse_fn(f)?;
+                quote! { .on_entry(|state, ctx| #f(state, &mut **ctx)) }
+            },
+            None => TokenStream::new()
+        };
+        let on_exit = match state.on_exit {
+            Some(ref f) => {
+                let f = parse_fn(f)?;
+                quote! { .on_exit(|state, ctx| #f(state, &mut **ctx)) }
+            },
+            None => TokenStream::new()
+        };
+        q.append_all(quote! { fsm.state::<#ty>() #on_entry #on_exit; });
+
+        for transition in &state.transitions {
+            let event_ty = parse_ty(&transition.event)?;
+            let guard = match transition.guard {
+                Some(ref guard) => {
+                    let guard = parse_fn(guard)?;
+                    quote! { .guard(|ev, ctx, _states| #guard(ev, &**ctx)) }
+                },
+                None => TokenStream::new()
+            };
+
+            let t = match transition.kind {
+                FileTransitionKind::Normal(ref target) => {
+                    let target_ty = parse_ty(target)?;
+                    let action = match transition.action {
+                        Some(ref action) => {
+                            let action = parse_fn(action)?;
+                            quote! { .action(|ev, ctx, _from, _to| #action(ev, &mut **ctx)) }
+                        },
+                        None => TokenStream::new()
+                    };
+                    quote! { .transition_to::<#target_ty>() #guard #action }
+                },
+                FileTransitionKind::SelfTransition | FileTransitionKind::Internal => {
+                    let action = match transition.action {
+                        Some(ref action) => {
+                            let action = parse_fn(action)?;
+                            quote! { .action(|ev, ctx, _state| #action(ev, &mut **ctx)) }
+                        },
+                        None => TokenStream::new()
+                    };
+                    match transition.kind {
+                        FileTransitionKind::SelfTransition => quote! { .self_transition() #guard #action },
+                        _ => quote! { .internal_transition() #guard #action }
+                    }
+                }
+            };
+
+            q.append_all(quote! { fsm.state::<#ty>().on_event::<#event_ty>() #t; });
+        }
+    }
+
+    Ok(q)
+}
+
+/// The JSON description of a machine:
+///
+/// ```json
+/// {
+///     "name": "Door",
+///     "initial": "Closed",
+///     "states": [
+///         { "name": "Closed", "on_entry": "closed_entry", "transitions": [
+///             { "event": "Open", "target": "Opened", "guard": "is_unlocked", "action": "log_open" },
+///             { "event": "Knock", "action": "count_knock" }
+///         ] },
+///         { "name": "Opened" }
+///     ]
+/// }
+/// ```
+///
+/// The `initial` state can also be a list of `{ "state", "guard", "action" }` alternatives. Named
+/// regions are declared with `"regions": [{ "name", "initial", "states" }]` instead. A transition without
+/// a target is an internal transition and one that targets its own state is a self transition.
+fn parse_json(contents: &str) -> Result<FileFsm, String> {
+    use serde_json::Value;
+
+    let root: Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+
+    fn str_field(v: &Value, field: &str) -> Result<Option<String>, String> {
+        match v.get(field) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s.clone())),
+            Some(_) => Err(format!("The field '{}' has to be a string.", field))
+        }
+    }
+
+    fn required_str_field(v: &Value, field: &str) -> Result<String, String> {
+        str_field(v, field)?.ok_or(format!("Missing the field '{}'.", field))
+    }
+
+    fn array_field<'a>(v: &'a Value, field: &str) -> Result<&'a [Value], String> {
+        match v.get(field) {
+            None | Some(Value::Null) => Ok(&[]),
+            Some(Value::Array(a)) => Ok(a.as_slice()),
+            Some(_) => Err(format!("The field '{}' has to be an array.", field))
+        }
+    }
+
+    fn initial_states(v: Option<&Value>) -> Result<Vec<FileInitialState>, String> {
+        fn initial_state(v: &Value) -> Result<FileInitialState, String> {
+            match v {
+                Value::String(s) => Ok(FileInitialState { state: s.clone(), ..Default::default() }),
+                Value::Object(_) => Ok(FileInitialState {
+                    state: required_str_field(v, "state")?,
+                    guard: str_field(v, "guard")?,
+                    action: str_field(v, "action")?
+                }),
+                _ => Err("An initial state has to be a string or an object.".into())
+            }
+        }
+
+        match v {
+            Some(Value::Array(a)) => a.iter().map(initial_state).collect(),
+            Some(v) => Ok(vec![initial_state(v)?]),
+            None => Err("Missing the initial state.".into())
+        }
+    }
+
+    let mut fsm = FileFsm {
+        name: str_field(&root, "name")?,
+        ..Default::default()
+    };
+
+    match root.get("regions") {
+        Some(_) => {
+            for region in array_field(&root, "regions")? {
+                let states = array_field(region, "states")?.iter()
+                    .map(|s| s.as_str().map(|s| s.to_string()).ok_or("The region's states have to be strings.".to_string()))
+                    .collect::<Result<_, _>>()?;
+
+                fsm.regions.push(FileRegion {
+                    name: Some(required_str_field(region, "name")?),
+                    initial_states: initial_states(region.get("initial"))?,
+                    states
+                });
+            }
+        },
+        None => {
+            fsm.regions.push(FileRegion {
+                name: None,
+                initial_states: initial_states(root.get("initial"))?,
+                states: vec![]
+            });
+        }
+    }
+
+    for state in array_field(&root, "states")? {
+        let name = required_str_field(state, "name")?;
+        let mut transitions = vec![];
+
+        for transition in array_field(state, "transitions")? {
+            let kind = match str_field(transition, "target")? {
+                None => FileTransitionKind::Internal,
+                Some(target) if target == name => FileTransitionKind::SelfTransition,
+                Some(target) => FileTransitionKind::Normal(target)
+            };
+
+            transitions.push(FileTransition {
+                event: required_str_field(transition, "event")?,
+                kind,
+                guard: str_field(transition, "guard")?,
+                action: str_field(transition, "action")?
+            });
+        }
+
+        fsm.states.push(FileState {
+            on_entry: str_field(state, "on_entry")?,
+            on_exit: str_field(state, "on_exit")?,
+            name,
+            transitions
+        });
+    }
+
+    Ok(fsm)
+}
+
+/// The W3C SCXML description of a machine. A top-level `<parallel>` declares named regions. Compound
+/// states aren't supported, sub-machines have to be declared separately.
+///
+/// State ids can be qualified with dots, the last segment names the state's type. Conditions and
+/// `<script>` contents name the guard and action functions. An initial state that only has eventless
+/// transitions selects the initial state using their conditions, as exported by `finny::render::write_scxml`.
+fn parse_scxml(contents: &str) -> Result<FileFsm, String> {
+    let doc = roxmltree::Document::parse(contents).map_err(|e| e.to_string())?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "scxml" {
+        return Err("The root element has to be 'scxml'.".into());
+    }
+
+    let mut fsm = FileFsm {
+        name: root.attribute("name").map(|s| s.to_string()),
+        ..Default::default()
+    };
+
+    let parallel: Vec<_> = root.children().filter(|n| n.has_tag_name_local("parallel")).collect();
+    match parallel.as_slice() {
+        [] => {
+            let (region, states) = scxml_region(root, None)?;
+            fsm.regions.push(region);
+            fsm.states.extend(states);
+        },
+        [parallel] => {
+            for region_node in parallel.children().filter(|n| n.has_tag_name_local("state")) {
+                let id = region_node.attribute("id").ok_or("A region is missing its id.")?;
+                let name = id.rsplit(['-', '.']).next().unwrap_or(id).to_string();
+                let (region, states) = scxml_region(region_node, Some(name))?;
+                fsm.regions.push(region);
+                fsm.states.extend(states);
+            }
+        },
+        _ => { return Err("Only a single top-level 'parallel' element is supported.".into()); }
+    }
+
+    Ok(fsm)
+}
+
+trait ScxmlNode {
+    fn has_tag_name_local(&self, name: &str) -> bool;
+}
+
+impl<'a, 'input> ScxmlNode for roxmltree::Node<'a, 'input> {
+    fn has_tag_name_local(&self, name: &str) -> bool {
+        self.is_element() && self.tag_name().name() == name
+    }
+}
+
+fn scxml_state_name(id: &str) -> String {
+    id.rsplit('.').next().unwrap_or(id).to_string()
+}
+
+/// The function named by the `<script>` elements within this node.
+fn scxml_script(node: roxmltree::Node) -> Result<Option<String>, String> {
+    let scripts: Vec<_> = node.children().filter(|n| n.has_tag_name_local("script")).collect();
+    match scripts.as_slice() {
+        [] => Ok(None),
+        [script] => Ok(script.text().map(|t| t.trim().to_string())),
+        _ => Err("Only a single 'script' element is supported.".into())
+    }
+}
+
+fn scxml_region(node: roxmltree::Node, name: Option<String>) -> Result<(FileRegion, Vec<FileState>), String> {
+    let state_nodes: Vec<_> = node.children().filter(|n| n.has_tag_name_local("state") || n.has_tag_name_local("final")).collect();
+
+    let initial_id = match node.attribute("initial") {
+        Some(initial) => initial.to_string(),
+        None => {
+            let initial_transition = node.children()
+                .filter(|n| n.has_tag_name_local("initial"))
+                .flat_map(|n| n.children())
+                .find(|n| n.has_tag_name_local("transition"))
+                .and_then(|t| t.attribute("target"));
+
+            match (initial_transition, state_nodes.first()) {
+                (Some(target), _) => target.to_string(),
+                (None, Some(first)) => first.attribute("id").ok_or("A state is missing its id.")?.to_string(),
+                (None, None) => { return Err("A region has no states.".into()); }
+            }
+        }
+    };
+
+    let mut region = FileRegion { name, ..Default::default() };
+    let mut states = vec![];
+
+    for state_node in state_nodes {
+        let id = state_node.attribute("id").ok_or("A state is missing its id.")?;
+        let transition_nodes: Vec<_> = state_node.children().filter(|n| n.has_tag_name_local("transition")).collect();
+
+        if state_node.children().any(|n| n.has_tag_name_local("state") || n.has_tag_name_local("parallel")) {
+            return Err(format!("The state '{}' is a compound state, declare it as a separate sub-machine.", id));
+        }
+
+        // the eventless transitions pick the initial state
+        if id == initial_id && !transition_nodes.is_empty() && transition_nodes.iter().all(|t| t.attribute("event").is_none()) {
+            for t in transition_nodes {
+                let target = t.attribute("target").ok_or(format!("The initial transition in '{}' is missing its target.", id))?;
+                region.initial_states.push(FileInitialState {
+                    state: scxml_state_name(target),
+                    guard: t.attribute("cond").map(|s| s.to_string()),
+                    action: scxml_script(t)?
+                });
+            }
+            continue;
+        }
+
+        if id == initial_id {
+            region.initial_states.push(FileInitialState { state: scxml_state_name(id), ..Default::default() });
+        }
+
+        let name = scxml_state_name(id);
+        let mut transitions = vec![];
+        for t in transition_nodes {
+            let event = t.attribute("event").ok_or(format!("The state '{}' has an eventless transition, which isn't supported.", id))?;
+            if event.split_whitespace().count() != 1 {
+                return Err(format!("The transition in '{}' has to be triggered by a single event.", id));
+            }
+
+            let kind = match t.attribute("target") {
+                None => FileTransitionKind::Internal,
+                Some(target) if target.split_whitespace().count() != 1 => {
+                    return Err(format!("The transition in '{}' has to have a single target.", id));
+                },
+                Some(target) if target == id => FileTransitionKind::SelfTransition,
+                Some(target) => FileTransitionKind::Normal(scxml_state_name(target))
+            };
+
+            transitions.push(FileTransition {
+                event: event.to_string(),
+                kind,
+                guard: t.attribute("cond").map(|s| s.to_string()),
+                action: scxml_script(t)?
+            });
+        }
+
+        let on_entry = match state_node.children().find(|n| n.has_tag_name_local("onentry")) {
+            Some(n) => scxml_script(n)?,
+            None => None
+        };
+        let on_exit = match state_node.children().find(|n| n.has_tag_name_local("onexit")) {
+            Some(n) => scxml_script(n)?,
+            None => None
+        };
+
+        region.states.push(name.clone());
+        states.push(FileState {
+            name,
+            on_entry,
+            on_exit,
+            transitions
+        });
+    }
+
+    if region.initial_states.is_empty() {
+        return Err(format!("The initial state '{}' wasn't found.", initial_id));
+    }
+
+    Ok((region, states))
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::{LineColumn, TokenStream};
+
+    use super::{builder_body, generate_fsm_from_file, parse_json, parse_scxml};
+
+    fn json_body(contents: &str) -> Result<TokenStream, String> {
+        builder_body(&parse_json(contents)?)
+    }
+
+    /// Writes the file and runs the macro, with the path as the first token of the input.
+    fn from_file(file_name: &str, contents: &str, options: &str) -> syn::Error {
+        let path = std::env::temp_dir().join(format!("finny_from_file_{}_{}", std::process::id(), file_name));
+        std::fs::write(&path, contents).unwrap();
+        let input: TokenStream = format!("{:?}{}", path.to_str().unwrap(), options).parse().unwrap();
+        let err = generate_fsm_from_file(input).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        err
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        assert!(parse_json(r#"{ "name": "Door", "states": [ "#).unwrap_err().starts_with("EOF while parsing"));
+        assert_eq!("The field 'states' has to be an array.", parse_json(r#"{ "initial": "A", "states": {} }"#).unwrap_err());
+        assert_eq!("Missing the field 'event'.", parse_json(r#"{ "initial": "A", "states": [{ "name": "A", "transitions": [{ "target": "A" }] }] }"#).unwrap_err());
+        assert_eq!("An initial state has to be a string or an object.", parse_json(r#"{ "initial": 1, "states": [] }"#).unwrap_err());
+
+        assert!(parse_scxml("<scxml><state id='A'>").is_err());
+        assert_eq!("The root element has to be 'scxml'.", parse_scxml("<machine/>").unwrap_err());
+        assert_eq!("The state 'A' has an eventless transition, which isn't supported.",
+            parse_scxml("<scxml initial='B'><state id='B'/><state id='A'><transition target='B'/></state></scxml>").unwrap_err());
+    }
+
+    #[test]
+    fn test_unknown_references() {
+        assert_eq!("The transition from 'A' references the unknown state 'Ghost'.",
+            json_body(r#"{ "initial": "A", "states": [{ "name": "A", "transitions": [{ "event": "Go", "target": "Ghost" }] }] }"#).unwrap_err());
+        assert_eq!("The initial state references the unknown state 'Ghost'.",
+            json_body(r#"{ "initial": "Ghost", "states": [{ "name": "A" }] }"#).unwrap_err());
+        assert_eq!("The region references the unknown state 'Ghost'.",
+            json_body(r#"{ "regions": [{ "name": "R", "initial": "A", "states": ["A", "Ghost"] }], "states": [{ "name": "A" }] }"#).unwrap_err());
+        assert_eq!("'Go Now' isn't a valid type name.",
+            json_body(r#"{ "initial": "A", "states": [{ "name": "A", "transitions": [{ "event": "Go Now" }] }] }"#).unwrap_err());
+        assert_eq!("'not a fn' isn't a valid function name.",
+            json_body(r#"{ "initial": "A", "states": [{ "name": "A", "on_entry": "not a fn" }] }"#).unwrap_err());
+    }
+
+    #[test]
+    fn test_missing_initial_state() {
+        assert_eq!("Missing the initial state.", parse_json(r#"{ "states": [{ "name": "A" }] }"#).unwrap_err());
+        assert_eq!("The initial state 'Ghost' wasn't found.", parse_scxml("<scxml initial='Ghost'><state id='A'/></scxml>").unwrap_err());
+    }
+
+    #[test]
+    fn test_error_spans() {
+        let err = from_file("missing.json", r#"{ "name": "Door", "states": [{ "name": "A" }] }"#, ", context = ()");
+        assert!(err.to_string().ends_with("missing.json': Missing the initial state."), "{}", err);
+        assert_eq!(LineColumn { line: 1, column: 0 }, err.span().start());
+
+        let err = from_file("unknown.json", r#"{ "initial": "Ghost", "states": [{ "name": "A" }] }"#, ", context = (), name = Door");
+        assert_eq!("The initial state references the unknown state 'Ghost'.", err.to_string());
+        assert_eq!(LineColumn { line: 1, column: 0 }, err.span().start());
+
+        let err = from_file("unnamed.json", r#"{ "initial": "A", "states": [{ "name": "A" }] }"#, ", context = ()");
+        assert_eq!("The file doesn't name the machine, use 'name = MyFsm'.", err.to_string());
+
+        let input = r#""door.json", context = (), kind = Door"#;
+        let err = generate_fsm_from_file(input.parse().unwrap()).err().unwrap();
+        assert_eq!("Unsupported option, expected 'context' or 'name'.", err.to_string());
+        assert_eq!(LineColumn { line: 1, column: input.find("kind").unwrap() }, err.span().start());
+    }
+}
@@ -3,18 +3,15 @@ extern crate proc_macro2;
 
 extern crate syn;
 extern crate quote;
+extern crate finny_model;
 
 use codegen::generate_fsm_code;
-use parse::FsmFnInput;
+use finny_model::parse::FsmFnInput;
 use proc_macro::TokenStream;
 
 mod codegen;
-mod parse;
-mod parse_blocks;
-mod parse_fsm;
-mod utils;
-mod validation;
-mod fsm;
+mod from_file;
+mod model;
 
 #[proc_macro_attribute]
 pub fn finny_fsm(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -26,7 +23,20 @@ pub fn finny_fsm(attr: TokenStream, item: TokenStream) -> TokenStream {
         Err(e) => return e.to_compile_error().into()
     };
 
-    match generate_fsm_code(&parsed, attr2.clone(), item2.clone()) {
+    if let Err(e) = model::emit_model(&parsed) {
+        return e.to_compile_error().into();
+    }
+
+    match generate_fsm_code(&parsed, item2.clone()) {
+        Ok(t) => t.into(),
+        Err(e) => e.to_compile_error().into()
+    }
+}
+
+/// Generates the FSM from an SCXML or a JSON file.
+#[proc_macro]
+pub fn finny_fsm_from_file(input: TokenStream) -> TokenStream {
+    match from_file::generate_fsm_from_file(input.into()) {
         Ok(t) => t.into(),
         Err(e) => e.to_compile_error().into()
     }
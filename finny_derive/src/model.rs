@@ -0,0 +1,145 @@
+//! The validated model of the machine, written as JSON for external tools when the
+//! `emit_model` option is used.
+
+use std::path::PathBuf;
+
+use proc_macro2::Span;
+use serde_json::{Value, json};
+use syn::spanned::Spanned;
+
+use finny_model::{parse::{EventGuardAction, FsmFnInput, FsmStateKind, FsmTransitionEndpoints, FsmTransitionEvent, FsmTransitionKind, FsmTransitionState}, utils::{tokens_to_string, type_name}};
+
+/// Writes the model into the file requested by the `emit_model` option. A relative path is resolved against
+/// the crate's manifest directory. The file is only rewritten when its contents change.
+pub fn emit_model(fsm: &FsmFnInput) -> syn::Result<()> {
+    let path_lit = match fsm.attributes.emit_model {
+        Some(ref p) => p,
+        None => { return Ok(()); }
+    };
+    let err = |msg: String| syn::Error::new(path_lit.span(), msg);
+
+    let path = PathBuf::from(path_lit.value());
+    let path = match path.is_absolute() {
+        true => path,
+        false => {
+            let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").map_err(|_| err("The CARGO_MANIFEST_DIR variable is not set.".into()))?;
+            PathBuf::from(manifest_dir).join(path)
+        }
+    };
+
+    let model = serde_json::to_string_pretty(&fsm_model(fsm)).map_err(|e| err(e.to_string()))?;
+
+    if std::fs::read_to_string(&path).ok().as_deref() == Some(model.as_str()) {
+        return Ok(());
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| err(format!("Can't create '{}': {}", dir.display(), e)))?;
+    }
+    std::fs::write(&path, model).map_err(|e| err(format!("Can't write '{}': {}", path.display(), e)))?;
+
+    Ok(())
+}
+
+/// The location in the source file, with 1-based lines and columns.
+fn span_model(span: Span) -> Value {
+    let start = span.start();
+    let end = span.end();
+    json!({
+        "file": span.file(),
+        "line": start.line,
+        "column": start.column + 1,
+        "end_line": end.line,
+        "end_column": end.column + 1
+    })
+}
+
+fn closure_model(closure: &Option<syn::ExprClosure>) -> Value {
+    match closure {
+        Some(c) => span_model(c.span()),
+        None => Value::Null
+    }
+}
+
+fn actions_model(action: &EventGuardAction) -> (Value, Value) {
+    (closure_model(&action.guard), closure_model(&action.action))
+}
+
+pub fn fsm_model(fsm: &FsmFnInput) -> Value {
+    let state_name_of = |st: &FsmTransitionState| st.state_name().map(Value::String).unwrap_or(Value::Null);
+
+    let states: Vec<_> = fsm.fsm.states.iter().map(|(ty, state)| {
+        let region = fsm.fsm.regions.iter().find(|r| r.states.contains(ty)).map(|r| r.region_id);
+        let sub_machine = match state.kind {
+            FsmStateKind::Normal => Value::Null,
+            FsmStateKind::SubMachine(_) => Value::String(type_name(ty))
+        };
+
+        json!({
+            "name": type_name(ty),
+            "ty": tokens_to_string(ty),
+            "region": region,
+            "sub_machine": sub_machine,
+            "on_entry": closure_model(&state.on_entry_closure),
+            "on_exit": closure_model(&state.on_exit_closure),
+            "span": span_model(ty.span())
+        })
+    }).collect();
+
+    let events: Vec<_> = fsm.fsm.events.keys().map(|ty| {
+        json!({
+            "name": type_name(ty),
+            "ty": tokens_to_string(ty),
+            "span": span_model(ty.span())
+        })
+    }).collect();
+
+    let regions: Vec<_> = fsm.fsm.regions.iter().map(|region| {
+        json!({
+            "id": region.region_id,
+            "name": region.name,
+            "initial_states": region.initial_states.iter().map(type_name).collect::<Vec<_>>(),
+            "states": region.states.iter().map(type_name).collect::<Vec<_>>()
+        })
+    }).collect();
+
+    let mut transitions = vec![];
+    for region in &fsm.fsm.regions {
+        for transition in &region.transitions {
+            let FsmTransitionEndpoints { kind, event, from, to, action } = transition.endpoints();
+            let kind = match kind {
+                FsmTransitionKind::Internal => "internal",
+                FsmTransitionKind::SelfTransition => "self",
+                FsmTransitionKind::Normal => "normal"
+            };
+
+            let (trigger, event) = match event {
+                FsmTransitionEvent::Start => ("start", Value::Null),
+                FsmTransitionEvent::Stop => ("stop", Value::Null),
+                FsmTransitionEvent::Event(ev) => ("event", Value::String(type_name(&ev.ty)))
+            };
+            let (guard, action) = actions_model(action);
+
+            transitions.push(json!({
+                "name": type_name(&transition.transition_ty),
+                "region": region.region_id,
+                "kind": kind,
+                "trigger": trigger,
+                "event": event,
+                "from": state_name_of(from),
+                "to": state_name_of(to),
+                "guard": guard,
+                "action": action
+            }));
+        }
+    }
+
+    json!({
+        "name": type_name(&fsm.base.fsm_ty),
+        "context_ty": tokens_to_string(&fsm.base.context_ty),
+        "states": states,
+        "events": events,
+        "regions": regions,
+        "transitions": transitions
+    })
+}
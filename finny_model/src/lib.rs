@@ -0,0 +1,49 @@
+//! # Finny's machine model
+//!
+//! The parser and validator of the builder API of [finny](https://crates.io/crates/finny). The
+//! procedural macro in `finny_derive` generates the machine's code from this model. Tools that need
+//! to understand the machines without compiling them, like linters, diagram generators or editor
+//! helpers, can use the same parser.
+//!
+//! The builder function is parsed into a [`parse::FsmFnInput`]. Its [`parse::ValidatedFsm`] holds
+//! the states, events and regions, with every transition assigned to its region:
+//!
+//! ```rust
+//! use finny_model::parse::FsmFnInput;
+//! use proc_macro2::TokenStream;
+//! use quote::quote;
+//!
+//! let builder = quote! {
+//!     fn build_fsm(mut fsm: FsmBuilder<Door, Context>) -> BuiltFsm {
+//!         fsm.initial_state::<Closed>();
+//!         fsm.state::<Closed>().on_event::<Open>().transition_to::<Opened>();
+//!         fsm.state::<Opened>().on_event::<Close>().transition_to::<Closed>();
+//!         fsm.build()
+//!     }
+//! };
+//!
+//! let input = FsmFnInput::parse(TokenStream::new(), builder).unwrap();
+//! assert_eq!(2, input.fsm.states.len());
+//! assert_eq!(1, input.fsm.regions.len());
+//! // the start transition and the two declared ones
+//! assert_eq!(3, input.fsm.regions[0].transitions.len());
+//! ```
+//!
+//! The errors are `syn::Error`s, spanned at the offending builder call.
+
+extern crate proc_macro2;
+extern crate syn;
+extern crate quote;
+
+/// The FSM's type and the types derived from its name.
+pub mod fsm;
+/// The model of the machine and the parser of the builder function.
+pub mod parse;
+/// The statements of the builder function, as chains of method calls.
+pub mod parse_blocks;
+/// The parser of the builder's method calls.
+pub mod parse_fsm;
+/// Helpers for working with types, closures and identifiers.
+pub mod utils;
+/// Assigns the states and transitions to regions and validates the transition graph.
+pub mod validation;
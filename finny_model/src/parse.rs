@@ -1,4 +1,6 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
+
+use indexmap::IndexMap;
 
 use proc_macro2::{Span, TokenStream};
 use syn::{Error, Expr, ExprMethodCall, GenericArgument, ItemFn, parse::{self, Parse, ParseStream}, spanned::Spanned};
@@ -6,11 +8,42 @@ use syn::{Error, Expr, ExprMethodCall, GenericArgument, ItemFn, parse::{self, Pa
 use crate::{parse_blocks::{FsmBlock, decode_blocks, get_generics, get_method_receiver_ident}, parse_fsm::{FsmCodegenOptions, FsmParser}, utils::{assert_no_generics, get_closure, to_field_name}};
 
 
+/// The parsed and validated builder function.
 pub struct FsmFnInput {
     pub base: FsmFnBase,
     pub fsm: ValidatedFsm,
+    pub attributes: FsmFnAttributes
+}
+
+/// The options of the `#[finny_fsm(...)]` attribute.
+#[derive(Debug, Default)]
+pub struct FsmFnAttributes {
+    /// Write the validated model as JSON into this file, relative to the crate's manifest directory.
+    pub emit_model: Option<syn::LitStr>
+}
+
+impl Parse for FsmFnAttributes {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut attributes = FsmFnAttributes::default();
+
+        while !input.is_empty() {
+            let key: syn::Ident = input.parse()?;
+            input.parse::<syn::Token![=]>()?;
+            match key.to_string().as_str() {
+                "emit_model" => { attributes.emit_model = Some(input.parse()?); },
+                _ => { return Err(Error::new(key.span(), "Unsupported option, expected 'emit_model'.")); }
+            }
+
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+
+        Ok(attributes)
+    }
 }
 
+/// The builder function's signature: the FSM and context types and the builder's name.
 #[derive(Debug, Clone)]
 pub struct FsmFnBase {
     pub context_ty: syn::Type,
@@ -21,7 +54,9 @@ pub struct FsmFnBase {
 
 
 impl FsmFnInput {
+    /// Parses and validates the builder function, `attr` are the tokens within `#[finny_fsm(...)]`.
     pub fn parse(attr: TokenStream, item: TokenStream) -> syn::Result<Self> {
+        let attributes: FsmFnAttributes = syn::parse2(attr)?;
         let input_fn: syn::ItemFn = syn::parse2(item)?;
 
         // builder name/generics
@@ -119,40 +154,71 @@ impl FsmFnInput {
 
         Ok(FsmFnInput {
             base,
-            fsm: fsm_declarations
+            fsm: fsm_declarations,
+            attributes
         })
     }
 }
 
+/// The declarations of the builder, before they are assigned to regions.
 #[derive(Debug)]
 pub struct FsmDeclarations {
-    pub initial_states: Vec<syn::Type>,
-    pub states: HashMap<syn::Type, FsmState>,
-    pub events: HashMap<syn::Type, FsmEvent>,
+    pub regions: Vec<FsmRegionDeclaration>,
+    pub states: IndexMap<syn::Type, FsmState>,
+    pub events: IndexMap<syn::Type, FsmEvent>,
     pub transitions: Vec<FsmTransition>
 }
 
+/// The machine, with its states and transitions assigned to regions. States and events are listed
+/// in the order of their declaration.
 #[derive(Debug)]
 pub struct ValidatedFsm {
     pub codegen_options: FsmCodegenOptions,
     pub regions: Vec<FsmRegion>,
-    pub states: HashMap<syn::Type, FsmState>,
-    pub events: HashMap<syn::Type, FsmEvent>
+    pub states: IndexMap<syn::Type, FsmState>,
+    pub events: IndexMap<syn::Type, FsmEvent>,
+    /// The states that are only entered through transitions that construct them. They are stored lazily
+    /// and never built by their factory.
+    pub constructed_states: HashSet<syn::Type>
+}
+
+/// A region as declared in the builder, either a named one or one of the initial states.
+#[derive(Debug, Clone)]
+pub struct FsmRegionDeclaration {
+    /// The generated marker type of a named region.
+    pub marker_ty: Option<syn::Type>,
+    pub initial_states: Vec<FsmInitialState>,
+    /// States that were explicitly placed into this region.
+    pub states: Vec<syn::Type>
 }
 
+/// One of the initial states of a region. The alternatives are evaluated in the order of their declaration.
+#[derive(Debug, Clone)]
+pub struct FsmInitialState {
+    pub ty: syn::Type,
+    pub action: EventGuardAction
+}
+
+/// An orthogonal region of the validated machine.
 #[derive(Debug)]
 pub struct FsmRegion {
     pub region_id: usize,
-    pub initial_state: syn::Type,
+    pub name: String,
+    pub marker_ty: Option<syn::Type>,
+    pub initial_states: Vec<syn::Type>,
+    /// All of the states in this region, as inferred from the transition graph.
+    pub states: Vec<syn::Type>,
     pub transitions: Vec<FsmTransition>
 }
 
+/// The source or the target of a transition, `None` for the stopped machine.
 #[derive(Debug, Clone)]
 pub enum FsmTransitionState {
     None,
     State(FsmState)
 }
 
+/// What triggers a transition.
 #[derive(Debug, Clone)]
 pub enum FsmTransitionEvent {
     Stop,
@@ -161,6 +227,7 @@ pub enum FsmTransitionEvent {
 }
 
 impl FsmTransitionEvent {
+    /// The event of a transition that isn't triggered by starting or stopping the machine.
     pub fn get_event(&self) -> syn::Result<&FsmEvent> {
         match self {            
             FsmTransitionEvent::Event(ev) => Ok(ev),
@@ -169,11 +236,48 @@ impl FsmTransitionEvent {
     }
 }
 
+/// A transition, with the name of its generated type.
 #[derive(Debug, Clone)]
 pub struct FsmTransition {
     pub ty: FsmTransitionType,
     pub transition_ty: syn::Type
 }
+
+impl FsmTransition {
+    /// The transition's kind, trigger, states and closures, regardless of its type. The internal and
+    /// self transitions start and end in the same state.
+    pub fn endpoints(&self) -> FsmTransitionEndpoints<'_> {
+        let (kind, event, from, to, action) = match &self.ty {
+            FsmTransitionType::InternalTransition(s) => (FsmTransitionKind::Internal, &s.event, &s.state, &s.state, &s.action),
+            FsmTransitionType::SelfTransition(s) => (FsmTransitionKind::SelfTransition, &s.event, &s.state, &s.state, &s.action),
+            FsmTransitionType::StateTransition(s) => (FsmTransitionKind::Normal, &s.event, &s.state_from, &s.state_to, &s.action)
+        };
+        FsmTransitionEndpoints { kind, event, from, to, action }
+    }
+}
+
+/// The kind of a transition, without its states and closures.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FsmTransitionKind {
+    /// Stays in the state, without its exit and entry actions.
+    Internal,
+    /// Exits and re-enters the same state.
+    SelfTransition,
+    /// From one state to another, or from and to the stopped machine.
+    Normal
+}
+
+/// A view of a transition, as returned by [`FsmTransition::endpoints`].
+#[derive(Debug, Copy, Clone)]
+pub struct FsmTransitionEndpoints<'a> {
+    pub kind: FsmTransitionKind,
+    pub event: &'a FsmTransitionEvent,
+    pub from: &'a FsmTransitionState,
+    pub to: &'a FsmTransitionState,
+    pub action: &'a EventGuardAction
+}
+
+/// A transition, with the states and closures of its kind.
 #[derive(Debug, Clone)]
 pub enum FsmTransitionType {
     /// Doesn't trigger the state's actions
@@ -185,6 +289,7 @@ pub enum FsmTransitionType {
 }
 
 impl FsmTransitionType {
+    /// The states that the transition starts or ends in.
     pub fn get_states(&self) -> Vec<syn::Type> {
         let mut ret = vec![];
 
@@ -208,6 +313,7 @@ impl FsmTransitionType {
     }
 }
 
+/// An internal or a self transition of a single state.
 #[derive(Debug, Clone)]
 pub struct FsmStateAction {
     pub state: FsmTransitionState,
@@ -215,6 +321,7 @@ pub struct FsmStateAction {
     pub action: EventGuardAction
 }
 
+/// A transition between two states, or into an initial state.
 #[derive(Debug, Clone)]
 pub struct FsmStateTransition {
     pub state_from: FsmTransitionState,
@@ -223,31 +330,40 @@ pub struct FsmStateTransition {
     pub event: FsmTransitionEvent,
 }
 
+/// A plain state or a sub-machine.
 #[derive(Debug, Clone, PartialEq)]
 pub enum FsmStateKind {
     Normal,
     SubMachine(FsmSubMachineOptions)
 }
 
+/// The options of a sub-machine state.
 #[derive(Default, Debug, Clone, PartialEq)]
 pub struct FsmSubMachineOptions {
+    /// Builds the sub-machine's context from the parent's one.
     pub context_constructor: Option<syn::ExprClosure>
 }
 
+/// A state, along with its actions and storage options.
 #[derive(Debug, Clone)]
 pub struct FsmState {
     pub ty: syn::Type,
     pub kind: FsmStateKind,
     pub state_storage_field: syn::Ident,
     pub on_entry_closure: Option<syn::ExprClosure>,
-    pub on_exit_closure: Option<syn::ExprClosure>
+    pub on_exit_closure: Option<syn::ExprClosure>,
+    pub constructor: Option<syn::ExprClosure>,
+    pub reset_on_entry: bool
 }
+
+/// An event and the transitions it triggers.
 #[derive(Debug, Clone)]
 pub struct FsmEvent {
     pub ty: syn::Type,
     pub transitions: Vec<FsmEventTransition>
 }
 
+/// One of the transitions that an event triggers, by the types of its states.
 #[derive(Debug, Clone)]
 pub enum FsmEventTransition {
     /// A transition from one state to another.
@@ -258,13 +374,16 @@ pub enum FsmEventTransition {
     SelfTransition(syn::Type, EventGuardAction)
 }
 
+/// The closures attached to a transition.
 #[derive(Default, Debug, Clone)]
 pub struct EventGuardAction{
     pub guard: Option<syn::ExprClosure>,
-    pub action: Option<syn::ExprClosure>
+    pub action: Option<syn::ExprClosure>,
+    pub construct: Option<syn::ExprClosure>
 }
 
 impl FsmDeclarations {
+    /// Parses the builder's blocks and validates the declarations.
     pub fn parse(base: &FsmFnBase, input_fn: &ItemFn, blocks: &Vec<FsmBlock>) -> syn::Result<ValidatedFsm> {
         let mut parser = FsmParser::new(base.clone());
         parser.parse(input_fn, blocks)?;
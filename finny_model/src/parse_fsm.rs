@@ -1,27 +1,44 @@
-use std::collections::HashMap;
+use indexmap::IndexMap;
 
 use proc_macro2::Span;
 use syn::{ExprMethodCall, ItemFn, Type, spanned::Spanned};
 
-use crate::{parse::{EventGuardAction, FsmDeclarations, FsmEvent, FsmEventTransition, FsmFnBase, FsmState, FsmStateAction, FsmStateKind, FsmStateTransition, FsmSubMachineOptions, FsmTransition, FsmTransitionEvent, FsmTransitionState, FsmTransitionType, ValidatedFsm}, parse_blocks::{FsmBlock, get_generics}, utils::{assert_no_generics, to_field_name, get_closure}, validation::create_regions};
+use crate::{parse::{EventGuardAction, FsmDeclarations, FsmEvent, FsmEventTransition, FsmFnBase, FsmInitialState, FsmRegionDeclaration, FsmState, FsmStateAction, FsmStateKind, FsmStateTransition, FsmSubMachineOptions, FsmTransition, FsmTransitionEvent, FsmTransitionState, FsmTransitionType, ValidatedFsm}, parse_blocks::{FsmBlock, get_generics}, utils::{assert_no_generics, to_field_name, get_closure}, validation::create_regions};
 
-#[derive(Copy, Clone, Debug)]
+/// The options of the generated code, set by the builder.
+#[derive(Copy, Clone, Debug, Default)]
 pub struct FsmCodegenOptions {
-    pub event_debug: bool
+    pub event_debug: bool,
+    pub state_storage_union: bool,
+    pub inspect_debug: bool
 }
 
 impl FsmCodegenOptions {
     pub fn new() -> Self {
         Self {
-            event_debug: false
+            event_debug: false,
+            state_storage_union: false,
+            inspect_debug: false
         }
     }
 }
 
+/// A named region, as it is being parsed.
+struct FsmNamedRegion {
+    marker_ty: syn::Type,
+    initial_states: Vec<FsmInitialState>,
+    states: Vec<syn::Type>
+}
+
+/// Collects the builder's declarations, statement by statement.
 pub struct FsmParser {
+    /// Alternative initial states of a single region, from `initial_state`.
+    initial_state_alternatives: Vec<FsmInitialState>,
+    /// One initial state for each region, from `initial_states`.
     initial_states: Vec<syn::Type>,
-    states: HashMap<Type, FsmState>,
-    events: HashMap<Type, FsmEvent>,
+    named_regions: Vec<FsmNamedRegion>,
+    states: IndexMap<Type, FsmState>,
+    events: IndexMap<Type, FsmEvent>,
     options: FsmCodegenOptions,
     base: FsmFnBase
 }
@@ -29,9 +46,11 @@ pub struct FsmParser {
 impl FsmParser {
     pub fn new(base: FsmFnBase) -> Self {
         FsmParser {
+            initial_state_alternatives: vec![],
             initial_states: vec![],
-            states: HashMap::new(),
-            events: HashMap::new(),
+            named_regions: vec![],
+            states: IndexMap::new(),
+            events: IndexMap::new(),
             options: FsmCodegenOptions::new(),
             base
         }
@@ -56,14 +75,20 @@ impl FsmParser {
                         [MethodOverviewRef { name: "events_debug", generics: [], .. }] => {
                             self.options.event_debug = true;
                         },
-                        [MethodOverviewRef { name: "initial_state", generics: [ty], .. }] => {
-                            assert_no_generics(ty)?;
+                        [MethodOverviewRef { name: "state_storage_union", generics: [], .. }] => {
+                            self.options.state_storage_union = true;
+                        },
+                        [MethodOverviewRef { name: "inspect_debug", generics: [], .. }] => {
+                            self.options.inspect_debug = true;
+                        },
+                        [MethodOverviewRef { name: "initial_state", generics: [ty], .. }, st @ .. ] => {
                             if self.initial_states.len() > 0 { return Err(syn::Error::new(ty.span(), "Duplicate initial_state!")); }
-                            self.initial_states.push(ty.clone());
+                            let initial_state = Self::parse_initial_state(ty, st)?;
+                            self.initial_state_alternatives.push(initial_state);
                         },
                         [MethodOverviewRef { name: "initial_states", generics: [ty_tuple], .. }] => {
 
-                            if self.initial_states.len() > 0 { return Err(syn::Error::new(ty_tuple.span(), "Duplicate initial_state!")); }
+                            if !self.initial_states.is_empty() || !self.initial_state_alternatives.is_empty() { return Err(syn::Error::new(ty_tuple.span(), "Duplicate initial_state!")); }
 
                             match ty_tuple {
                                 Type::Tuple(tuple) => {
@@ -76,6 +101,10 @@ impl FsmParser {
                             }
                         },
 
+                        [MethodOverviewRef { name: "region", generics: [ty_region], .. }, st @ .. ] => {
+                            self.region_builder_parser(ty_region, st)?;
+                        },
+
                         [MethodOverviewRef { name: "sub_machine", generics: [ty_sub_fsm], ..}, st @ .. ] => {
 
                             //assert_no_generics(ty_sub_fsm)?;
@@ -88,6 +117,8 @@ impl FsmParser {
                                     state_storage_field: field_name,
                                     on_entry_closure: None,
                                     on_exit_closure: None,
+                                    constructor: None,
+                                    reset_on_entry: false,
                                     kind: FsmStateKind::SubMachine(FsmSubMachineOptions::default())
                                 });
                             let mut sub_options = match state.kind {                                
@@ -136,7 +167,7 @@ impl FsmParser {
     }
 
     fn parse_event_guard_action(event_method_calls: &[MethodOverviewRef]) -> syn::Result<EventGuardAction> {
-        let mut guard_action = EventGuardAction { guard: None, action: None};
+        let mut guard_action = EventGuardAction { guard: None, action: None, construct: None };
         
         for method in event_method_calls {
             match method {
@@ -157,6 +188,15 @@ impl FsmParser {
                     }
 
                     guard_action.action = Some(closure.clone());
+                },
+                MethodOverviewRef { name: "construct", .. } => {
+                    let closure = get_closure(method.call)?;
+
+                    if guard_action.construct.is_some() {
+                        return Err(syn::Error::new(closure.span(), "Duplicate 'construct'!"));
+                    }
+
+                    guard_action.construct = Some(closure.clone());
                 }
                 _ => { return Err(syn::Error::new(method.call.span(), "Unsupported method.")); }
             }
@@ -165,6 +205,15 @@ impl FsmParser {
         Ok(guard_action)
     }
 
+    fn parse_initial_state(ty: &syn::Type, method_calls: &[MethodOverviewRef]) -> syn::Result<FsmInitialState> {
+        assert_no_generics(ty)?;
+
+        Ok(FsmInitialState {
+            ty: ty.clone(),
+            action: Self::parse_event_guard_action(method_calls)?
+        })
+    }
+
     fn parse_state_on_event(state: &FsmState, event: &mut FsmEvent, method_calls: &[MethodOverviewRef]) -> syn::Result<()> {
         match method_calls {
             [MethodOverviewRef { name: "transition_to", generics: [ty_to], .. }, ev @ .. ] => {
@@ -186,9 +235,40 @@ impl FsmParser {
     pub fn validate(mut self, input_fn: &ItemFn) -> syn::Result<ValidatedFsm> {
         let mut transitions = vec![];
 
-        if self.initial_states.len() == 0 {
-            return Err(syn::Error::new(input_fn.span(), "Missing the initial state declaration! Use the method 'initial_state' or 'initial_states'."));
-        }
+        let regions = match (self.initial_state_alternatives.len() + self.initial_states.len(), self.named_regions.len()) {
+            (0, 0) => {
+                return Err(syn::Error::new(input_fn.span(), "Missing the initial state declaration! Use the method 'initial_state', 'initial_states' or 'region'."));
+            },
+            (_, 0) if !self.initial_state_alternatives.is_empty() => {
+                vec![FsmRegionDeclaration {
+                    marker_ty: None,
+                    initial_states: self.initial_state_alternatives.clone(),
+                    states: vec![]
+                }]
+            },
+            (_, 0) => {
+                self.initial_states.iter().map(|ty| FsmRegionDeclaration {
+                    marker_ty: None,
+                    initial_states: vec![FsmInitialState { ty: ty.clone(), action: EventGuardAction::default() }],
+                    states: vec![]
+                }).collect()
+            },
+            (0, _) => {
+                self.named_regions.iter().map(|r| {
+                    if r.initial_states.is_empty() {
+                        return Err(syn::Error::new(r.marker_ty.span(), "Missing the initial state of this region! Use the method 'initial_state' on the region."));
+                    }
+                    Ok(FsmRegionDeclaration {
+                        marker_ty: Some(r.marker_ty.clone()),
+                        initial_states: r.initial_states.clone(),
+                        states: r.states.clone()
+                    })
+                }).collect::<syn::Result<Vec<_>>>()?
+            },
+            (_, _) => {
+                return Err(syn::Error::new(input_fn.span(), "Named regions can't be combined with 'initial_state' or 'initial_states'!"));
+            }
+        };
         
         // build and validate the transitions table
         {
@@ -199,19 +279,29 @@ impl FsmParser {
                 crate::utils::ty_append(&base.fsm_ty, &format!("Transition{}", i))
             }
 
-            // start transition
-            for initial_state in &self.initial_states {
-                let fsm_initial_state = self.states.get(&initial_state).ok_or(syn::Error::new(initial_state.span(), "The initial state is not refered in the builder. Use the 'state' method on the builder."))?;
-
-                transitions.push(FsmTransition {
-                    transition_ty: generate_transition_ty(&self.base, &mut i),
-                    ty: FsmTransitionType::StateTransition(FsmStateTransition {
-                        action: EventGuardAction::default(),
-                        event: FsmTransitionEvent::Start,
-                        state_from: FsmTransitionState::None,
-                        state_to: FsmTransitionState::State(fsm_initial_state.clone())
-                    })
-                });
+            // start transitions, the alternatives are matched in the order of their declaration
+            for region in &regions {
+                let (last, alternatives) = region.initial_states.split_last().ok_or(syn::Error::new(input_fn.span(), "Missing the initial state of a region!"))?;
+                for alternative in alternatives {
+                    if alternative.action.guard.is_none() {
+                        return Err(syn::Error::new(alternative.ty.span(), "Only the last initial state of a region can be declared without a guard!"));
+                    }
+                }
+
+                for initial_state in alternatives.iter().chain(Some(last)) {
+                    let ty = &initial_state.ty;
+                    let fsm_initial_state = self.states.get(ty).ok_or(syn::Error::new(ty.span(), "The initial state is not refered in the builder. Use the 'state' method on the builder."))?;
+
+                    transitions.push(FsmTransition {
+                        transition_ty: generate_transition_ty(&self.base, &mut i),
+                        ty: FsmTransitionType::StateTransition(FsmStateTransition {
+                            action: initial_state.action.clone(),
+                            event: FsmTransitionEvent::Start,
+                            state_from: FsmTransitionState::None,
+                            state_to: FsmTransitionState::State(fsm_initial_state.clone())
+                        })
+                    });
+                }
             }
 
             for (ty, ev) in self.events.iter() {
@@ -262,7 +352,7 @@ impl FsmParser {
         }
                 
         let dec = FsmDeclarations {
-            initial_states: self.initial_states,
+            regions,
             states: self.states,
             events: self.events,
             transitions
@@ -273,6 +363,40 @@ impl FsmParser {
         Ok(regions)
     }
 
+    fn region_builder_parser(&mut self, ty_region: &syn::Type, st: &[MethodOverviewRef]) -> syn::Result<()> {
+        assert_no_generics(ty_region)?;
+
+        let idx = match self.named_regions.iter().position(|r| &r.marker_ty == ty_region) {
+            Some(idx) => idx,
+            None => {
+                self.named_regions.push(FsmNamedRegion {
+                    marker_ty: ty_region.clone(),
+                    initial_states: vec![],
+                    states: vec![]
+                });
+                self.named_regions.len() - 1
+            }
+        };
+        let region = &mut self.named_regions[idx];
+
+        for (i, method) in st.iter().enumerate() {
+            match method {
+                MethodOverviewRef { name: "initial_state", generics: [ty], .. } => {
+                    let initial_state = Self::parse_initial_state(ty, &st[(i+1)..])?;
+                    region.initial_states.push(initial_state);
+                    break;
+                },
+                MethodOverviewRef { name: "state", generics: [ty], .. } => {
+                    assert_no_generics(ty)?;
+                    region.states.push(ty.clone());
+                },
+                _ => { return Err(syn::Error::new(method.call.span(), format!("Unsupported method '{}'!", method.name))); }
+            }
+        }
+
+        Ok(())
+    }
+
     fn state_builder_parser(&mut self, ty_state: &syn::Type, st: &[MethodOverviewRef], is_sub_fsm: bool) -> syn::Result<()> {
         if !is_sub_fsm { assert_no_generics(ty_state)?; }
         let field_name = to_field_name(&ty_state)?;
@@ -282,6 +406,8 @@ impl FsmParser {
                 ty: ty_state.clone(),
                 on_entry_closure: None,
                 on_exit_closure: None,
+                constructor: None,
+                reset_on_entry: false,
                 state_storage_field: field_name,
                 kind: FsmStateKind::Normal
             });
@@ -305,6 +431,17 @@ impl FsmParser {
                     }
                     state.on_exit_closure = Some(closure.clone());
                 },
+                MethodOverviewRef { name: "with_constructor", .. } => {
+                    let closure = get_closure(&method.call)?;
+
+                    if state.constructor.is_some() {
+                        return Err(syn::Error::new(closure.span(), "Duplicate 'with_constructor'!"));
+                    }
+                    state.constructor = Some(closure.clone());
+                },
+                MethodOverviewRef { name: "reset_on_entry", .. } => {
+                    state.reset_on_entry = true;
+                },
                 MethodOverviewRef { name: "on_event", generics: [ty_event], .. } => {
                     assert_no_generics(ty_event)?;
 
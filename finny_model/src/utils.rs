@@ -104,6 +104,19 @@ impl FsmTransitionState {
             Err(syn::Error::new(Span::call_site(), "Missing the required FSM state!"))
         }
     }
+
+    /// The state's type name, `None` for the stopped machine.
+    pub fn state_name(&self) -> Option<String> {
+        match self {
+            FsmTransitionState::None => None,
+            FsmTransitionState::State(st) => Some(type_name(&st.ty))
+        }
+    }
+}
+
+/// The name of the type without its generic arguments, as used by the descriptions, the models and the tools.
+pub fn type_name(ty: &syn::Type) -> String {
+    tokens_to_string(&strip_generics(ty.clone()))
 }
 
 pub fn ty_append(ty: &syn::Type, suffix: &str) -> syn::Type {
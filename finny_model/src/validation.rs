@@ -0,0 +1,252 @@
+use std::collections::{HashMap, HashSet};
+
+use petgraph::{Graph, graph::NodeIndex, visit::Dfs};
+use proc_macro2::Span;
+use syn::spanned::Spanned;
+
+use crate::{parse::{FsmDeclarations, FsmRegion, FsmStateKind, FsmTransitionEvent, FsmTransitionType, ValidatedFsm}, parse_fsm::FsmCodegenOptions, utils::tokens_to_string};
+
+#[derive(Debug)]
+struct TypeNode {
+    state: syn::Type,
+    region: Option<usize>
+}
+
+pub fn create_regions(decl: FsmDeclarations, options: FsmCodegenOptions) -> syn::Result<ValidatedFsm> {
+    let mut graph = Graph::new();
+    let mut nodes = HashMap::new();
+
+    fn get_or_add_node(nodes: &mut HashMap<syn::Type, NodeIndex>, graph: &mut Graph::<TypeNode, i32>, ty: &syn::Type) -> NodeIndex {
+        *nodes.entry(ty.clone())
+            .or_insert_with(|| {
+                let node = TypeNode { state: ty.clone(), region: None };
+                graph.add_node(node)
+            })
+    }
+
+    for (ty, _) in &decl.states {
+        get_or_add_node(&mut nodes, &mut graph, ty);
+    }
+
+    for transition in &decl.transitions {
+        let states = transition.ty.get_states();
+        for state in &states {
+            get_or_add_node(&mut nodes, &mut graph, state);
+        }
+        match states.as_slice() {
+            [from, to] => {
+                let state_from = get_or_add_node(&mut nodes, &mut graph, from);
+                let state_to = get_or_add_node(&mut nodes, &mut graph, to);
+
+                graph.add_edge(state_from, state_to, 0);
+            },
+            _ => ()
+        }
+    }
+
+    for (region_id, region) in decl.regions.iter().enumerate() {
+        for initial_state in &region.initial_states {
+            let start_node = get_or_add_node(&mut nodes, &mut graph, &initial_state.ty);
+            let mut dfs = Dfs::new(&graph, start_node);
+            while let Some(idx) = dfs.next(&graph) {
+                match graph[idx].region {
+                    Some(r) if r != region_id => {
+                        let s = &graph[idx].state;
+                        return Err(syn::Error::new(s.span(), &format!("The state '{}' was already matched into another region, check the transition graph of the states!",
+                        tokens_to_string(s))));
+                    },
+                    _ => ()
+                }
+                graph[idx].region = Some(region_id);
+            }
+        }
+    }
+
+    for node in graph.raw_nodes() {
+        if node.weight.region == None {
+            return Err(syn::Error::new(node.weight.state.span(), "Unreachable state! Add some transitions that will make this state reachable!"));
+        }
+    }
+
+    // the states that were explicitly placed into a named region have to match the inferred ones
+    for (region_id, region) in decl.regions.iter().enumerate() {
+        if region.states.is_empty() { continue; }
+
+        let region_name = region.marker_ty.as_ref().map(tokens_to_string).unwrap_or_default();
+
+        for state in &region.states {
+            let node = nodes.get(state).ok_or(syn::Error::new(state.span(), "State not found."))?;
+            if graph[*node].region != Some(region_id) {
+                return Err(syn::Error::new(state.span(), format!("The state '{}' was placed into the region '{}', but the transition graph puts it into another region!",
+                    tokens_to_string(state), region_name)));
+            }
+        }
+
+        for node in graph.raw_nodes() {
+            let state = &node.weight.state;
+            if node.weight.region == Some(region_id) && !region.initial_states.iter().any(|i| &i.ty == state) && !region.states.contains(state) {
+                return Err(syn::Error::new(state.span(), format!("The state '{}' is reachable in the region '{}', but it wasn't placed into it. Use the region's 'state' method.",
+                    tokens_to_string(state), region_name)));
+            }
+        }
+    }
+
+    // the states that are entered only by transitions that construct them don't need a factory
+    let mut constructed_states = HashSet::new();
+    for (ty, state) in &decl.states {
+        if state.kind != FsmStateKind::Normal || state.constructor.is_some() || state.reset_on_entry {
+            continue;
+        }
+
+        let entries: Vec<bool> = decl.transitions.iter().filter_map(|transition| match &transition.ty {
+            // the start of the machine is checked below, an initial state always needs a factory
+            FsmTransitionType::StateTransition(s) => match (&s.event, s.state_to.get_fsm_state()) {
+                (FsmTransitionEvent::Start, _) => None,
+                (_, Ok(to)) if &to.ty == ty => Some(s.action.construct.is_some()),
+                _ => None
+            },
+            FsmTransitionType::SelfTransition(s) => match s.state.get_fsm_state() {
+                Ok(st) if &st.ty == ty => Some(false),
+                _ => None
+            },
+            FsmTransitionType::InternalTransition(_) => None
+        }).collect();
+
+        if !entries.is_empty() && entries.iter().all(|c| *c) {
+            constructed_states.insert(ty.clone());
+        }
+    }
+
+    for region in &decl.regions {
+        for initial_state in &region.initial_states {
+            if constructed_states.contains(&initial_state.ty) {
+                return Err(syn::Error::new(initial_state.ty.span(), format!("The state '{}' is only entered through transitions that construct it, so it can't be an initial state. Add a constructor with 'with_constructor'.",
+                    tokens_to_string(&initial_state.ty))));
+            }
+        }
+    }
+
+    // build the regions
+    let mut regions = vec![];
+    for (region_id, region) in decl.regions.iter().enumerate() {
+        let region_states: Vec<_> = graph.raw_nodes().iter()
+            .filter(|n| n.weight.region == Some(region_id))
+            .map(|n| n.weight.state.clone())
+            .collect();
+
+        let transitions = {
+            let region_states: HashSet<_> = region_states.iter().collect();
+
+            let mut transitions = vec![];
+            for transition in &decl.transitions {
+                let states = transition.ty.get_states();
+                if states.len() == 0 {
+                    return Err(syn::Error::new(Span::call_site(), "No states for this transition found, codegen bug!"));
+                }
+
+                let c = states.iter().filter(|s| region_states.contains(s)).count();
+                
+                if c == states.len() {
+                    transitions.push(transition.clone());
+                } else if c != 0 {
+                    return Err(syn::Error::new(Span::call_site(), "Only some states belong to this region, codegen bug!"));
+                }
+            }
+
+            transitions
+        };
+
+        let name = match region.marker_ty {
+            Some(ref ty) => tokens_to_string(ty),
+            None => format!("Region{}", region_id)
+        };
+
+        regions.push(FsmRegion {
+            initial_states: region.initial_states.iter().map(|i| i.ty.clone()).collect(),
+            marker_ty: region.marker_ty.clone(),
+            name,
+            region_id,
+            states: region_states,
+            transitions
+        });
+    }
+    
+    Ok(ValidatedFsm {
+        events: decl.events,
+        states: decl.states,
+        regions,
+        codegen_options: options,
+        constructed_states
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::TokenStream;
+    use quote::quote;
+
+    use crate::parse::FsmFnInput;
+
+    #[test]
+    fn test_constructed_states() {
+        let input = FsmFnInput::parse(TokenStream::new(), quote! {
+            fn build_fsm(mut fsm: FsmBuilder<Link, ()>) -> BuiltFsm {
+                fsm.initial_state::<Down>();
+                fsm.state::<Down>().on_event::<Connect>().transition_to::<Up>().construct(|ev, _, _| Up { id: ev.id });
+                fsm.state::<Up>().on_event::<Drop>().transition_to::<Down>();
+                fsm.build()
+            }
+        }).unwrap();
+
+        let up: syn::Type = syn::parse_quote!(Up);
+        assert_eq!(vec![&up], input.fsm.constructed_states.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_constructed_initial_state() {
+        let err = FsmFnInput::parse(TokenStream::new(), quote! {
+            fn build_fsm(mut fsm: FsmBuilder<Link, ()>) -> BuiltFsm {
+                fsm.initial_state::<Up>();
+                fsm.state::<Up>().on_event::<Drop>().transition_to::<Down>();
+                fsm.state::<Down>().on_event::<Connect>().transition_to::<Up>().construct(|ev, _, _| Up { id: ev.id });
+                fsm.build()
+            }
+        }).err().unwrap();
+
+        assert_eq!("The state 'Up' is only entered through transitions that construct it, so it can't be an initial state. Add a constructor with 'with_constructor'.", err.to_string());
+    }
+
+    #[test]
+    fn test_region_state_placed_into_another_region() {
+        let err = FsmFnInput::parse(TokenStream::new(), quote! {
+            fn build_fsm(mut fsm: FsmBuilder<Machine, ()>) -> BuiltFsm {
+                fsm.region::<LightRegion>().state::<Running>().initial_state::<LightOff>();
+                fsm.region::<MotorRegion>().initial_state::<Idle>();
+                fsm.state::<Idle>().on_event::<Event>().transition_to::<Running>();
+                fsm.state::<LightOff>().on_event::<Event>().transition_to::<LightOn>();
+                fsm.state::<Running>();
+                fsm.state::<LightOn>();
+                fsm.build()
+            }
+        }).err().unwrap();
+
+        assert_eq!("The state 'Running' was placed into the region 'LightRegion', but the transition graph puts it into another region!", err.to_string());
+    }
+
+    #[test]
+    fn test_region_state_not_placed() {
+        let err = FsmFnInput::parse(TokenStream::new(), quote! {
+            fn build_fsm(mut fsm: FsmBuilder<Machine, ()>) -> BuiltFsm {
+                fsm.region::<LightRegion>().initial_state::<LightOff>();
+                fsm.region::<MotorRegion>().state::<Idle>().initial_state::<Idle>();
+                fsm.state::<Idle>().on_event::<Event>().transition_to::<Running>();
+                fsm.state::<LightOff>().on_event::<Event>().transition_to::<LightOn>();
+                fsm.state::<Running>();
+                fsm.state::<LightOn>();
+                fsm.build()
+            }
+        }).err().unwrap();
+
+        assert_eq!("The state 'Running' is reachable in the region 'MotorRegion', but it wasn't placed into it. Use the region's 'state' method.", err.to_string());
+    }
+}
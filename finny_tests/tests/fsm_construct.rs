@@ -0,0 +1,104 @@
+extern crate finny;
+
+use finny::{FsmCurrentState, FsmFactory, FsmResult, decl::{BuiltFsm, FsmBuilder}, finny_fsm};
+
+#[derive(Default)]
+pub struct Context {
+    connections: usize
+}
+
+#[derive(Default)]
+pub struct Disconnected {
+    attempts: usize
+}
+
+/// Has no `Default`, it's only built by the transition that enters it.
+pub struct Connected {
+    id: u32,
+    attempt: usize,
+    greeted: bool
+}
+
+#[derive(Clone)]
+pub struct Connect {
+    id: u32
+}
+#[derive(Clone)]
+pub struct Disconnect;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, Context>) -> BuiltFsm {
+    fsm.initial_state::<Disconnected>();
+
+    fsm.state::<Disconnected>()
+        .on_exit(|state, _ctx| {
+            state.attempts += 1;
+        })
+        .on_event::<Connect>()
+        .transition_to::<Connected>()
+        .construct(|ev, ctx, from| Connected { id: ev.id + ctx.connections as u32, attempt: from.attempts, greeted: false })
+        .action(|_ev, ctx, _from, to| {
+            to.greeted = true;
+            ctx.connections += 1;
+        });
+
+    fsm.state::<Connected>()
+        .on_event::<Disconnect>()
+        .transition_to::<Disconnected>();
+
+    fsm.build()
+}
+
+#[finny_fsm]
+fn build_union_fsm(mut fsm: FsmBuilder<UnionMachine, Context>) -> BuiltFsm {
+    fsm.state_storage_union();
+    fsm.initial_state::<Disconnected>();
+
+    fsm.state::<Disconnected>()
+        .on_event::<Connect>()
+        .transition_to::<Connected>()
+        .construct(|ev, _ctx, from| Connected { id: ev.id, attempt: from.attempts, greeted: false });
+
+    fsm.state::<Connected>()
+        .on_event::<Disconnect>()
+        .transition_to::<Disconnected>();
+
+    fsm.build()
+}
+
+#[test]
+fn test_construct_from_event() -> FsmResult<()> {
+    let mut fsm = StateMachine::new(Context::default())?;
+    assert!(fsm.try_get_state::<Connected>().is_none());
+    fsm.start()?;
+
+    fsm.dispatch(Connect { id: 10 })?;
+    assert_eq!(FsmCurrentState::State(StateMachineCurrentState::Connected), fsm.get_current_states()[0]);
+    let state: &Connected = fsm.try_get_state().unwrap();
+    assert_eq!(10, state.id);
+    assert_eq!(1, state.attempt);
+    assert!(state.greeted);
+
+    fsm.dispatch(Disconnect)?;
+    fsm.dispatch(Connect { id: 20 })?;
+    let state: &Connected = fsm.try_get_state().unwrap();
+    assert_eq!(21, state.id);
+    assert_eq!(2, state.attempt);
+
+    Ok(())
+}
+
+#[test]
+fn test_construct_with_union_storage() -> FsmResult<()> {
+    let mut fsm = UnionMachine::new(Context::default())?;
+    fsm.start()?;
+    assert!(fsm.try_get_state::<Connected>().is_none());
+
+    fsm.dispatch(Connect { id: 7 })?;
+    assert_eq!(7, fsm.try_get_state::<Connected>().unwrap().id);
+
+    fsm.dispatch(Disconnect)?;
+    assert!(fsm.try_get_state::<Connected>().is_none());
+
+    Ok(())
+}
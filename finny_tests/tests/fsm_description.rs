@@ -0,0 +1,96 @@
+extern crate finny;
+
+use finny::{FsmDescription, FsmTransitionEventMeta, FsmTransitionKindMeta, decl::{BuiltFsm, FsmBuilder}, finny_fsm};
+
+#[derive(Default)]
+pub struct Context;
+
+#[derive(Default)]
+pub struct Idle;
+#[derive(Default)]
+pub struct Running;
+#[derive(Default)]
+pub struct LightOff;
+#[derive(Clone)]
+pub struct Start;
+#[derive(Clone)]
+pub struct Tick;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, Context>) -> BuiltFsm {
+    fsm.region::<MotorRegion>().initial_state::<Idle>();
+    fsm.region::<LightRegion>().initial_state::<LightOff>();
+
+    fsm.state::<Idle>()
+        .on_event::<Start>()
+        .transition_to::<Running>()
+        .guard(|_ev, _ctx, _states| true);
+
+    fsm.state::<Running>()
+        .on_event::<Tick>()
+        .internal_transition()
+        .action(|_ev, _ctx, _state| { });
+
+    fsm.state::<Running>()
+        .on_event::<Start>()
+        .transition_to::<SubStateMachine>();
+
+    fsm.state::<LightOff>();
+    fsm.sub_machine::<SubStateMachine>();
+
+    fsm.build()
+}
+
+#[derive(Default)]
+pub struct SubContext;
+#[derive(Default)]
+pub struct SubState;
+#[derive(Clone)]
+pub struct SubEvent;
+
+#[finny_fsm]
+fn build_sub_fsm(mut fsm: FsmBuilder<SubStateMachine, SubContext>) -> BuiltFsm {
+    fsm.initial_state::<SubState>();
+    fsm.state::<SubState>()
+        .on_event::<SubEvent>()
+        .self_transition();
+    fsm.build()
+}
+
+#[test]
+fn test_fsm_description() {
+    let meta = StateMachine::describe();
+    assert_eq!("StateMachine", meta.name);
+
+    let states: Vec<_> = meta.states.iter().map(|s| s.name).collect();
+    assert_eq!(vec!["Idle", "Running", "LightOff", "SubStateMachine"], states);
+    assert_eq!(&["Start", "Tick"], meta.events);
+
+    assert_eq!(2, meta.regions.len());
+    assert_eq!("MotorRegion", meta.regions[0].name);
+    assert_eq!(&["Idle"], meta.regions[0].initial_states);
+    assert_eq!(&["Idle", "Running", "SubStateMachine"], meta.regions[0].states);
+    assert_eq!(&["LightOff"], meta.regions[1].states);
+
+    let start = meta.region_transitions(0).find(|t| t.event == FsmTransitionEventMeta::Start).unwrap();
+    assert_eq!(None, start.from);
+    assert_eq!(Some("Idle"), start.to);
+
+    let guarded = meta.transitions.iter().find(|t| t.from == Some("Idle") && t.event == FsmTransitionEventMeta::Event("Start")).unwrap();
+    assert_eq!(FsmTransitionKindMeta::Normal, guarded.kind);
+    assert_eq!(Some("Running"), guarded.to);
+    assert!(guarded.has_guard);
+    assert!(!guarded.has_action);
+
+    let internal = meta.transitions.iter().find(|t| t.event == FsmTransitionEventMeta::Event("Tick")).unwrap();
+    assert_eq!(FsmTransitionKindMeta::Internal, internal.kind);
+    assert_eq!(internal.from, internal.to);
+    assert!(internal.has_action);
+
+    assert_eq!(1, meta.region_transitions(1).count());
+
+    let sub = meta.state("SubStateMachine").unwrap().sub_machine.unwrap();
+    assert_eq!("SubStateMachine", sub.name);
+    assert_eq!(FsmTransitionKindMeta::SelfTransition, sub.transitions[1].kind);
+    assert!(meta.state("Idle").unwrap().sub_machine.is_none());
+}
@@ -0,0 +1,108 @@
+extern crate finny;
+extern crate serde_json;
+
+use finny::{decl::{BuiltFsm, FsmBuilder}, finny_fsm};
+use serde_json::Value;
+
+#[derive(Default)]
+pub struct Context;
+
+#[derive(Default)]
+pub struct Idle;
+#[derive(Default)]
+pub struct Running;
+#[derive(Clone)]
+pub struct Start;
+#[derive(Clone)]
+pub struct Tick;
+
+#[finny_fsm(emit_model = "target/finny/emit_model.json")]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, Context>) -> BuiltFsm {
+    fsm.initial_state::<Idle>();
+
+    fsm.state::<Idle>()
+        .on_event::<Start>()
+        .transition_to::<Running>()
+        .guard(|_ev, _ctx, _states| true);
+
+    fsm.state::<Running>()
+        .on_entry(|_state, _ctx| { })
+        .on_event::<Tick>()
+        .internal_transition()
+        .action(|_ev, _ctx, _state| { });
+
+    fsm.state::<Running>()
+        .on_event::<Start>()
+        .transition_to::<SubStateMachine>();
+
+    fsm.sub_machine::<SubStateMachine>();
+
+    fsm.build()
+}
+
+#[derive(Default)]
+pub struct SubContext;
+#[derive(Default)]
+pub struct SubState;
+#[derive(Clone)]
+pub struct SubEvent;
+
+#[finny_fsm]
+fn build_sub_fsm(mut fsm: FsmBuilder<SubStateMachine, SubContext>) -> BuiltFsm {
+    fsm.initial_state::<SubState>();
+    fsm.state::<SubState>()
+        .on_event::<SubEvent>()
+        .self_transition();
+    fsm.build()
+}
+
+#[test]
+fn test_emit_model() {
+    let model = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/target/finny/emit_model.json")).unwrap();
+    let model: Value = serde_json::from_str(&model).unwrap();
+
+    assert_eq!("StateMachine", model["name"]);
+    assert_eq!("Context", model["context_ty"]);
+
+    let states: Vec<_> = model["states"].as_array().unwrap().iter().map(|s| s["name"].as_str().unwrap()).collect();
+    assert_eq!(vec!["Idle", "Running", "SubStateMachine"], states);
+    assert_eq!("SubStateMachine", model["states"][2]["sub_machine"]);
+    assert_eq!(Value::Null, model["states"][0]["sub_machine"]);
+    assert!(model["states"][1]["on_entry"].is_object());
+
+    let events: Vec<_> = model["events"].as_array().unwrap().iter().map(|e| e["name"].as_str().unwrap()).collect();
+    assert_eq!(vec!["Start", "Tick"], events);
+
+    assert_eq!(1, model["regions"].as_array().unwrap().len());
+    assert_eq!("Idle", model["regions"][0]["initial_states"][0]);
+
+    let transitions = model["transitions"].as_array().unwrap();
+    let start = transitions.iter().find(|t| t["trigger"] == "start").unwrap();
+    assert_eq!(Value::Null, start["from"]);
+    assert_eq!("Idle", start["to"]);
+
+    let guarded = transitions.iter().find(|t| t["from"] == "Idle" && t["event"] == "Start").unwrap();
+    assert_eq!("normal", guarded["kind"]);
+    assert_eq!("Running", guarded["to"]);
+    assert_eq!(Value::Null, guarded["action"]);
+
+    // the spans point into this file
+    let guard = &guarded["guard"];
+    assert!(guard["file"].as_str().unwrap().ends_with("fsm_emit_model.rs"));
+    let (line, column) = source_location(".guard(|_ev, _ctx, _states| true)", "|");
+    assert_eq!(line, guard["line"]);
+    assert_eq!(column, guard["column"]);
+
+    let internal = transitions.iter().find(|t| t["event"] == "Tick").unwrap();
+    assert_eq!("internal", internal["kind"]);
+    assert_eq!(source_location(".action(|_ev, _ctx, _state| { })", "|").0, internal["action"]["line"]);
+}
+
+/// The 1-based line and column of `marker` within the first line of this file that contains `line`.
+fn source_location(line: &str, marker: &str) -> (usize, usize) {
+    let source = include_str!("fsm_emit_model.rs");
+    source.lines().enumerate()
+        .find(|(_, l)| l.contains(line))
+        .map(|(i, l)| (i + 1, l.find(line).unwrap() + line.find(marker).unwrap() + 1))
+        .unwrap()
+}
@@ -0,0 +1,143 @@
+extern crate finny;
+
+use finny::{FsmCurrentState, FsmFactory, FsmResult, finny_fsm_from_file};
+
+#[derive(Default)]
+pub struct DoorContext {
+    unlocked: bool,
+    entries: usize,
+    knocks: usize,
+    log: Vec<&'static str>
+}
+
+#[derive(Default)]
+pub struct Closed;
+#[derive(Default)]
+pub struct Opened;
+#[derive(Default)]
+pub struct Locked;
+
+#[derive(Clone)]
+pub struct OpenDoor;
+#[derive(Clone)]
+pub struct CloseDoor;
+#[derive(Clone)]
+pub struct Knock;
+#[derive(Clone)]
+pub struct Lock;
+#[derive(Clone)]
+pub struct Unlock;
+
+fn closed_entry(_state: &mut Closed, ctx: &mut DoorContext) {
+    ctx.entries += 1;
+}
+
+fn is_unlocked(_ev: &OpenDoor, ctx: &DoorContext) -> bool {
+    ctx.unlocked
+}
+
+fn log_open(_ev: &OpenDoor, ctx: &mut DoorContext) {
+    ctx.log.push("open");
+}
+
+fn unlock(_ev: &Unlock, ctx: &mut DoorContext) {
+    ctx.unlocked = true;
+}
+
+fn count_knock(_ev: &Knock, ctx: &mut DoorContext) {
+    ctx.knocks += 1;
+}
+
+finny_fsm_from_file!("tests/data/door.scxml", context = DoorContext);
+
+#[test]
+fn test_from_scxml() -> FsmResult<()> {
+    let mut fsm = Door::new(DoorContext::default())?;
+    fsm.start()?;
+    assert_eq!(FsmCurrentState::State(DoorCurrentState::Closed), fsm.get_current_states()[0]);
+    assert_eq!(1, fsm.entries);
+
+    assert!(fsm.dispatch(OpenDoor).is_err());
+    fsm.dispatch(Knock)?;
+    assert_eq!(1, fsm.knocks);
+    assert_eq!(1, fsm.entries);
+
+    fsm.dispatch(Lock)?;
+    assert_eq!(FsmCurrentState::State(DoorCurrentState::Locked), fsm.get_current_states()[0]);
+    fsm.dispatch(Unlock)?;
+    assert_eq!(2, fsm.entries);
+
+    fsm.dispatch(OpenDoor)?;
+    assert_eq!(FsmCurrentState::State(DoorCurrentState::Opened), fsm.get_current_states()[0]);
+    assert_eq!(vec!["open"], fsm.log);
+
+    fsm.dispatch(CloseDoor)?;
+    assert_eq!(3, fsm.entries);
+
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct LampContext {
+    power: bool,
+    toggles: usize,
+    switch_on_exits: usize,
+    no_power: bool
+}
+
+#[derive(Default)]
+pub struct PoweredOn;
+#[derive(Default)]
+pub struct PoweredOff;
+#[derive(Default)]
+pub struct SwitchOff;
+#[derive(Default)]
+pub struct SwitchOn;
+
+#[derive(Clone)]
+pub struct Toggle;
+#[derive(Clone)]
+pub struct Flicker;
+
+fn has_power(ctx: &LampContext) -> bool {
+    ctx.power
+}
+
+fn log_no_power(ctx: &mut LampContext) {
+    ctx.no_power = true;
+}
+
+fn count_toggle(_ev: &Toggle, ctx: &mut LampContext) {
+    ctx.toggles += 1;
+}
+
+fn switch_on_exit(_state: &mut SwitchOn, ctx: &mut LampContext) {
+    ctx.switch_on_exits += 1;
+}
+
+finny_fsm_from_file!("tests/data/lamp.json", context = LampContext, name = LampFsm);
+
+#[test]
+fn test_from_json() -> FsmResult<()> {
+    let mut fsm = LampFsm::new(LampContext::default())?;
+    fsm.start()?;
+    assert_eq!(FsmCurrentState::State(LampFsmCurrentState::PoweredOff), fsm.get_current_state::<PowerRegion>());
+    assert_eq!(FsmCurrentState::State(LampFsmCurrentState::SwitchOff), fsm.get_current_state::<SwitchRegion>());
+    assert!(fsm.no_power);
+
+    let mut fsm = LampFsm::new(LampContext { power: true, ..Default::default() })?;
+    fsm.start()?;
+    assert_eq!(FsmCurrentState::State(LampFsmCurrentState::PoweredOn), fsm.get_current_state::<PowerRegion>());
+    assert!(!fsm.no_power);
+
+    fsm.dispatch(Toggle)?;
+    assert_eq!(FsmCurrentState::State(LampFsmCurrentState::SwitchOn), fsm.get_current_state::<SwitchRegion>());
+    fsm.dispatch(Flicker)?;
+    assert_eq!(1, fsm.switch_on_exits);
+    fsm.dispatch(Toggle)?;
+    assert_eq!(FsmCurrentState::State(LampFsmCurrentState::SwitchOff), fsm.get_current_state::<SwitchRegion>());
+    assert_eq!(2, fsm.toggles);
+    assert_eq!(2, fsm.switch_on_exits);
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+extern crate finny;
+
+use finny::{FsmCurrentState, FsmFactory, FsmResult, decl::{BuiltFsm, FsmBuilder}, finny_fsm};
+
+#[derive(Default)]
+pub struct Config {
+    provisioned: bool,
+    started: usize
+}
+
+#[derive(Default)]
+pub struct Provisioned {
+    entered_after_start: bool
+}
+#[derive(Default)]
+pub struct Unprovisioned;
+#[derive(Clone)]
+pub struct Provision;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, Config>) -> BuiltFsm {
+    fsm.initial_state::<Provisioned>()
+        .guard(|ctx| ctx.provisioned)
+        .action(|ctx, _state| {
+            ctx.started += 1;
+        });
+    fsm.initial_state::<Unprovisioned>();
+
+    fsm.state::<Provisioned>()
+        .on_entry(|state, ctx| {
+            state.entered_after_start = ctx.started > 0;
+        });
+
+    fsm.state::<Unprovisioned>()
+        .on_event::<Provision>()
+        .transition_to::<Provisioned>();
+
+    fsm.build()
+}
+
+#[test]
+fn test_conditional_initial_state() -> FsmResult<()> {
+    let mut fsm = StateMachine::new(Config { provisioned: true, started: 0 })?;
+    fsm.start()?;
+    assert_eq!(FsmCurrentState::State(StateMachineCurrentState::Provisioned), fsm.get_current_states()[0]);
+    assert_eq!(1, fsm.started);
+    let state: &Provisioned = fsm.get_state();
+    assert!(state.entered_after_start);
+
+    let mut fsm = StateMachine::new(Config { provisioned: false, started: 0 })?;
+    fsm.start()?;
+    assert_eq!(FsmCurrentState::State(StateMachineCurrentState::Unprovisioned), fsm.get_current_states()[0]);
+    assert_eq!(0, fsm.started);
+
+    fsm.dispatch(Provision)?;
+    assert_eq!(FsmCurrentState::State(StateMachineCurrentState::Provisioned), fsm.get_current_states()[0]);
+    assert_eq!(0, fsm.started);
+
+    Ok(())
+}
+
+mod all_guarded {
+    use finny::{FsmCurrentState, FsmError, FsmFactory, FsmResult, decl::{BuiltFsm, FsmBuilder}, finny_fsm};
+
+    #[derive(Default)]
+    pub struct Config {
+        fast: bool,
+        slow: bool
+    }
+
+    #[derive(Default)]
+    pub struct Fast;
+    #[derive(Default)]
+    pub struct Slow;
+    #[derive(Default)]
+    pub struct LightOff;
+    #[derive(Clone)]
+    pub struct Blink;
+
+    #[finny_fsm]
+    fn build_fsm(mut fsm: FsmBuilder<GuardedMachine, Config>) -> BuiltFsm {
+        fsm.region::<Motor>().initial_state::<Fast>()
+            .guard(|ctx| ctx.fast);
+        fsm.region::<Motor>().initial_state::<Slow>()
+            .guard(|ctx| ctx.slow);
+        fsm.state::<Fast>();
+        fsm.state::<Slow>();
+
+        fsm.region::<Light>().initial_state::<LightOff>();
+        fsm.state::<LightOff>()
+            .on_event::<Blink>()
+            .internal_transition();
+
+        fsm.build()
+    }
+
+    #[test]
+    fn test_all_initial_guards_fail() -> FsmResult<()> {
+        let mut fsm = GuardedMachine::new(Config::default())?;
+        assert_eq!(Err(FsmError::NoInitialState), fsm.start());
+        assert_eq!(FsmCurrentState::Stopped, fsm.get_current_states()[0]);
+        assert_eq!(FsmCurrentState::State(GuardedMachineCurrentState::LightOff), fsm.get_current_states()[1]);
+
+        let mut fsm = GuardedMachine::new(Config { fast: false, slow: true })?;
+        fsm.start()?;
+        assert_eq!(FsmCurrentState::State(GuardedMachineCurrentState::Slow), fsm.get_current_states()[0]);
+
+        Ok(())
+    }
+}
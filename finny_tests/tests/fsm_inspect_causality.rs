@@ -0,0 +1,138 @@
+extern crate finny;
+
+use std::cell::Cell;
+
+use finny::{FsmClock, FsmEventQueueCausal, FsmEventQueueSender, FsmEventQueueVec, FsmFactory, FsmResult, decl::{BuiltFsm, FsmBuilder}, finny_fsm, inspect_json::InspectJsonLines};
+use serde_json::Value;
+
+#[derive(Default)]
+pub struct Idle;
+#[derive(Default)]
+pub struct Working;
+#[derive(Default)]
+pub struct Done;
+
+#[derive(Clone)]
+pub struct Order;
+#[derive(Clone)]
+pub struct Pick;
+#[derive(Clone)]
+pub struct Pack;
+#[derive(Clone)]
+pub struct Ship;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<Warehouse, ()>) -> BuiltFsm {
+    fsm.initial_state::<Idle>();
+
+    fsm.state::<Idle>()
+        .on_event::<Order>()
+        .transition_to::<Working>()
+        .action(|_, ctx, _, _| {
+            ctx.queue.enqueue(Pick).unwrap();
+            ctx.queue.enqueue(Pack).unwrap();
+        });
+
+    fsm.state::<Working>()
+        .on_event::<Pick>()
+        .internal_transition()
+        .action(|_, ctx, _| {
+            ctx.queue.enqueue(Ship).unwrap();
+        });
+
+    fsm.state::<Working>()
+        .on_event::<Pack>()
+        .internal_transition();
+
+    fsm.state::<Working>()
+        .on_event::<Ship>()
+        .transition_to::<Done>();
+
+    fsm.state::<Done>()
+        .on_event::<Order>()
+        .transition_to::<Working>();
+
+    fsm.state::<Done>()
+        .on_event::<Pack>()
+        .internal_transition();
+
+    fsm.build()
+}
+
+#[derive(Default)]
+struct TickClock(Cell<u64>);
+
+impl FsmClock for TickClock {
+    fn now(&self) -> u64 {
+        self.0.set(self.0.get() + 1);
+        self.0.get()
+    }
+}
+
+fn events(inspect: &InspectJsonLines<Vec<u8>>) -> Vec<(String, u64, Option<u64>, u64)> {
+    inspect.with_writer(|w| {
+        let lines: Vec<Value> = std::str::from_utf8(w.as_slice()).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        w.clear();
+        lines.into_iter()
+            .filter(|l| l["hook"] == "event")
+            .map(|l| (l["event"].as_str().unwrap().to_string(), l["event_id"].as_u64().unwrap(), l["parent_id"].as_u64(), l["root_id"].as_u64().unwrap()))
+            .collect()
+    })
+}
+
+#[test]
+fn test_inspect_causality() -> FsmResult<()> {
+    let queue = FsmEventQueueCausal::<_, _, _, 16>::with_clock(FsmEventQueueVec::new(), TickClock::default());
+    let mut fsm = Warehouse::new_with((), queue, InspectJsonLines::new(vec![]))?;
+    fsm.start()?;
+    assert_eq!(vec![("Fsm::Start".to_string(), 1, None, 1)], events(&fsm.inspect));
+
+    fsm.dispatch(Order)?;
+    assert_eq!(vec![
+        ("Order".to_string(), 2, None, 2),
+        ("Pick".to_string(), 3, Some(2), 2),
+        ("Pack".to_string(), 4, Some(2), 2),
+        ("Ship".to_string(), 5, Some(3), 2)
+    ], events(&fsm.inspect));
+
+    // enqueued from the outside, starts a chain of its own
+    fsm.queue.enqueue(Order)?;
+    fsm.dispatch(Pack)?;
+    assert_eq!(vec![
+        ("Pack".to_string(), 7, None, 7),
+        ("Order".to_string(), 6, None, 6)
+    ], events(&fsm.inspect));
+
+    Ok(())
+}
+
+#[test]
+fn test_enqueue_timestamps() -> FsmResult<()> {
+    let queue = FsmEventQueueCausal::<_, _, _, 16>::with_clock(FsmEventQueueVec::new(), TickClock::default());
+    let mut fsm = Warehouse::new_with((), queue, InspectJsonLines::new(vec![]))?;
+    fsm.start()?;
+    fsm.dispatch(Order)?;
+
+    let stamps: Vec<u64> = fsm.inspect.with_writer(|w| {
+        std::str::from_utf8(w.as_slice()).unwrap().lines()
+            .map(|l| serde_json::from_str::<Value>(l).unwrap())
+            .filter(|l| l["hook"] == "event")
+            .map(|l| l["enqueued_at"].as_u64().unwrap())
+            .collect()
+    });
+    assert_eq!(vec![1, 2, 3, 4, 5], stamps);
+
+    Ok(())
+}
+
+#[test]
+fn test_queue_without_causality() -> FsmResult<()> {
+    let mut fsm = Warehouse::new_with((), FsmEventQueueVec::new(), InspectJsonLines::new(vec![]))?;
+    fsm.start()?;
+    fsm.dispatch(Order)?;
+
+    let has_envelope = fsm.inspect.with_writer(|w| std::str::from_utf8(w.as_slice()).unwrap().contains("event_id"));
+    assert!(!has_envelope);
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+extern crate finny;
+
+use std::{cell::RefCell, rc::Rc};
+
+use finny::{FsmBackend, FsmEvent, FsmEventEnvelope, FsmEventQueueVec, FsmFactory, FsmRegionId, FsmResult, Inspect, InspectChain, InspectNull, decl::{BuiltFsm, FsmBuilder}, finny_fsm, short_type_name};
+
+#[derive(Default)]
+pub struct StateA;
+#[derive(Default)]
+pub struct StateB;
+
+#[derive(Clone)]
+pub struct Go;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, ()>) -> BuiltFsm {
+    fsm.initial_state::<StateA>();
+    fsm.state::<StateA>()
+        .on_event::<Go>()
+        .transition_to::<StateB>();
+    fsm.state::<StateB>();
+
+    fsm.build()
+}
+
+type Log = Rc<RefCell<Vec<String>>>;
+
+/// Records the hooks, prefixed with its name and the scopes it was created for.
+pub struct InspectNamed {
+    name: String,
+    log: Log
+}
+
+impl InspectNamed {
+    fn new(name: &str, log: &Log) -> Self {
+        InspectNamed { name: name.into(), log: log.clone() }
+    }
+
+    fn scope(&self, scope: &str) -> Self {
+        InspectNamed::new(&format!("{}/{}", self.name, scope), &self.log)
+    }
+
+    fn push(&self, hook: &str) {
+        self.log.borrow_mut().push(format!("{}: {}", self.name, hook));
+    }
+}
+
+impl Inspect for InspectNamed {
+    fn new_event<F: FsmBackend>(&self, event: &FsmEvent<<F as FsmBackend>::Events>, _envelope: Option<&FsmEventEnvelope>) -> Self {
+        self.scope(event.as_ref())
+    }
+
+    fn event_done(self) {
+        self.push("done");
+    }
+
+    fn for_region<F: FsmBackend>(&self, _region: FsmRegionId) -> Self {
+        self.scope("region")
+    }
+
+    fn for_transition<T>(&self) -> Self {
+        self.scope(short_type_name::<T>())
+    }
+
+    fn for_sub_machine<FSub: FsmBackend>(&self) -> Self {
+        self.scope(short_type_name::<FSub>())
+    }
+
+    fn on_state_enter<S>(&self) {
+        self.push(&format!("enter {}", short_type_name::<S>()));
+    }
+}
+
+#[test]
+fn test_inspect_chain() -> FsmResult<()> {
+    let log = Log::default();
+    let inspect = InspectChain::new(InspectNamed::new("a", &log), InspectNamed::new("b", &log)).chain(InspectNull::new());
+    let mut fsm = StateMachine::new_with((), FsmEventQueueVec::new(), inspect)?;
+
+    fsm.start()?;
+    fsm.dispatch(Go)?;
+
+    assert_eq!(vec![
+        "a/Fsm::Start/region/StateMachineTransition1: enter StateA",
+        "b/Fsm::Start/region/StateMachineTransition1: enter StateA",
+        "a/Fsm::Start: done",
+        "b/Fsm::Start: done",
+        "a/Go/region/StateMachineTransition2: enter StateB",
+        "b/Go/region/StateMachineTransition2: enter StateB",
+        "a/Go: done",
+        "b/Go: done"
+    ], *log.borrow());
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+extern crate finny;
+
+use finny::{FsmEventQueueVec, FsmFactory, FsmResult, decl::{BuiltFsm, FsmBuilder}, finny_fsm, inspect_chrome::InspectChromeTrace};
+use serde_json::Value;
+
+#[derive(Default)]
+pub struct Off;
+#[derive(Default)]
+pub struct On;
+#[derive(Default)]
+pub struct Quiet;
+
+#[derive(Clone)]
+pub struct Toggle;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<Light, ()>) -> BuiltFsm {
+    fsm.region::<Main>().initial_state::<Off>();
+    fsm.state::<Off>()
+        .on_event::<Toggle>()
+        .transition_to::<On>()
+        .guard(|_, _, _| true);
+    fsm.state::<On>();
+
+    fsm.region::<Buzzer>().initial_state::<Quiet>();
+    fsm.state::<Quiet>();
+
+    fsm.build()
+}
+
+fn slices<'a>(trace: &'a [Value], cat: &str) -> Vec<&'a Value> {
+    trace.iter().filter(|e| e["ph"] == "X" && e["cat"] == cat).collect()
+}
+
+fn within(outer: &Value, inner: &Value) -> bool {
+    let (os, od) = (outer["ts"].as_f64().unwrap(), outer["dur"].as_f64().unwrap());
+    let (is, id) = (inner["ts"].as_f64().unwrap(), inner["dur"].as_f64().unwrap());
+    outer["tid"] == inner["tid"] && os <= is && is + id <= os + od + 0.001
+}
+
+#[test]
+fn test_inspect_chrome() -> FsmResult<()> {
+    let mut fsm = Light::new_with((), FsmEventQueueVec::new(), InspectChromeTrace::new(vec![]))?;
+    fsm.start()?;
+    fsm.dispatch(Toggle)?;
+    fsm.inspect.finish();
+
+    let trace: Vec<Value> = fsm.inspect.with_writer(|w| serde_json::from_slice(w).unwrap());
+
+    let tracks: Vec<_> = trace.iter().filter(|e| e["ph"] == "M").map(|e| (e["tid"].as_u64().unwrap(), e["args"]["name"].as_str().unwrap())).collect();
+    assert_eq!(vec![(1, "Light"), (2, "Light / Main"), (3, "Light / Buzzer")], tracks);
+
+    let events = slices(&trace, "event");
+    assert_eq!(vec!["Fsm::Start", "Toggle"], events.iter().map(|e| e["name"].as_str().unwrap()).collect::<Vec<_>>());
+    assert!(events.iter().all(|e| e["tid"] == 1));
+
+    let regions = slices(&trace, "region");
+    assert_eq!(4, regions.len());
+    let toggle_main = regions.iter().find(|r| r["name"] == "Toggle" && r["tid"] == 2).unwrap();
+
+    let transition = slices(&trace, "transition").into_iter().filter(|t| within(toggle_main, t)).collect::<Vec<_>>();
+    assert_eq!(1, transition.len());
+    let transition = transition[0];
+
+    let guard = slices(&trace, "guard")[0];
+    assert!(within(toggle_main, guard));
+    assert_eq!("true", guard["args"]["result"]);
+    assert!(guard["ts"].as_f64().unwrap() <= transition["ts"].as_f64().unwrap());
+
+    let nested: Vec<_> = trace.iter().filter(|e| e["ph"] == "X" && e != &transition && within(transition, e)).map(|e| e["name"].as_str().unwrap()).collect();
+    assert_eq!(vec!["exit Off", "action LightTransition3", "enter On"], nested);
+
+    Ok(())
+}
@@ -0,0 +1,126 @@
+extern crate finny;
+
+use finny::{FsmEventQueueVec, FsmFactory, FsmResult, decl::{BuiltFsm, FsmBuilder}, finny_fsm, inspect_coverage::FsmCoverage};
+use serde_json::Value;
+
+#[derive(Default)]
+pub struct Context {
+    allow: bool
+}
+
+#[derive(Default)]
+pub struct StateA;
+#[derive(Default)]
+pub struct StateB;
+#[derive(Default)]
+pub struct StateC;
+
+#[derive(Clone)]
+pub struct EventClick;
+#[derive(Clone)]
+pub struct EventEnter;
+#[derive(Clone)]
+pub struct EventSub;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, Context>) -> BuiltFsm {
+    fsm.initial_state::<StateA>();
+
+    fsm.state::<StateA>()
+        .on_event::<EventClick>()
+        .transition_to::<StateB>()
+        .guard(|_, ctx, _| ctx.context.allow);
+
+    fsm.state::<StateB>()
+        .on_event::<EventEnter>()
+        .internal_transition();
+
+    fsm.state::<StateB>()
+        .on_event::<EventClick>()
+        .transition_to::<SubMachine>();
+
+    fsm.state::<StateA>()
+        .on_event::<EventEnter>()
+        .transition_to::<StateC>();
+
+    fsm.state::<StateC>();
+    fsm.sub_machine::<SubMachine>();
+
+    fsm.build()
+}
+
+#[derive(Default)]
+pub struct SubA;
+#[derive(Default)]
+pub struct SubB;
+
+#[finny_fsm]
+fn build_sub_fsm(mut fsm: FsmBuilder<SubMachine, ()>) -> BuiltFsm {
+    fsm.initial_state::<SubA>();
+    fsm.state::<SubA>()
+        .on_event::<EventSub>()
+        .transition_to::<SubB>();
+    fsm.state::<SubB>();
+    fsm.build()
+}
+
+#[test]
+fn test_inspect_coverage() -> FsmResult<()> {
+    let coverage = FsmCoverage::new::<StateMachine>();
+    let mut fsm = StateMachine::new_with(Context::default(), FsmEventQueueVec::new(), coverage.clone())?;
+    fsm.start()?;
+    assert!(fsm.dispatch(EventClick).is_err());
+    fsm.backend.context.allow = true;
+    fsm.dispatch(EventClick)?;
+    fsm.dispatch(EventClick)?;
+
+    let report = coverage.report();
+    assert!(!report.is_complete());
+    assert_eq!(vec!["StateMachine", "SubMachine"], report.machines.iter().map(|m| m.name).collect::<Vec<_>>());
+
+    let main = &report.machines[0];
+    assert_eq!((3, 5), main.transitions_covered());
+    assert_eq!((2, 2), main.guards_covered());
+    assert_eq!((3, 4), main.states_covered());
+    assert_eq!(vec![
+        "transition StateMachineTransition4 (StateB --EventEnter--> internal) never exercised",
+        "transition StateMachineTransition5 (StateA --EventEnter--> StateC) never exercised",
+        "state StateC never entered"
+    ], main.uncovered());
+
+    let sub = &report.machines[1];
+    assert_eq!((1, 2), sub.transitions_covered());
+    assert_eq!(vec![
+        "transition SubMachineTransition2 (SubA --EventSub--> SubB) never exercised",
+        "state SubB never entered"
+    ], sub.uncovered());
+
+    let text = report.to_text();
+    assert!(text.starts_with("StateMachine: 3/5 transitions, 2/2 guard outcomes, 3/4 states\n  transition StateMachineTransition4"), "{}", text);
+    assert!(text.contains("SubMachine: 1/2 transitions, 0/0 guard outcomes, 1/2 states\n"), "{}", text);
+
+    let json: Value = serde_json::from_str(&report.to_json()).unwrap();
+    let guarded = json["machines"][0]["transitions"].as_array().unwrap().iter().find(|t| t["description"] == "StateA --EventClick--> StateB").unwrap();
+    assert_eq!(1, guarded["taken"]);
+    assert_eq!(1, guarded["guard_passed"]);
+    assert_eq!(1, guarded["guard_rejected"]);
+    assert_eq!(4, json["machines"][0]["states_total"]);
+
+    fsm.dispatch(SubMachineEvents::EventSub(EventSub))?;
+    fsm.dispatch(EventEnter).unwrap_err();
+    assert_eq!(0, coverage.report().machines[1].uncovered().len());
+
+    coverage.reset();
+    assert_eq!((0, 5), coverage.report().machines[0].transitions_covered());
+
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "state StateC never entered")]
+fn test_assert_complete() {
+    let coverage = FsmCoverage::new::<StateMachine>();
+    let mut fsm = StateMachine::new_with(Context::default(), FsmEventQueueVec::new(), coverage.clone()).unwrap();
+    fsm.start().unwrap();
+    coverage.assert_complete();
+}
@@ -0,0 +1,178 @@
+extern crate finny;
+
+use std::{cell::RefCell, rc::Rc};
+
+use finny::{FsmBackend, FsmError, FsmEvent, FsmEventEnvelope, FsmEventQueueSender, FsmEventQueueVec, FsmFactory, FsmRegionId, FsmResult, Inspect, decl::{BuiltFsm, FsmBuilder}, finny_fsm};
+
+#[derive(Debug, Default)]
+pub struct Context {
+    pings: usize
+}
+
+#[derive(Debug, Default)]
+pub struct StateA;
+#[derive(Debug, Default)]
+pub struct StateB {
+    entries: usize
+}
+
+#[derive(Clone, Debug)]
+pub struct Ping;
+#[derive(Clone, Debug)]
+pub struct Go;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, Context>) -> BuiltFsm {
+    fsm.inspect_debug();
+    fsm.initial_state::<StateA>();
+
+    fsm.state::<StateA>()
+        .on_event::<Ping>()
+        .internal_transition()
+        .action(|_, ctx, _| {
+            ctx.context.pings += 1;
+            ctx.queue.enqueue(Go).unwrap();
+        });
+
+    fsm.state::<StateA>()
+        .on_event::<Go>()
+        .transition_to::<StateB>();
+
+    fsm.state::<StateB>()
+        .on_entry(|state, _| {
+            state.entries += 1;
+        });
+
+    fsm.build()
+}
+
+#[derive(Default)]
+pub struct PlainState;
+
+#[derive(Clone)]
+pub struct PlainEvent;
+
+#[finny_fsm]
+fn build_plain_fsm(mut fsm: FsmBuilder<PlainMachine, ()>) -> BuiltFsm {
+    fsm.initial_state::<PlainState>();
+
+    fsm.state::<PlainState>()
+        .on_event::<PlainEvent>()
+        .self_transition()
+        .guard(|_, _, _| false);
+
+    fsm.build()
+}
+
+/// Records every hook that carries some data.
+#[derive(Clone, Default)]
+pub struct InspectLog {
+    log: Rc<RefCell<Vec<String>>>
+}
+
+impl InspectLog {
+    fn push(&self, line: String) {
+        self.log.borrow_mut().push(line);
+    }
+
+    fn take(&self) -> Vec<String> {
+        self.log.borrow_mut().drain(..).collect()
+    }
+}
+
+impl Inspect for InspectLog {
+    fn new_event<F: FsmBackend>(&self, _event: &FsmEvent<<F as FsmBackend>::Events>, _envelope: Option<&FsmEventEnvelope>) -> Self {
+        self.clone()
+    }
+
+    fn event_done(self) {
+
+    }
+
+    fn for_region<F: FsmBackend>(&self, _region: FsmRegionId) -> Self {
+        self.clone()
+    }
+
+    fn for_transition<T>(&self) -> Self {
+        self.clone()
+    }
+
+    fn for_sub_machine<FSub: FsmBackend>(&self) -> Self {
+        self.clone()
+    }
+
+    fn on_event_data(&self, event: &dyn std::fmt::Debug) {
+        self.push(format!("event {:?}", event));
+    }
+
+    fn on_state_enter_data<S>(&self, state: &dyn std::fmt::Debug, context: &dyn std::fmt::Debug) {
+        self.push(format!("enter {:?} {:?}", state, context));
+    }
+
+    fn on_state_exit_data<S>(&self, state: &dyn std::fmt::Debug, context: &dyn std::fmt::Debug) {
+        self.push(format!("exit {:?} {:?}", state, context));
+    }
+
+    fn on_action_data<T>(&self, context: &dyn std::fmt::Debug) {
+        self.push(format!("action {:?}", context));
+    }
+
+    fn on_no_transition(&self) {
+        self.push("no transition".into());
+    }
+
+    fn on_error(&self, error: &FsmError) {
+        self.push(format!("error {:?}", error));
+    }
+
+    fn on_queue_enqueue<F: FsmBackend>(&self, event: &<F as FsmBackend>::Events) {
+        self.push(format!("enqueue {}", event.as_ref()));
+    }
+}
+
+#[test]
+fn test_inspect_data() -> FsmResult<()> {
+    let inspect = InspectLog::default();
+    let mut fsm = StateMachine::new_with(Context::default(), FsmEventQueueVec::new(), inspect.clone())?;
+
+    fsm.start()?;
+    assert_eq!(vec![
+        "action Context { pings: 0 }",
+        "enter StateA Context { pings: 0 }"
+    ], inspect.take());
+
+    fsm.dispatch(Ping)?;
+    assert_eq!(vec![
+        "event Ping(Ping)",
+        "enqueue Go",
+        "action Context { pings: 1 }",
+        "event Go(Go)",
+        "exit StateA Context { pings: 1 }",
+        "action Context { pings: 1 }",
+        "enter StateB { entries: 1 } Context { pings: 1 }"
+    ], inspect.take());
+
+    assert_eq!(Err(FsmError::NoTransition), fsm.dispatch(Ping));
+    assert_eq!(vec![
+        "event Ping(Ping)",
+        "no transition",
+        "error NoTransition"
+    ], inspect.take());
+
+    Ok(())
+}
+
+#[test]
+fn test_inspect_without_data() -> FsmResult<()> {
+    let inspect = InspectLog::default();
+    let mut fsm = PlainMachine::new_with((), FsmEventQueueVec::new(), inspect.clone())?;
+
+    fsm.start()?;
+    assert_eq!(Err(FsmError::NoTransition), fsm.dispatch(PlainEvent));
+    assert_eq!(vec![
+        "no transition",
+        "error NoTransition"
+    ], inspect.take());
+
+    Ok(())
+}
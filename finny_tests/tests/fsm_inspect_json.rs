@@ -0,0 +1,92 @@
+extern crate finny;
+
+use finny::{FsmEventQueueSender, FsmEventQueueVec, FsmFactory, FsmResult, decl::{BuiltFsm, FsmBuilder}, finny_fsm, inspect_json::InspectJsonLines};
+use serde_json::Value;
+
+#[derive(Debug, Default)]
+pub struct Idle;
+#[derive(Debug, Default)]
+pub struct Busy;
+
+#[derive(Clone, Debug)]
+pub struct Work;
+#[derive(Clone, Debug)]
+pub struct Finish;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, ()>) -> BuiltFsm {
+    fsm.inspect_debug();
+    fsm.initial_state::<Idle>();
+
+    fsm.state::<Idle>()
+        .on_event::<Work>()
+        .transition_to::<Busy>()
+        .action(|_, ctx, _, _| {
+            ctx.queue.enqueue(Finish).unwrap();
+        });
+
+    fsm.state::<Busy>()
+        .on_event::<Finish>()
+        .transition_to::<Idle>();
+
+    fsm.build()
+}
+
+fn lines(inspect: &InspectJsonLines<Vec<u8>>) -> Vec<Value> {
+    inspect.with_writer(|w| {
+        let lines = std::str::from_utf8(w.as_slice()).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        w.clear();
+        lines
+    })
+}
+
+#[test]
+fn test_inspect_json() -> FsmResult<()> {
+    let mut fsm = StateMachine::new_with((), FsmEventQueueVec::new(), InspectJsonLines::new(vec![]))?;
+    fsm.start()?;
+    let start = lines(&fsm.inspect);
+    assert!(start.iter().all(|l| l["id"] == 1));
+
+    fsm.dispatch(Work)?;
+    let lines = lines(&fsm.inspect);
+
+    let hooks: Vec<_> = lines.iter().map(|l| (l["id"].as_u64().unwrap(), l["depth"].as_u64().unwrap(), l["hook"].as_str().unwrap())).collect();
+    assert_eq!(vec![
+        (2, 0, "event"),
+        (2, 0, "event_data"),
+        (2, 1, "region"),
+        (2, 2, "transition"),
+        (2, 2, "exit"),
+        (2, 2, "exit_data"),
+        (2, 2, "action"),
+        (2, 2, "enqueue"),
+        (2, 2, "action_data"),
+        (2, 2, "enter"),
+        (2, 2, "enter_data"),
+        (2, 0, "done"),
+        (3, 0, "event"),
+        (3, 0, "event_data"),
+        (3, 1, "region"),
+        (3, 2, "transition"),
+        (3, 2, "exit"),
+        (3, 2, "exit_data"),
+        (3, 2, "action"),
+        (3, 2, "action_data"),
+        (3, 2, "enter"),
+        (3, 2, "enter_data"),
+        (3, 0, "done")
+    ], hooks);
+
+    assert_eq!("Work", lines[0]["event"]);
+    assert_eq!("fsm_inspect_json::StateMachine", lines[0]["fsm"]);
+    assert_eq!("Work(Work)", lines[1]["data"]);
+    assert_eq!("Region0", lines[2]["region"]);
+    assert_eq!("fsm_inspect_json::Idle", lines[4]["name"]);
+    assert_eq!("Finish", lines[7]["event"]);
+    assert_eq!("Busy", lines[10]["data"]);
+
+    let timestamps: Vec<_> = lines.iter().map(|l| l["ts_us"].as_u64().unwrap()).collect();
+    assert!(timestamps.windows(2).all(|w| w[0] <= w[1]));
+
+    Ok(())
+}
@@ -0,0 +1,117 @@
+extern crate finny;
+
+use std::cell::Cell;
+
+use finny::{FsmClock, FsmError, FsmEventQueueVec, FsmFactory, FsmMetricKind, FsmMetrics, FsmResult, decl::{BuiltFsm, FsmBuilder}, finny_fsm};
+
+#[derive(Default)]
+pub struct Idle;
+#[derive(Default)]
+pub struct Running;
+
+#[derive(Clone)]
+pub struct Run { allowed: bool }
+#[derive(Clone)]
+pub struct Halt;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<Motor, ()>) -> BuiltFsm {
+    fsm.initial_state::<Idle>();
+
+    fsm.state::<Idle>()
+        .on_event::<Run>()
+        .transition_to::<Running>()
+        .guard(|ev, _, _| ev.allowed);
+
+    fsm.state::<Running>()
+        .on_event::<Halt>()
+        .transition_to::<Idle>();
+
+    fsm.build()
+}
+
+pub struct TestClock<'a>(&'a Cell<u64>);
+
+impl<'a> FsmClock for TestClock<'a> {
+    fn now(&self) -> u64 {
+        self.0.get()
+    }
+}
+
+#[test]
+fn test_metrics_counters() -> FsmResult<()> {
+    let metrics = FsmMetrics::<_, 16>::new();
+    let mut fsm = Motor::new_with((), FsmEventQueueVec::new(), metrics.inspect())?;
+
+    fsm.start()?;
+    assert_eq!(Err(FsmError::NoTransition), fsm.dispatch(Run { allowed: false }));
+    fsm.dispatch(Run { allowed: true })?;
+    fsm.dispatch(Halt)?;
+    assert_eq!(Err(FsmError::NoTransition), fsm.dispatch(Halt));
+
+    let snapshot = metrics.snapshot();
+    let run = snapshot.get(FsmMetricKind::Event, "Motor", "Run").unwrap();
+    assert_eq!((2, 1), (run.count, run.rejected));
+    let halt = snapshot.get(FsmMetricKind::Event, "Motor", "Halt").unwrap();
+    assert_eq!((2, 1), (halt.count, halt.rejected));
+
+    let guard = snapshot.iter().find(|m| m.kind == FsmMetricKind::Guard).unwrap();
+    assert_eq!((1, 1), (guard.count, guard.rejected));
+
+    assert_eq!(3, snapshot.iter().filter(|m| m.kind == FsmMetricKind::Transition).map(|m| m.count).sum::<u64>());
+    assert_eq!(2, snapshot.get(FsmMetricKind::State, "Motor", "Idle").unwrap().count);
+    assert_eq!(1, snapshot.get(FsmMetricKind::State, "Motor", "Running").unwrap().count);
+    assert_eq!(0, snapshot.dropped);
+
+    Ok(())
+}
+
+#[test]
+fn test_metrics_time_in_state() -> FsmResult<()> {
+    let time = Cell::new(0);
+    let metrics = FsmMetrics::<_, 16>::with_clock_and_bounds(TestClock(&time), &[10, 100]);
+    let mut fsm = Motor::new_with((), FsmEventQueueVec::new(), metrics.inspect())?;
+
+    fsm.start()?;
+    time.set(5);
+    fsm.dispatch(Run { allowed: true })?;
+    time.set(55);
+    fsm.dispatch(Halt)?;
+    time.set(555);
+    fsm.dispatch(Run { allowed: true })?;
+
+    let snapshot = metrics.snapshot();
+    let idle = snapshot.get(FsmMetricKind::State, "Motor", "Idle").unwrap();
+    assert_eq!(505, idle.time_total);
+    assert_eq!([1, 0, 1], idle.time_histogram[..3]);
+    let running = snapshot.get(FsmMetricKind::State, "Motor", "Running").unwrap();
+    assert_eq!(50, running.time_total);
+    assert_eq!([0, 1, 0], running.time_histogram[..3]);
+
+    let mut text = String::new();
+    snapshot.render_prometheus(&mut text).unwrap();
+    assert!(text.contains("finny_events_total{fsm=\"Motor\",event=\"Run\"} 2\n"), "{}", text);
+    assert!(text.contains("finny_state_entries_total{fsm=\"Motor\",state=\"Idle\"} 2\n"), "{}", text);
+    assert!(text.contains("# TYPE finny_state_time histogram\n"), "{}", text);
+    assert!(text.contains("finny_state_time_bucket{fsm=\"Motor\",state=\"Idle\",le=\"10\"} 1\n"), "{}", text);
+    assert!(text.contains("finny_state_time_bucket{fsm=\"Motor\",state=\"Idle\",le=\"100\"} 1\n"), "{}", text);
+    assert!(text.contains("finny_state_time_bucket{fsm=\"Motor\",state=\"Idle\",le=\"+Inf\"} 2\n"), "{}", text);
+    assert!(text.contains("finny_state_time_sum{fsm=\"Motor\",state=\"Idle\"} 505\n"), "{}", text);
+
+    Ok(())
+}
+
+#[test]
+fn test_metrics_bounded() -> FsmResult<()> {
+    let metrics = FsmMetrics::<_, 2>::new();
+    let mut fsm = Motor::new_with((), FsmEventQueueVec::new(), metrics.inspect())?;
+
+    fsm.start()?;
+    fsm.dispatch(Run { allowed: true })?;
+
+    let snapshot = metrics.snapshot();
+    assert_eq!(2, snapshot.iter().count());
+    assert!(snapshot.dropped > 0);
+
+    Ok(())
+}
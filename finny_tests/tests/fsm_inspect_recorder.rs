@@ -0,0 +1,113 @@
+extern crate finny;
+
+use finny::{assert_trace, FsmError, FsmEventQueueSender, FsmEventQueueVec, FsmFactory, FsmResult, decl::{BuiltFsm, FsmBuilder}, finny_fsm, inspect_recorder::{FsmTraceEntry, InspectRecorder}};
+
+#[derive(Default)]
+pub struct Idle;
+
+#[derive(Clone)]
+pub struct Enter;
+#[derive(Clone)]
+pub struct Leave;
+#[derive(Clone)]
+pub struct Poke { n: usize }
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, ()>) -> BuiltFsm {
+    fsm.initial_state::<Idle>();
+    fsm.state::<Idle>()
+        .on_event::<Enter>()
+        .transition_to::<SubStateMachine>();
+
+    fsm.sub_machine::<SubStateMachine>()
+        .with_context(|_| ())
+        .on_event::<Leave>()
+        .transition_to::<Idle>();
+
+    fsm.sub_machine::<SubStateMachine>()
+        .on_event::<Poke>()
+        .internal_transition()
+        .guard(|ev, _, _| ev.n > 0)
+        .action(|_, ctx, _| {
+            ctx.queue.enqueue(Leave).unwrap();
+        });
+
+    fsm.build()
+}
+
+#[derive(Default)]
+pub struct SubA;
+#[derive(Default)]
+pub struct SubB;
+
+#[derive(Clone)]
+pub struct Step;
+
+#[finny_fsm]
+fn build_sub_fsm(mut fsm: FsmBuilder<SubStateMachine, ()>) -> BuiltFsm {
+    fsm.initial_state::<SubA>();
+    fsm.state::<SubA>()
+        .on_event::<Step>()
+        .transition_to::<SubB>();
+    fsm.state::<SubB>();
+
+    fsm.build()
+}
+
+#[test]
+fn test_assert_trace() -> FsmResult<()> {
+    let recorder = InspectRecorder::new();
+    let mut fsm = StateMachine::new_with((), FsmEventQueueVec::new(), recorder.clone())?;
+
+    fsm.start()?;
+    assert_trace!(recorder, [Event("Fsm::Start"), Action(..), Enter(Idle), Done]);
+
+    fsm.dispatch(Enter)?;
+    assert_trace!(recorder, [
+        Event(Enter), Exit(Idle), Action(..), Enter(SubStateMachine),
+            SubMachine(SubStateMachine), Event("Fsm::Start"), Action(..), Enter(SubA), Done,
+        Done
+    ]);
+
+    fsm.dispatch(SubStateMachineEvents::Step(Step))?;
+    assert_trace!(recorder, [
+        Event("SubStateMachineEvents"),
+            SubMachine(SubStateMachine), Event(Step), Exit(SubA), Action(..), Enter(SubB), Done,
+        Done
+    ]);
+
+    assert_eq!(Err(FsmError::NoTransition), fsm.dispatch(Poke { n: 0 }));
+    assert_trace!(recorder, [Event(Poke), Guard(.., false), NoTransition, Error("NoTransition"), Done]);
+
+    fsm.dispatch(Poke { n: 1 })?;
+    let trace = recorder.take();
+    assert_eq!(FsmTraceEntry::Enqueue("Leave".into()), trace[3]);
+    assert_eq!(Some(&FsmTraceEntry::Enter("Idle".into())), trace.iter().rev().nth(1));
+
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "The trace doesn't match")]
+fn test_assert_trace_mismatch() {
+    let recorder = InspectRecorder::new();
+    let mut fsm = StateMachine::new_with((), FsmEventQueueVec::new(), recorder.clone()).unwrap();
+
+    fsm.start().unwrap();
+    assert_trace!(recorder, [Event("Fsm::Start"), Action(..), Enter(SubA), Done]);
+}
+
+#[test]
+fn test_golden_trace() -> FsmResult<()> {
+    let recorder = InspectRecorder::new();
+    let mut fsm = StateMachine::new_with((), FsmEventQueueVec::new(), recorder.clone())?;
+
+    fsm.start()?;
+    fsm.dispatch(Enter)?;
+    fsm.dispatch(SubStateMachineEvents::Step(Step))?;
+    fsm.dispatch(Poke { n: 1 })?;
+
+    recorder.assert_golden("tests/data/fsm_inspect_recorder.trace");
+
+    Ok(())
+}
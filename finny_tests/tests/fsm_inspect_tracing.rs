@@ -0,0 +1,85 @@
+extern crate finny;
+
+use std::{io, sync::{Arc, Mutex}};
+
+use finny::{FsmEventQueueVec, FsmFactory, FsmResult, decl::{BuiltFsm, FsmBuilder}, finny_fsm, inspect_tracing::InspectTracing};
+
+#[derive(Debug, Default)]
+pub struct StateA;
+#[derive(Debug, Default)]
+pub struct StateB;
+
+#[derive(Clone, Debug)]
+pub struct Ping {
+    n: usize
+}
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, ()>) -> BuiltFsm {
+    fsm.inspect_debug();
+    fsm.initial_state::<StateA>();
+
+    fsm.state::<StateA>()
+        .on_event::<Ping>()
+        .transition_to::<StateB>()
+        .guard(|ev, _, _| ev.n > 0)
+        .action(|ev, _, _, _| {
+            tracing::info!(n = ev.n, "Pinged");
+        });
+
+    fsm.state::<StateB>();
+
+    fsm.build()
+}
+
+#[derive(Clone, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Output {
+    fn take(&self) -> String {
+        let lines = self.0.lock().unwrap().drain(..).collect();
+        String::from_utf8(lines).unwrap()
+    }
+}
+
+#[test]
+fn test_inspect_tracing() -> FsmResult<()> {
+    let output = Output::default();
+    let writer = output.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .with_target(false)
+        .with_max_level(tracing::Level::TRACE)
+        .finish();
+
+    tracing::subscriber::with_default(subscriber, || {
+        let mut fsm = StateMachine::new_with((), FsmEventQueueVec::new(), InspectTracing::new())?;
+        fsm.start()?;
+        output.take();
+
+        assert!(fsm.dispatch(Ping { n: 0 }).is_err());
+        let log = output.take();
+        assert!(log.contains("finny_event{fsm=\"fsm_inspect_tracing::StateMachine\" event=\"Ping\" data=Ping(Ping { n: 0 }) error=NoTransition}: Dispatch done"), "{}", log);
+        assert!(log.contains("finny_region{region=\"Region0\" guard=\""), "{}", log);
+        assert!(log.contains("guard_result=false}"), "{}", log);
+
+        fsm.dispatch(Ping { n: 1 })?;
+        let log = output.take();
+        assert!(log.contains("guard_result=true}:finny_transition{transition=\""), "{}", log);
+        assert!(log.contains("}: Pinged n=1"), "{}", log);
+        assert!(log.contains("}: Entering state=\"fsm_inspect_tracing::StateB\""), "{}", log);
+
+        Ok(())
+    })
+}
@@ -0,0 +1,62 @@
+extern crate finny;
+
+use finny::{FsmBackend, FsmCurrentState, FsmFactory, FsmRegion, FsmResult, decl::{BuiltFsm, FsmBuilder}, finny_fsm};
+
+#[derive(Default)]
+pub struct Context {
+    motor_regions: Vec<&'static str>
+}
+
+#[derive(Default)]
+pub struct Idle;
+#[derive(Default)]
+pub struct Running;
+#[derive(Default)]
+pub struct LightOff;
+#[derive(Default)]
+pub struct LightOn;
+#[derive(Clone)]
+pub struct Event;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, Context>) -> BuiltFsm {
+    fsm.region::<LightRegion>().initial_state::<LightOff>();
+    fsm.region::<MotorRegion>().state::<Running>().initial_state::<Idle>();
+
+    fsm.state::<Idle>()
+        .on_event::<Event>()
+        .transition_to::<Running>()
+        .action(|_ev, ctx, _from, _to| {
+            if ctx.is_region::<MotorRegion>() {
+                let region = ctx.region_name();
+                ctx.motor_regions.push(region);
+            }
+        });
+    fsm.state::<Running>();
+
+    fsm.state::<LightOff>().on_event::<Event>().transition_to::<LightOn>();
+    fsm.state::<LightOn>();
+
+    fsm.build()
+}
+
+#[test]
+fn test_named_regions() -> FsmResult<()> {
+    assert_eq!(&["LightRegion", "MotorRegion"], StateMachine::REGION_NAMES);
+    assert_eq!(1, <MotorRegion as FsmRegion<StateMachine>>::REGION_ID);
+    assert_eq!("MotorRegion", <MotorRegion as FsmRegion<StateMachine>>::NAME);
+
+    let mut fsm = StateMachine::new(Context::default())?;
+    assert_eq!(FsmCurrentState::Stopped, fsm.get_current_state::<MotorRegion>());
+
+    fsm.start()?;
+    assert_eq!(FsmCurrentState::State(StateMachineCurrentState::Idle), fsm.get_current_state::<MotorRegion>());
+    assert_eq!(FsmCurrentState::State(StateMachineCurrentState::LightOff), fsm.get_current_state::<LightRegion>());
+
+    fsm.dispatch(Event)?;
+    assert_eq!(FsmCurrentState::State(StateMachineCurrentState::Running), fsm.get_current_state::<MotorRegion>());
+    assert_eq!(FsmCurrentState::State(StateMachineCurrentState::LightOn), fsm.get_current_state::<LightRegion>());
+    assert_eq!(vec!["MotorRegion"], fsm.motor_regions);
+
+    Ok(())
+}
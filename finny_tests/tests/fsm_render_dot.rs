@@ -0,0 +1,93 @@
+extern crate finny;
+
+use finny::{FsmDescription, decl::{BuiltFsm, FsmBuilder}, finny_fsm, render::{to_dot, write_dot}};
+
+#[derive(Default)]
+pub struct Context;
+
+#[derive(Default)]
+pub struct Idle;
+#[derive(Default)]
+pub struct Running;
+#[derive(Clone)]
+pub struct Start;
+#[derive(Clone)]
+pub struct Tick;
+#[derive(Clone)]
+pub struct Stop;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, Context>) -> BuiltFsm {
+    fsm.initial_state::<Idle>();
+
+    fsm.state::<Idle>()
+        .on_event::<Start>()
+        .transition_to::<Running>()
+        .guard(|_ev, _ctx, _states| true)
+        .action(|_ev, _ctx, _from, _to| { });
+
+    fsm.state::<Running>()
+        .on_event::<Tick>()
+        .internal_transition()
+        .action(|_ev, _ctx, _state| { });
+
+    fsm.state::<Running>()
+        .on_event::<Stop>()
+        .transition_to::<SubStateMachine>();
+
+    fsm.sub_machine::<SubStateMachine>()
+        .on_event::<Stop>()
+        .transition_to::<Idle>();
+
+    fsm.build()
+}
+
+#[derive(Default)]
+pub struct SubContext;
+#[derive(Default)]
+pub struct SubState;
+#[derive(Clone)]
+pub struct SubEvent;
+
+#[finny_fsm]
+fn build_sub_fsm(mut fsm: FsmBuilder<SubStateMachine, SubContext>) -> BuiltFsm {
+    fsm.initial_state::<SubState>();
+    fsm.state::<SubState>()
+        .on_event::<SubEvent>()
+        .self_transition();
+    fsm.build()
+}
+
+#[test]
+fn test_render_dot() {
+    let dot = to_dot(StateMachine::describe());
+
+    let expected = r#"digraph "StateMachine" {
+    compound=true;
+    node [shape=box, style=rounded];
+    "StateMachine#start0" [shape=point];
+    "StateMachine/Idle" [label="Idle"];
+    "StateMachine/Running" [label="Running"];
+    subgraph "cluster_StateMachine/SubStateMachine" {
+        label="SubStateMachine";
+        style=rounded;
+        "StateMachine/SubStateMachine" [shape=point, style=invis];
+        "StateMachine/SubStateMachine#start0" [shape=point];
+        "StateMachine/SubStateMachine/SubState" [label="SubState"];
+        "StateMachine/SubStateMachine#start0" -> "StateMachine/SubStateMachine/SubState" [label=""];
+        "StateMachine/SubStateMachine/SubState" -> "StateMachine/SubStateMachine/SubState" [label="SubEvent"];
+    }
+    "StateMachine#start0" -> "StateMachine/Idle" [label=""];
+    "StateMachine/Idle" -> "StateMachine/Running" [label="Start [guard] / action"];
+    "StateMachine/Running" -> "StateMachine/Running" [label="Tick / action", style=dashed];
+    "StateMachine/Running" -> "StateMachine/SubStateMachine" [label="Stop", lhead="cluster_StateMachine/SubStateMachine"];
+    "StateMachine/SubStateMachine" -> "StateMachine/Idle" [label="Stop", ltail="cluster_StateMachine/SubStateMachine"];
+}
+"#;
+    assert_eq!(expected, dot);
+
+    // the same output without an allocating helper
+    let mut s = String::new();
+    write_dot(StateMachine::describe(), &mut s).unwrap();
+    assert_eq!(dot, s);
+}
@@ -0,0 +1,108 @@
+extern crate finny;
+
+use finny::{FsmDescription, decl::{BuiltFsm, FsmBuilder}, finny_fsm, render::to_scxml};
+
+#[derive(Default)]
+pub struct Context {
+    provisioned: bool
+}
+
+#[derive(Default)]
+pub struct Provisioned;
+#[derive(Default)]
+pub struct Unprovisioned;
+#[derive(Clone)]
+pub struct Provision;
+#[derive(Clone)]
+pub struct Ping;
+#[derive(Clone)]
+pub struct Reboot;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, Context>) -> BuiltFsm {
+    fsm.initial_state::<Provisioned>()
+        .guard(|ctx| ctx.provisioned);
+    fsm.initial_state::<Unprovisioned>();
+
+    fsm.state::<Unprovisioned>()
+        .on_event::<Provision>()
+        .transition_to::<Provisioned>()
+        .action(|_ev, ctx, _from, _to| {
+            ctx.provisioned = true;
+        });
+
+    fsm.state::<Provisioned>()
+        .on_event::<Ping>()
+        .internal_transition()
+        .action(|_ev, _ctx, _state| { });
+
+    fsm.state::<Provisioned>()
+        .on_event::<Reboot>()
+        .self_transition();
+
+    fsm.state::<Provisioned>()
+        .on_event::<Provision>()
+        .transition_to::<Session>();
+
+    fsm.sub_machine::<Session>();
+
+    fsm.build()
+}
+
+#[derive(Default)]
+pub struct SessionContext;
+#[derive(Default)]
+pub struct Rx;
+#[derive(Default)]
+pub struct Tx;
+#[derive(Clone)]
+pub struct Ack;
+
+#[finny_fsm]
+fn build_session_fsm(mut fsm: FsmBuilder<Session, SessionContext>) -> BuiltFsm {
+    fsm.region::<Receive>().initial_state::<Rx>();
+    fsm.region::<Transmit>().initial_state::<Tx>();
+    fsm.state::<Rx>();
+    fsm.state::<Tx>()
+        .on_event::<Ack>()
+        .self_transition();
+    fsm.build()
+}
+
+#[test]
+fn test_render_scxml() {
+    let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<scxml xmlns="http://www.w3.org/2005/07/scxml" version="1.0" datamodel="ecmascript" name="StateMachine" initial="StateMachine-Region0-initial">
+    <state id="StateMachine-Region0-initial">
+        <transition target="StateMachine.Provisioned" cond="StateMachineTransition1_guard()"/>
+        <transition target="StateMachine.Unprovisioned"/>
+    </state>
+    <state id="StateMachine.Unprovisioned">
+        <transition event="Provision" target="StateMachine.Provisioned">
+            <script>StateMachineTransition3_action();</script>
+        </transition>
+    </state>
+    <state id="StateMachine.Provisioned">
+        <transition event="Provision" target="StateMachine.Session"/>
+        <transition event="Ping">
+            <script>StateMachineTransition5_action();</script>
+        </transition>
+        <transition event="Reboot" target="StateMachine.Provisioned"/>
+    </state>
+    <state id="StateMachine.Session">
+        <parallel id="StateMachine.Session-regions">
+            <state id="StateMachine.Session-Receive" initial="StateMachine.Session.Rx">
+                <state id="StateMachine.Session.Rx"/>
+            </state>
+            <state id="StateMachine.Session-Transmit" initial="StateMachine.Session.Tx">
+                <state id="StateMachine.Session.Tx">
+                    <transition event="Ack" target="StateMachine.Session.Tx"/>
+                </state>
+            </state>
+        </parallel>
+    </state>
+</scxml>
+"#;
+
+    assert_eq!(expected, to_scxml(StateMachine::describe()));
+}
@@ -0,0 +1,87 @@
+extern crate finny;
+
+use finny::{FsmDescription, decl::{BuiltFsm, FsmBuilder}, finny_fsm, render::{to_mermaid, to_plantuml}};
+
+#[derive(Default)]
+pub struct Context;
+
+#[derive(Default)]
+pub struct Idle;
+#[derive(Default)]
+pub struct LightOff;
+#[derive(Default)]
+pub struct LightOn;
+#[derive(Clone)]
+pub struct Start;
+#[derive(Clone)]
+pub struct Toggle;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, Context>) -> BuiltFsm {
+    fsm.region::<Motor>().initial_state::<Idle>();
+    fsm.region::<Light>().initial_state::<LightOff>();
+
+    fsm.state::<Idle>()
+        .on_event::<Start>()
+        .transition_to::<Running>()
+        .guard(|_ev, _ctx, _states| true);
+
+    fsm.sub_machine::<Running>();
+
+    fsm.state::<LightOff>()
+        .on_event::<Toggle>()
+        .transition_to::<LightOn>()
+        .action(|_ev, _ctx, _from, _to| { });
+    fsm.state::<LightOn>()
+        .on_event::<Toggle>()
+        .transition_to::<LightOff>();
+
+    fsm.build()
+}
+
+#[derive(Default)]
+pub struct RunningContext;
+#[derive(Default)]
+pub struct Slow;
+#[derive(Default)]
+pub struct Fast;
+#[derive(Clone)]
+pub struct Faster;
+
+#[finny_fsm]
+fn build_running_fsm(mut fsm: FsmBuilder<Running, RunningContext>) -> BuiltFsm {
+    fsm.initial_state::<Slow>();
+    fsm.state::<Slow>()
+        .on_event::<Faster>()
+        .transition_to::<Fast>();
+    fsm.state::<Fast>();
+    fsm.build()
+}
+
+const BODY: &str = r#"    state StateMachine {
+        state "Idle" as StateMachine_Idle
+        state "Running" as StateMachine_Running
+        state StateMachine_Running {
+            state "Slow" as StateMachine_Running_Slow
+            state "Fast" as StateMachine_Running_Fast
+            [*] --> StateMachine_Running_Slow
+            StateMachine_Running_Slow --> StateMachine_Running_Fast : Faster
+        }
+        [*] --> StateMachine_Idle
+        StateMachine_Idle --> StateMachine_Running : Start [guard]
+        --
+        state "LightOff" as StateMachine_LightOff
+        state "LightOn" as StateMachine_LightOn
+        [*] --> StateMachine_LightOff
+        StateMachine_LightOff --> StateMachine_LightOn : Toggle / action
+        StateMachine_LightOn --> StateMachine_LightOff : Toggle
+    }
+"#;
+
+#[test]
+fn test_render_state_diagrams() {
+    let meta = StateMachine::describe();
+
+    assert_eq!(format!("stateDiagram-v2\n{}", BODY), to_mermaid(meta));
+    assert_eq!(format!("@startuml\n{}@enduml\n", BODY), to_plantuml(meta));
+}
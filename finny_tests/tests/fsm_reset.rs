@@ -0,0 +1,114 @@
+extern crate finny;
+
+use finny::{FsmCurrentState, FsmEventQueueVec, FsmFactory, FsmResult, InspectNull, decl::{BuiltFsm, FsmBuilder}, finny_fsm};
+
+#[derive(Default)]
+pub struct Context {
+    value: usize,
+    exits: usize
+}
+
+#[derive(Default)]
+pub struct StateA {
+    entries: usize
+}
+#[derive(Default)]
+pub struct StateB;
+#[derive(Clone)]
+pub struct Event;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, Context>) -> BuiltFsm {
+    fsm.initial_state::<StateA>();
+
+    fsm.state::<StateA>()
+        .on_entry(|state, _ctx| {
+            state.entries += 1;
+        })
+        .on_event::<Event>()
+        .transition_to::<StateB>();
+
+    fsm.state::<StateB>()
+        .on_event::<Event>()
+        .transition_to::<SubStateMachine>();
+
+    fsm.sub_machine::<SubStateMachine>()
+        .with_context(|ctx| SubContext { value: ctx.value })
+        .on_exit(|_sub, ctx| {
+            ctx.exits += 1;
+        });
+
+    fsm.build()
+}
+
+pub struct SubContext {
+    value: usize
+}
+
+#[derive(Default)]
+pub struct SubStateA;
+#[derive(Default)]
+pub struct SubStateB;
+#[derive(Clone)]
+pub struct SubEvent;
+
+#[finny_fsm]
+fn build_sub_fsm(mut fsm: FsmBuilder<SubStateMachine, SubContext>) -> BuiltFsm {
+    fsm.initial_state::<SubStateA>();
+    fsm.state::<SubStateA>()
+        .on_exit(|_state, ctx| {
+            ctx.value += 1;
+        })
+        .on_event::<SubEvent>()
+        .transition_to::<SubStateB>();
+    fsm.state::<SubStateB>();
+    fsm.build()
+}
+
+#[test]
+fn test_restart_and_reset() -> FsmResult<()> {
+    let mut fsm = StateMachine::new_with(Context::default(), FsmEventQueueVec::new(), InspectNull::new())?;
+
+    fsm.start()?;
+    fsm.dispatch(Event)?;
+    assert_eq!(FsmCurrentState::State(StateMachineCurrentState::StateB), fsm.get_current_states()[0]);
+
+    // restarting keeps the data of the states
+    fsm.restart()?;
+    assert_eq!(FsmCurrentState::State(StateMachineCurrentState::StateA), fsm.get_current_states()[0]);
+    let state: &StateA = fsm.get_state();
+    assert_eq!(2, state.entries);
+
+    // stopping exits the sub-machine and its states
+    fsm.dispatch(Event)?;
+    fsm.dispatch(Event)?;
+    assert_eq!(FsmCurrentState::State(StateMachineCurrentState::SubStateMachine), fsm.get_current_states()[0]);
+    fsm.stop()?;
+    assert_eq!(FsmCurrentState::Stopped, fsm.get_current_states()[0]);
+    assert_eq!(1, fsm.exits);
+    let sub: &SubStateMachine = fsm.get_state();
+    assert_eq!(FsmCurrentState::Stopped, sub.get_current_states()[0]);
+    assert_eq!(1, sub.get_context().value);
+
+    // resetting rebuilds the states
+    fsm.reset()?;
+    assert_eq!(FsmCurrentState::Stopped, fsm.get_current_states()[0]);
+    let state: &StateA = fsm.get_state();
+    assert_eq!(0, state.entries);
+    let sub: &SubStateMachine = fsm.get_state();
+    assert_eq!(0, sub.get_context().value);
+
+    // the new context is used to build the sub-machine
+    fsm.start()?;
+    fsm.reset_with_context(Context { value: 42, exits: 0 })?;
+    assert_eq!(FsmCurrentState::Stopped, fsm.get_current_states()[0]);
+    assert_eq!(0, fsm.exits);
+    let sub: &SubStateMachine = fsm.get_state();
+    assert_eq!(42, sub.get_context().value);
+
+    fsm.start()?;
+    let state: &StateA = fsm.get_state();
+    assert_eq!(1, state.entries);
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+extern crate finny;
+
+use finny::{FsmFactory, FsmResult, decl::{BuiltFsm, FsmBuilder}, finny_fsm};
+
+#[derive(Default)]
+pub struct Context;
+
+#[derive(Default)]
+pub struct StateA {
+    n: usize
+}
+#[derive(Default)]
+pub struct StateB {
+    n: usize
+}
+#[derive(Clone)]
+pub struct EventClick;
+#[derive(Clone)]
+pub struct EventReset;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, Context>) -> BuiltFsm {
+    fsm.initial_state::<StateA>();
+
+    fsm.state::<StateA>()
+        .reset_on_entry()
+        .on_entry(|state, _ctx| {
+            state.n += 1;
+        })
+        .on_event::<EventClick>()
+        .transition_to::<StateB>()
+        .action(|_ev, _ctx, from, to| {
+            to.n += from.n;
+        });
+
+    fsm.state::<StateA>()
+        .on_event::<EventReset>()
+        .self_transition();
+
+    fsm.state::<StateB>()
+        .on_entry(|state, _ctx| {
+            state.n += 1;
+        })
+        .on_event::<EventClick>()
+        .transition_to::<StateA>()
+        .action(|_ev, _ctx, _from, to| {
+            // the new instance is visible in the action
+            assert_eq!(0, to.n);
+        });
+
+    fsm.build()
+}
+
+#[test]
+fn test_reset_on_entry() -> FsmResult<()> {
+    let mut fsm = StateMachine::new(Context::default())?;
+
+    fsm.start()?;
+    let state: &StateA = fsm.get_state();
+    assert_eq!(1, state.n);
+
+    fsm.dispatch(EventReset)?;
+    let state: &StateA = fsm.get_state();
+    assert_eq!(1, state.n);
+
+    fsm.dispatch(EventClick)?;
+    fsm.dispatch(EventClick)?;
+    let state: &StateA = fsm.get_state();
+    assert_eq!(1, state.n);
+
+    // states without the option keep their data
+    fsm.dispatch(EventClick)?;
+    let state: &StateB = fsm.get_state();
+    assert_eq!(4, state.n);
+
+    Ok(())
+}
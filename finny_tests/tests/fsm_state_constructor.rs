@@ -0,0 +1,60 @@
+extern crate finny;
+
+use finny::{FsmFactory, FsmResult, decl::{BuiltFsm, FsmBuilder}, finny_fsm};
+
+pub struct Config {
+    buffer_size: usize,
+    sessions: usize
+}
+
+pub struct Buffering {
+    buffer: Vec<u8>,
+    session: usize
+}
+
+#[derive(Default)]
+pub struct Idle;
+#[derive(Clone)]
+pub struct Start;
+#[derive(Clone)]
+pub struct Stop;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, Config>) -> BuiltFsm {
+    fsm.initial_state::<Idle>();
+
+    fsm.state::<Idle>()
+        .on_event::<Start>()
+        .transition_to::<Buffering>()
+        .action(|_ev, ctx, _from, _to| {
+            ctx.sessions += 1;
+        });
+
+    fsm.state::<Buffering>()
+        .with_constructor(|ctx| Buffering { buffer: Vec::with_capacity(ctx.buffer_size), session: ctx.sessions })
+        .reset_on_entry()
+        .on_event::<Stop>()
+        .transition_to::<Idle>();
+
+    fsm.build()
+}
+
+#[test]
+fn test_state_constructor() -> FsmResult<()> {
+    let mut fsm = StateMachine::new(Config { buffer_size: 128, sessions: 0 })?;
+    let state: &Buffering = fsm.get_state();
+    assert!(state.buffer.capacity() >= 128);
+    assert_eq!(0, state.session);
+
+    fsm.start()?;
+    fsm.dispatch(Start)?;
+    fsm.dispatch(Stop)?;
+    fsm.dispatch(Start)?;
+
+    // the constructor is also used when the state is rebuilt on entry
+    let state: &Buffering = fsm.get_state();
+    assert_eq!(1, state.session);
+    assert_eq!(2, fsm.sessions);
+
+    Ok(())
+}
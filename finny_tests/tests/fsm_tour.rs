@@ -0,0 +1,268 @@
+extern crate finny;
+
+use finny::{FsmEventQueueSender, FsmFactory, FsmTransitionEventMeta, decl::{BuiltFsm, FsmBuilder}, finny_fsm, tour::{FsmTourEvent, FsmTourHarness, FsmTourTransition}};
+
+#[derive(Default)]
+pub struct Context {
+    armed: bool
+}
+
+#[derive(Default)]
+pub struct Idle;
+#[derive(Default)]
+pub struct Running;
+#[derive(Default)]
+pub struct Paused;
+#[derive(Default)]
+pub struct Broken;
+#[derive(Default)]
+pub struct LightOff;
+#[derive(Default)]
+pub struct LightOn;
+
+#[derive(Clone)]
+pub struct Go { speed: u32 }
+#[derive(Clone)]
+pub struct Pause;
+#[derive(Clone)]
+pub struct Tick;
+#[derive(Clone)]
+pub struct Smash;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<Machine, Context>) -> BuiltFsm {
+    fsm.region::<Motor>().initial_state::<Idle>();
+
+    fsm.state::<Idle>()
+        .on_event::<Go>()
+        .transition_to::<Running>()
+        .guard(|ev, _, _| ev.speed > 0);
+
+    fsm.state::<Running>()
+        .on_event::<Tick>()
+        .internal_transition()
+        .action(|_, ctx, _| {
+            ctx.queue.enqueue(Pause).unwrap();
+        });
+
+    fsm.state::<Running>()
+        .on_event::<Pause>()
+        .transition_to::<Paused>();
+
+    fsm.state::<Paused>()
+        .on_event::<Go>()
+        .transition_to::<Running>();
+
+    fsm.state::<Paused>()
+        .on_event::<Tick>()
+        .self_transition();
+
+    fsm.state::<Idle>()
+        .on_event::<Smash>()
+        .transition_to::<Broken>()
+        .guard(|_, ctx, _| ctx.context.armed);
+
+    fsm.state::<Paused>()
+        .on_event::<Smash>()
+        .transition_to::<Broken>()
+        .guard(|_, _, _| false);
+
+    fsm.state::<Broken>();
+
+    fsm.region::<Light>().initial_state::<LightOff>();
+
+    fsm.state::<LightOff>()
+        .on_event::<Go>()
+        .transition_to::<LightOn>();
+
+    fsm.state::<LightOn>()
+        .on_event::<Pause>()
+        .transition_to::<LightOff>();
+
+    fsm.build()
+}
+
+fn harness() -> FsmTourHarness<Machine, finny::FsmEventQueueVec<Machine>, finny::InspectNull> {
+    FsmTourHarness::new(|| Machine::new(Context::default()))
+        .event(|| Go { speed: 0 })
+        .event(|| Pause)
+        .event(|| Tick)
+        .event(|| Smash)
+        .guard_event(FsmTourTransition::new::<Idle, Go, Running>(), || Go { speed: 5 })
+        .guard_context(FsmTourTransition::new::<Idle, Smash, Broken>(), |ctx| ctx.armed = true)
+}
+
+fn never() -> FsmTourTransition {
+    FsmTourTransition::new::<Paused, Smash, Broken>()
+}
+
+#[test]
+fn test_tour_generation() {
+    let tour = harness().guard_never(never()).tour();
+
+    let covered: Vec<_> = tour.sequences.iter()
+        .flat_map(|s| s.steps.iter())
+        .flat_map(|s| s.transitions.iter().map(|t| t.name))
+        .collect();
+    for t in tour.meta.transitions.iter().filter(|t| t.name != "MachineTransition11") {
+        assert!(covered.contains(&t.name), "{} not covered by\n{}", t.name, tour);
+    }
+
+    assert_eq!(vec!["MachineTransition11"], tour.unreachable_transitions.iter().map(|t| t.name).collect::<Vec<_>>());
+    assert!(tour.unreachable_states.is_empty());
+
+    for sequence in &tour.sequences {
+        assert_eq!(FsmTourEvent::Start, sequence.steps[0].event);
+        assert!(sequence.steps[1..].iter().all(|s| s.event != FsmTourEvent::Start));
+    }
+
+    // the default event doesn't pass the guard, only the light region moves
+    let steps = &tour.sequences[0].steps;
+    assert_eq!(FsmTourEvent::Event("Go"), steps[1].event);
+    assert!(steps[1].target.is_none());
+    assert_eq!(vec![Some("Idle"), Some("LightOn")], steps[1].expected);
+
+    let guarded = steps.iter().find(|s| s.target.is_some()).unwrap();
+    assert_eq!("MachineTransition3", guarded.target.unwrap().name);
+    assert_eq!(FsmTransitionEventMeta::Event("Go"), guarded.target.unwrap().event);
+    assert_eq!(vec![Some("Running"), Some("LightOn")], guarded.expected);
+}
+
+#[test]
+fn test_tour_run() {
+    let tour = harness().guard_never(never()).run().unwrap();
+    assert_eq!(1, tour.unreachable_transitions.len());
+    assert_eq!(2, tour.sequences.len());
+}
+
+#[test]
+#[should_panic(expected = "Step 3 of the tour's sequence 1 failed: the dispatch failed with NoTransition")]
+fn test_tour_run_without_hint() {
+    FsmTourHarness::new(|| Machine::new(Context::default()))
+        .event(|| Go { speed: 0 })
+        .event(|| Pause)
+        .event(|| Tick)
+        .assert_tour();
+}
+
+#[test]
+#[should_panic(expected = "unreachable transition MachineTransition11")]
+fn test_tour_incomplete() {
+    harness().guard_never(never()).assert_tour();
+}
+
+#[derive(Default)]
+pub struct Shelf;
+#[derive(Default)]
+pub struct Floor;
+#[derive(Default)]
+pub struct Bin;
+
+#[derive(Clone)]
+pub struct Drop;
+#[derive(Clone)]
+pub struct PickUp;
+#[derive(Clone)]
+pub struct Empty;
+#[derive(Clone)]
+pub struct Kick;
+
+#[finny_fsm]
+fn build_fallback_fsm(mut fsm: FsmBuilder<Fallback, Context>) -> BuiltFsm {
+    fsm.initial_state::<Shelf>();
+
+    // once armed, a drop always breaks it, otherwise it lands on the floor
+    fsm.state::<Shelf>()
+        .on_event::<Drop>()
+        .transition_to::<Bin>()
+        .guard(|_, ctx, _| ctx.context.armed);
+
+    fsm.state::<Shelf>()
+        .on_event::<Drop>()
+        .transition_to::<Floor>();
+
+    fsm.state::<Floor>()
+        .on_event::<PickUp>()
+        .transition_to::<Shelf>();
+
+    fsm.state::<Floor>()
+        .on_event::<Kick>()
+        .internal_transition();
+
+    fsm.state::<Bin>()
+        .on_event::<PickUp>()
+        .transition_to::<Shelf>();
+
+    fsm.state::<Bin>()
+        .on_event::<Empty>()
+        .transition_to::<Floor>();
+
+    fsm.build()
+}
+
+#[test]
+fn test_tour_guard_with_fallback() {
+    let harness = FsmTourHarness::new(|| Fallback::new(Context::default()))
+        .event(|| Drop)
+        .event(|| PickUp)
+        .event(|| Empty)
+        .event(|| Kick)
+        .guard_context(FsmTourTransition::new::<Shelf, Drop, Bin>(), |ctx| ctx.armed = true);
+
+    // the armed context persists, the drops after it end in the bin instead of on the floor
+    let tour = harness.run().unwrap();
+    assert!(tour.unreachable_transitions.is_empty(), "{}", tour);
+    assert_eq!(1, tour.sequences.len());
+
+    let steps = &tour.sequences[0].steps;
+    let armed = steps.iter().position(|s| s.target.is_some()).unwrap();
+    let drops: Vec<_> = steps[armed + 1..].iter().filter(|s| s.event == FsmTourEvent::Event("Drop")).collect();
+    assert!(!drops.is_empty(), "{}", tour);
+    for drop in drops {
+        assert!(drop.target.is_none());
+        assert_eq!(vec![Some("Bin")], drop.expected);
+    }
+}
+
+
+#[derive(Default)]
+pub struct Counting;
+
+#[finny_fsm]
+fn build_counter_fsm(mut fsm: FsmBuilder<Counter, Context>) -> BuiltFsm {
+    fsm.initial_state::<Counting>();
+
+    fsm.state::<Counting>()
+        .on_event::<Tick>()
+        .internal_transition()
+        .guard(|_, ctx, _| ctx.context.armed);
+
+    fsm.state::<Counting>()
+        .on_event::<Tick>()
+        .internal_transition()
+        .guard(|_, _, _| false);
+
+    fsm.build()
+}
+
+#[test]
+#[should_panic(expected = "matches the guarded transitions CounterTransition2, CounterTransition3")]
+fn test_tour_ambiguous_hint() {
+    FsmTourHarness::new(|| Counter::new(Context::default()))
+        .event(|| Tick)
+        .guard_context(FsmTourTransition::new::<Counting, Tick, Counting>(), |ctx| ctx.armed = true);
+}
+
+#[test]
+fn test_tour_hint_by_transition_type() {
+    let tour = FsmTourHarness::new(|| Counter::new(Context::default()))
+        .event(|| Tick)
+        .guard_context(FsmTourTransition::of::<CounterTransition2>(), |ctx| ctx.armed = true)
+        .guard_never(FsmTourTransition::of::<CounterTransition3>())
+        .run()
+        .unwrap();
+
+    let targets: Vec<_> = tour.sequences.iter().flat_map(|s| s.steps.iter()).filter_map(|s| s.target).map(|t| t.name).collect();
+    assert_eq!(vec!["CounterTransition2"], targets);
+    assert_eq!(vec!["CounterTransition3"], tour.unreachable_transitions.iter().map(|t| t.name).collect::<Vec<_>>());
+}
@@ -0,0 +1,152 @@
+extern crate finny;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use finny::{FsmCurrentState, FsmFactory, FsmResult, decl::{BuiltFsm, FsmBuilder}, finny_fsm};
+
+static BUFFERS_DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Default)]
+pub struct Context {
+    received: usize
+}
+
+#[derive(Default)]
+pub struct Idle {
+    entries: usize
+}
+
+pub struct Receiving {
+    buffer: [u8; 1024],
+    len: usize
+}
+
+impl Default for Receiving {
+    fn default() -> Self {
+        Self { buffer: [0; 1024], len: 0 }
+    }
+}
+
+impl Drop for Receiving {
+    fn drop(&mut self) {
+        BUFFERS_DROPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+pub struct Sending {
+    buffer: [u8; 512]
+}
+
+impl Default for Sending {
+    fn default() -> Self {
+        Self { buffer: [0; 512] }
+    }
+}
+
+#[derive(Clone)]
+pub struct Begin;
+#[derive(Clone)]
+pub struct Data(u8);
+#[derive(Clone)]
+pub struct Restart;
+#[derive(Clone)]
+pub struct Send;
+#[derive(Clone)]
+pub struct Done;
+
+#[finny_fsm]
+fn build_fsm(mut fsm: FsmBuilder<StateMachine, Context>) -> BuiltFsm {
+    fsm.state_storage_union();
+    fsm.initial_state::<Idle>();
+
+    fsm.state::<Idle>()
+        .on_entry(|state, _ctx| {
+            state.entries += 1;
+        })
+        .on_event::<Begin>()
+        .transition_to::<Receiving>();
+
+    fsm.state::<Receiving>()
+        .on_event::<Data>()
+        .internal_transition()
+        .action(|ev, ctx, state| {
+            state.buffer[state.len] = ev.0;
+            state.len += 1;
+            ctx.received += 1;
+        });
+
+    fsm.state::<Receiving>()
+        .on_event::<Restart>()
+        .self_transition();
+
+    fsm.state::<Receiving>()
+        .on_event::<Send>()
+        .transition_to::<Sending>()
+        .action(|_ev, _ctx, from, to| {
+            to.buffer[..from.len].copy_from_slice(&from.buffer[..from.len]);
+        });
+
+    fsm.state::<Sending>()
+        .on_event::<Done>()
+        .transition_to::<Idle>();
+
+    fsm.build()
+}
+
+#[finny_fsm]
+fn build_field_fsm(mut fsm: FsmBuilder<FieldMachine, Context>) -> BuiltFsm {
+    fsm.initial_state::<Idle>();
+    fsm.state::<Idle>().on_event::<Begin>().transition_to::<Receiving>();
+    fsm.state::<Receiving>().on_event::<Send>().transition_to::<Sending>();
+    fsm.state::<Sending>().on_event::<Done>().transition_to::<Idle>();
+    fsm.build()
+}
+
+#[test]
+fn test_union_storage_size() {
+    use core::mem::size_of;
+
+    // only the largest state of the region takes space, the field storage keeps all of them
+    assert!(size_of::<StateMachineStates>() < size_of::<FieldMachineStates>());
+}
+
+#[test]
+fn test_union_storage() -> FsmResult<()> {
+
+    let mut fsm = StateMachine::new(Context::default())?;
+    assert!(fsm.try_get_state::<Idle>().is_none());
+
+    fsm.start()?;
+    assert_eq!(1, fsm.try_get_state::<Idle>().unwrap().entries);
+    assert!(fsm.try_get_state::<Receiving>().is_none());
+
+    fsm.dispatch(Begin)?;
+    assert!(fsm.try_get_state::<Idle>().is_none());
+    fsm.dispatch(Data(1))?;
+    fsm.dispatch(Data(2))?;
+    assert_eq!(2, fsm.try_get_state::<Receiving>().unwrap().len);
+
+    // a self transition constructs the state again
+    fsm.dispatch(Restart)?;
+    assert_eq!(1, BUFFERS_DROPPED.load(Ordering::SeqCst));
+    assert_eq!(0, fsm.try_get_state::<Receiving>().unwrap().len);
+
+    fsm.dispatch(Data(3))?;
+    fsm.dispatch(Data(4))?;
+    fsm.dispatch(Send)?;
+    assert_eq!(FsmCurrentState::State(StateMachineCurrentState::Sending), fsm.get_current_states()[0]);
+    assert_eq!(2, BUFFERS_DROPPED.load(Ordering::SeqCst));
+    assert_eq!(&[3, 4, 0], &fsm.try_get_state::<Sending>().unwrap().buffer[..3]);
+    assert_eq!(4, fsm.received);
+
+    // the data of the previous visit is gone
+    fsm.dispatch(Done)?;
+    assert_eq!(1, fsm.try_get_state::<Idle>().unwrap().entries);
+
+    fsm.dispatch(Begin)?;
+    fsm.stop()?;
+    assert_eq!(3, BUFFERS_DROPPED.load(Ordering::SeqCst));
+    assert!(fsm.try_get_state::<Receiving>().is_none());
+
+    Ok(())
+}